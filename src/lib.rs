@@ -5,6 +5,10 @@
 #![feature(alloc_error_handler)]
 #![feature(const_mut_refs)]
 #![feature(asm)]
+#![feature(custom_test_frameworks)]
+#![test_runner(crate::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+#![cfg_attr(test, no_main)]
 
 //! Almond OS - Library
 
@@ -14,6 +18,7 @@ use alloc::string::String;
 use bootloader::BootInfo;
 use sys::config::SystemConfig;
 use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
 use x86_64::instructions::hlt;
 use lazy_static::lazy_static;
 use spin::{Mutex, MutexGuard};
@@ -25,7 +30,10 @@ pub mod pci;
 
 pub use x86_64::instructions::interrupts::without_interrupts;
 
-use crate::{sys::storage::{ustar, mfs}};
+use crate::{sys::storage::{ustar, mfs}, sys::qemu::{exit_qemu, QemuExitCode}};
+
+#[cfg(test)]
+use bootloader::entry_point;
 
 /// The Kernel Result, Used To unify error-handling / reporting.
 pub type KResult<T> = core::result::Result<T, &'static str>;
@@ -42,8 +50,14 @@ pub fn boot(info: &'static BootInfo) {
     strict_initialize!(test_init);
     strict_initialize!(sys::interrupt::initialize);
     strict_initialize!(sys::timer::initialize);
+    strict_initialize!(sys::rtc::initialize);
     strict_initialize!(sys::input::initialize);
     strict_initialize!(sys::mem::initialize, info);
+    strict_initialize!(sys::timer::initialize_clock);
+    strict_initialize!(sys::interrupt::initialize_controller);
+    strict_initialize!(sys::thread::initialize);
+    strict_initialize!(sys::task::initialize);
+    strict_initialize!(sys::user::initialize);
     strict_initialize!(sys::storage::initialize);
 
     run!("mount HDB");
@@ -76,11 +90,60 @@ pub fn halt() -> ! {
 #[panic_handler]
 #[doc(hidden)]
 pub fn _panic(info: &PanicInfo) -> ! {
+    if TESTING.load(Ordering::SeqCst) {
+        test_panic_handler(info);
+    }
     print!("Panic: {}", info);
     serr!("Panic: {}", info);
     loop {}
 }
 
+/// Set By [test_runner], Makes A Panic Fail The Test Run Instead Of Hanging The Machine.
+static TESTING: AtomicBool = AtomicBool::new(false);
+
+/// A Single `#[test_case]`, Reports Its Name & Result Over Serial.
+pub trait Testable {
+    /// Run The Test.
+    fn run(&self);
+}
+
+impl<T: Fn()> Testable for T {
+    fn run(&self) {
+        sprint!("{}...\t", core::any::type_name::<T>());
+        self();
+        sprint!("\x1b[32m[ok]\x1b[39m\n");
+    }
+}
+
+/// Runs Every `#[test_case]` Then Exits QEMU With [QemuExitCode::Success].
+/// Expects [boot] To Have Been Called.
+pub fn test_runner(tests: &[&dyn Testable]) {
+    TESTING.store(true, Ordering::SeqCst);
+    sprint!("Running {} tests\n", tests.len());
+    for test in tests {
+        test.run();
+    }
+    exit_qemu(QemuExitCode::Success);
+}
+
+/// Reports The Failing Test Over Serial & Exits QEMU With [QemuExitCode::Failed].
+pub fn test_panic_handler(info: &PanicInfo) -> ! {
+    sprint!("\x1b[31m[failed]\x1b[39m\n\nError: {}\n", info);
+    exit_qemu(QemuExitCode::Failed);
+    halt();
+}
+
+#[cfg(test)]
+entry_point!(test_kernel_main);
+
+/// Entry Point For `cargo test --lib`.
+#[cfg(test)]
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    boot(boot_info);
+    test_main();
+    halt();
+}
+
 #[macro_export]
 /// Utility To Run Intitialize Functions & Report Status To The User. Uses [KResult]
 macro_rules! strict_initialize {
@@ -218,8 +281,9 @@ macro_rules! serr {
 
 }
 
-/// Wait For The Next Interrupt.
+/// Run Any Deferred Work, Then Wait For The Next Interrupt.
 pub fn spin() {
+    sys::workqueue::run_pending();
     hlt()
 }
 
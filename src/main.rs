@@ -1,6 +1,9 @@
 //! Almond Runtime
 #![no_std]
 #![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
 
 extern crate alloc;
 use almond_os::{print, pci, slog, sys::storage::mfs::{self, super_block::SuperBlock, api::FileIO, dir::Dir, dir_entry::DirEntry}};
@@ -11,6 +14,9 @@ entry_point!(kernel_main);
 
 fn kernel_main(boot_info: &'static BootInfo) -> ! {
     almond_os::boot(boot_info);
+    #[cfg(test)]
+    test_main();
+
     #[cfg(feature = "shell")]
     {
         almond_os::shell::main();
@@ -1,4 +1,13 @@
 //! AlmondOS Shell Program
+//! Programs Print To [STDOUT], So Their Output Follows `>`, `>>` & `|`.
+
+/// Print To [STDOUT], Shadowing The Crate's `print!` In The Shell & Its Programs.
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::shell::_print(format_args!($($arg)*))
+    };
+}
+
 mod sleep;
 mod debug;
 mod ls;
@@ -6,31 +15,46 @@ mod beep;
 mod env;
 mod clear;
 mod cat;
+mod echo;
 mod mount;
 mod hexdump;
 mod almond_vm;
 mod assembler;
 mod elf;
 mod texteditor;
+mod irqstat;
+mod date;
+mod free;
+mod process;
+mod run;
+mod threads;
 
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
+use crate::sys::fd::{self, OPEN_APPEND, OPEN_CREATE, STDIN, STDOUT};
 use crate::sys::{input, terminal};
 use crate::sys::vga::Color;
-use crate::{print, set_bg, set_fg, clear, globals};
+use crate::{set_bg, set_fg, clear, globals};
 
 use self::assembler::Assembler;
 use self::beep::Beep;
 use self::cat::Cat;
 use self::clear::ClearScreen;
+use self::date::Date;
+use self::echo::Echo;
 use self::debug::{Disassemble, RegisterDump, MemoryDump};
 use self::elf::ElfReader;
+use self::free::{Free, MemInfo};
 use self::hexdump::{HexDump, SectorDump};
+use self::irqstat::IrqStat;
 use self::ls::FileLister;
 use self::mount::Mount;
+use self::process::{Kill, Ps, Wait};
+use self::run::Run;
 use self::sleep::Sleep;
 use self::texteditor::TextEditor;
+use self::threads::Threads;
 
 /// Alias For The Arguments Of A Program
 pub type Args = Vec<String>;
@@ -41,14 +65,137 @@ pub trait Program {
     fn run(&mut self, args: Args) -> ShellExitCode;
 }
 
+#[doc(hidden)]
+pub fn _print(args: core::fmt::Arguments) {
+    // Nowhere Else To Report A Failure, Like Writing To A Closed Unix Pipe.
+    let _ = fd::write(STDOUT, alloc::fmt::format(args).as_bytes());
+}
+
 fn parse_cmd(cmd: String) -> Args {
     let v = cmd.split_whitespace().map(|s| {s.to_string()}).collect();
     v
 }
 
-/// Run The Given Command
+/// Run The Given Command.
+/// Commands Joined By `|` Run One After Another, Each Reading What The Last Wrote.
+/// `< file`, `> file` & `>> file` Point The Standard Descriptors At Files.
 pub fn run(cmd: &str) -> ShellExitCode {
-    let parts: Vec<String> = parse_cmd(cmd.into());
+    let stages: Vec<&str> = cmd.split('|').collect();
+    let mut input = None;
+    let mut code = ShellExitCode::Ok;
+    for (index, stage) in stages.iter().enumerate() {
+        let output = if index + 1 < stages.len() {
+            match fd::pipe() {
+                Ok(ends) => Some(ends),
+                Err(e) => {
+                    print!("Can't Create Pipe: {}\n", e);
+                    code = ShellExitCode::Failed;
+                    break;
+                }
+            }
+        } else {
+            None
+        };
+        let mut redirects = Vec::new();
+        if let Some(reader) = input {
+            redirects.push((STDIN, reader));
+        }
+        if let Some((_, writer)) = output {
+            redirects.push((STDOUT, writer));
+        }
+        code = run_redirected(stage, redirects);
+        // Closing The Write End Here Lets The Next Command See The End Of Its Input.
+        if let Some(reader) = input {
+            let _ = fd::close(reader);
+        }
+        if let Some((_, writer)) = output {
+            let _ = fd::close(writer);
+        }
+        input = output.map(|(reader, _)| reader);
+    }
+    if let Some(reader) = input {
+        let _ = fd::close(reader);
+    }
+    code
+}
+
+/// Split The `<`, `>` & `>>` Redirections Out Of A Command's Words.
+/// Returns The Remaining Words & Each Redirection's Descriptor, Path & Open Flags.
+fn parse_redirections(words: Args) -> Result<(Args, Vec<(usize, String, u64)>), String> {
+    let mut parts = Vec::new();
+    let mut redirections = Vec::new();
+    let mut words = words.into_iter();
+    while let Some(word) = words.next() {
+        let (target, flags) = match word.as_str() {
+            "<" => (STDIN, 0),
+            ">" => (STDOUT, OPEN_CREATE),
+            ">>" => (STDOUT, OPEN_CREATE | OPEN_APPEND),
+            _ => {
+                parts.push(word);
+                continue;
+            }
+        };
+        let path = words.next().ok_or_else(|| alloc::format!("Missing File After '{}'", word))?;
+        redirections.push((target, path, flags));
+    }
+    Ok((parts, redirections))
+}
+
+/// Run One Command With Each `(target, fd)` In `redirects` Opened On `target`,
+/// Followed By The Redirections Written In The Command. Everything Is Put Back After.
+fn run_redirected(cmd: &str, mut redirects: Vec<(usize, usize)>) -> ShellExitCode {
+    let (parts, redirections) = match parse_redirections(parse_cmd(cmd.into())) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            print!("{}\n", e);
+            return ShellExitCode::BadArguments;
+        }
+    };
+    let mut opened = Vec::new();
+    let mut code = None;
+    for (target, path, flags) in redirections {
+        match fd::open(&path, flags) {
+            Ok(file) => {
+                opened.push(file);
+                redirects.push((target, file));
+            }
+            Err(e) => {
+                print!("Can't Open '{}': {}\n", path, e);
+                code = Some(ShellExitCode::BadArguments);
+                break;
+            }
+        }
+    }
+
+    let mut saved = Vec::new();
+    if code.is_none() {
+        for &(target, file) in redirects.iter() {
+            saved.push((target, fd::dup(target).ok()));
+            if let Err(e) = fd::dup2(file, target) {
+                print!("Can't Redirect: {}\n", e);
+                code = Some(ShellExitCode::Failed);
+                break;
+            }
+        }
+    }
+    let code = code.unwrap_or_else(|| dispatch(parts));
+    for (target, previous) in saved.into_iter().rev() {
+        let _ = match previous {
+            Some(previous) => fd::dup2(previous, target).and_then(|_| fd::close(previous)),
+            None => fd::close(target),
+        };
+    }
+    for file in opened {
+        let _ = fd::close(file);
+    }
+    code
+}
+
+/// Run The Program Named By The First Word.
+fn dispatch(parts: Args) -> ShellExitCode {
+    if parts.is_empty() {
+        return ShellExitCode::Ok;
+    }
     let code = match parts[0].as_str() {
         "sleep" => Sleep.run( parts),
         "disassemble" | ":d" => Disassemble.run(parts),
@@ -58,15 +205,25 @@ pub fn run(cmd: &str) -> ShellExitCode {
         "clear" | "clr" | "cls" => {ClearScreen.run(parts)}
         "beep" => {Beep.run(parts)}
         "cat" => {Cat.run(parts)}
+        "echo" => {Echo.run(parts)}
         "mount" => {Mount.run(parts)}
         "hexdump" => {HexDump.run(parts)}
         "blkdump" | "blkd" => {SectorDump.run(parts)}
         "asm" => {Assembler::get(parts.clone()).run(parts)}
         "elf" => {ElfReader.run(parts)}
+        "irqstat" => {IrqStat.run(parts)}
+        "date" => {Date.run(parts)}
+        "free" => {Free.run(parts)}
+        "meminfo" => {MemInfo.run(parts)}
+        "threads" => {Threads.run(parts)}
+        "run" => {Run.run(parts)}
+        "ps" => {Ps.run(parts)}
+        "kill" => {Kill.run(parts)}
+        "wait" => {Wait.run(parts)}
 
         "ted" => {TextEditor::load_or_create(parts.clone()).run(parts)}
 
-        _ => { print!("Unknown Command: '{}'...\n", cmd); ShellExitCode::NoSuchProgram},
+        _ => { print!("Unknown Command: '{}'...\n", parts[0]); ShellExitCode::NoSuchProgram},
     };
 
     return code;
@@ -101,6 +258,8 @@ pub enum ShellExitCode {
     BadArguments = 1,
     /// The Program Attempted To Perform A Privledged Action, Without The Necessary Permissions.
     PrivledgeError = 2,
+    /// The Program Ran But Exited With A Non-Zero Status.
+    Failed = 3,
 
     /// The Program Was Not Found
     NoSuchProgram = 16,
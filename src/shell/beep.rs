@@ -1,4 +1,4 @@
-use crate::sys::{sound::pc_speaker, timer::sleep_ticks};
+use crate::sys::{sound::pc_speaker, timer::Duration};
 
 use super::Program;
 
@@ -8,11 +8,11 @@ impl Program for Beep {
     fn run(&mut self, args: super::Args) -> super::ShellExitCode {
 
         if args.len() >= 3 {
-
-            pc_speaker::set_pitch(args[1].parse().expect("Expected A Number."));
-            pc_speaker::play();
-            sleep_ticks(args[2].parse().expect("Expected An Integer."));
-            pc_speaker::stop();
+            let freq = args[1].parse().expect("Expected A Number.");
+            let duration = Duration::from_millis(args[2].parse().expect("Expected An Integer."));
+            if pc_speaker::beep(freq, duration).is_err() {
+                return super::ShellExitCode::BadArguments;
+            }
         } else {
             return super::ShellExitCode::BadArguments;
         }
@@ -1,24 +1,41 @@
-use crate::sys::storage::{ustar, mfs::file::File};
+use crate::sys::fd::{self, STDIN, STDOUT};
 
 use super::*;
 
+/// Copies A File, Or The Standard Input If None Is Given, To The Standard Output.
 pub struct Cat;
 
 impl Program for Cat {
     fn run(&mut self, args: Args) -> ShellExitCode {
-        if args.len() >= 2 {
-            let file = args[1].as_str();
-            let md = File::open(file);
-            if let Some(mut md) = md {
-                print!("{}\n", md.read_to_string());
-            } else {
-                print!("No Such File '{}'\n", file);
-                return ShellExitCode::BadArguments;
+        let (file, name) = match args.get(1) {
+            Some(path) => match fd::open(path, 0) {
+                Ok(file) => (file, path.as_str()),
+                Err(_) => {
+                    print!("No Such File '{}'\n", path);
+                    return ShellExitCode::BadArguments;
+                }
+            },
+            None => (STDIN, "Standard Input"),
+        };
+        let mut buffer = [0; 512];
+        let code = loop {
+            match fd::read(file, &mut buffer) {
+                Ok(0) => break ShellExitCode::Ok,
+                Ok(read) => {
+                    if let Err(e) = fd::write(STDOUT, &buffer[..read]) {
+                        print!("Can't Write Output: {}\n", e);
+                        break ShellExitCode::Failed;
+                    }
+                }
+                Err(e) => {
+                    print!("Can't Read '{}': {}\n", name, e);
+                    break ShellExitCode::Failed;
+                }
             }
-            return ShellExitCode::Ok;
-        } else {
-            print!("Usage: cat <input file>\n");
-            return ShellExitCode::BadArguments;
+        };
+        if file != STDIN {
+            let _ = fd::close(file);
         }
+        code
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,20 @@
+use crate::sys::rtc;
+
+use super::*;
+
+/// Prints The Current Date & Time, `date -u` Prints Unix Seconds Instead.
+pub struct Date;
+
+impl Program for Date {
+    fn run(&mut self, args: Args) -> ShellExitCode {
+        match args.get(1).map(String::as_str) {
+            None => print!("{} UTC\n", rtc::now()),
+            Some("-u") => print!("{}\n", rtc::realtime()),
+            Some(_) => {
+                print!("Usage: date [-u]\n");
+                return ShellExitCode::BadArguments;
+            }
+        }
+        ShellExitCode::Ok
+    }
+}
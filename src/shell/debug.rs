@@ -1,7 +1,6 @@
 //! Debugging Shell Commands
 use crate::shell::ShellExitCode;
 use crate::sys::debugger::{disassembler, self};
-use crate::print;
 
 use super::*;
 
@@ -0,0 +1,20 @@
+use crate::sys::fd::{self, STDOUT};
+
+use super::*;
+
+/// Writes Its Arguments To The Standard Output.
+pub struct Echo;
+
+impl Program for Echo {
+    fn run(&mut self, args: Args) -> ShellExitCode {
+        let mut line = args[1..].join(" ");
+        line.push('\n');
+        match fd::write(STDOUT, line.as_bytes()) {
+            Ok(_) => ShellExitCode::Ok,
+            Err(e) => {
+                print!("Can't Write Output: {}\n", e);
+                ShellExitCode::Failed
+            }
+        }
+    }
+}
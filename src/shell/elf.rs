@@ -1,33 +1,52 @@
-use elf_rs::{Elf, ElfFile};
-
-use crate::sys::storage::ustar;
+use crate::sys::loader::{self, elf::Elf};
 
 use super::*;
 
+/// Prints The Headers Of An ELF64 File Stored In MFS.
 pub struct ElfReader;
 
 impl Program for ElfReader {
     fn run(&mut self, args: Args) -> ShellExitCode {
-        let mut bytes = [0; 8192];
-        ustar::load_bytes(&args[1], &mut bytes);
-        let elf = Elf::from_bytes(&bytes).unwrap();
+        if args.len() < 2 {
+            print!("Usage: elf <file>\n");
+            return ShellExitCode::BadArguments;
+        }
+        let data = match loader::read_file(&args[1]) {
+            Ok(data) => data,
+            Err(e) => {
+                print!("Can't Read '{}': {}\n", args[1], e);
+                return ShellExitCode::BadArguments;
+            }
+        };
+        let elf = match Elf::parse(&data) {
+            Ok(elf) => elf,
+            Err(e) => {
+                print!("'{}': {}\n", args[1], e);
+                return ShellExitCode::BadArguments;
+            }
+        };
+
+        let header = elf.header();
+        print!("Type: {}, Entry: 0x{:08x}\n", header.kind_name(), header.entry);
         print!("Section Headers:\n");
-        for header in elf.section_header_iter() {
-            print!("\t0x{:08x} (Align {}): {} - {:?}\n",
-            header.addr(),
-            header.addralign(), 
-            String::from_utf8(header.section_name().to_vec()).expect("Failed To decode Name"),
-            header.flags());
+        for section in elf.section_headers() {
+            print!("\t0x{:08x} (Align {}): {} - {:#x}\n",
+            section.addr,
+            section.align,
+            elf.section_name(&section).unwrap_or("?"),
+            section.flags);
         }
         print!("Program Headers:\n");
-        for header in elf.program_header_iter() {
-            print!("\t0x{:08x} (Align {}): {} Bytes\n",
-            header.vaddr(),
-            header.align(),
-            header.content().len(),
+        for segment in elf.program_headers() {
+            print!("\t0x{:08x} (Align {}): {} {} Bytes ({} In File)\n",
+            segment.vaddr,
+            segment.align,
+            segment,
+            segment.memsz,
+            segment.filesz,
             );
         }
 
         return ShellExitCode::Ok;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,81 @@
+use crate::sys::mem::{self, frame_allocator};
+
+use super::*;
+
+const KIB: usize = 1024;
+const FRAME_KIB: usize = 4;
+
+/// Prints A Summary Of Heap & Physical Memory Usage, In KiB.
+pub struct Free;
+
+impl Program for Free {
+    fn run(&mut self, _args: Args) -> ShellExitCode {
+        let heap = mem::stats();
+        let frames = frame_allocator::stats();
+        print!("         |      Total |       Used |       Free\n");
+        print!("Heap     | {:>10} | {:>10} | {:>10}\n",
+            heap.heap_size / KIB,
+            heap.allocated / KIB,
+            heap.free / KIB);
+        print!("Physical | {:>10} | {:>10} | {:>10}\n",
+            frames.total * FRAME_KIB,
+            frames.used * FRAME_KIB,
+            frames.free * FRAME_KIB);
+        ShellExitCode::Ok
+    }
+}
+
+/// Prints Detailed Heap Statistics, `meminfo -l` Also Lists Live Allocations
+/// When Built With The `heap_trace` Feature.
+pub struct MemInfo;
+
+impl Program for MemInfo {
+    fn run(&mut self, args: Args) -> ShellExitCode {
+        let list = match args.get(1).map(String::as_str) {
+            None => false,
+            Some("-l") => true,
+            Some(_) => {
+                print!("Usage: meminfo [-l]\n");
+                return ShellExitCode::BadArguments;
+            }
+        };
+
+        let heap = mem::stats();
+        let frames = frame_allocator::stats();
+        print!("Heap Size:         {:>10} B\n", heap.heap_size);
+        print!("Heap Limit:        {:>10} B\n", mem::heap_limit());
+        print!("Allocated:         {:>10} B\n", heap.allocated);
+        print!("Peak Allocated:    {:>10} B\n", heap.peak);
+        print!("Free:              {:>10} B\n", heap.free);
+        print!("Largest Free:      {:>10} B\n", heap.largest_free);
+        print!("Fragmentation:     {:>10} %\n", heap.fragmentation);
+        print!("Live Allocations:  {:>10}\n", heap.allocations);
+        print!("Total Allocations: {:>10}\n", heap.total_allocations);
+        print!("Frames:            {:>10} Used / {} Total\n", frames.used, frames.total);
+
+        if list {
+            list_allocations();
+        }
+        ShellExitCode::Ok
+    }
+}
+
+#[cfg(feature = "heap_trace")]
+fn list_allocations() {
+    print!("     Address |     Size | Callers\n");
+    let untracked = mem::trace::for_each(|record| {
+        print!("{:>#12x} | {:>8} |", record.ptr, record.size);
+        for caller in record.callers.iter().take_while(|&&c| c != 0) {
+            print!(" {:#x}", caller);
+        }
+        print!("\n");
+    });
+    if untracked > 0 {
+        print!("{} Allocations Not Tracked, The Table Is Full\n", untracked);
+    }
+}
+
+#[cfg(not(feature = "heap_trace"))]
+fn list_allocations() {
+    print!("Allocation Tracking Needs The `heap_trace` Feature\n");
+}
@@ -0,0 +1,25 @@
+use crate::sys::{interrupt::{self, idt::IRQ_COUNT}, timer::{self, tsc}};
+
+use super::*;
+
+pub struct IrqStat;
+
+impl Program for IrqStat {
+    fn run(&mut self, _args: Args) -> ShellExitCode {
+        print!("Controller: {}, Tick Source: {:?}\n", interrupt::controller(), timer::tick_source());
+        print!("IRQ | Handlers |    Count | Unhandled | Spurious | Last Tick |    Time (us)\n");
+        for irq in 0..IRQ_COUNT {
+            if let Some(stats) = interrupt::irq_stats(irq) {
+                print!("{:>3} | {:>8} | {:>8} | {:>9} | {:>8} | {:>9} | {:>12}\n",
+                    irq,
+                    stats.handlers,
+                    stats.count,
+                    stats.unhandled,
+                    stats.spurious,
+                    stats.last_tick,
+                    tsc::cycles_to_nanos(stats.cycles) / 1000);
+            }
+        }
+        ShellExitCode::Ok
+    }
+}
@@ -0,0 +1,79 @@
+use crate::sys::process::{self, Pid, ProcessState};
+
+use super::*;
+
+/// Parse The PID Argument Shared By `kill` & `wait`.
+fn pid_argument(args: &Args, usage: &str) -> Option<Pid> {
+    match args.get(1).map(|arg| arg.parse::<u64>()) {
+        Some(Ok(pid)) => Some(Pid(pid)),
+        _ => {
+            print!("Usage: {} <pid>\n", usage);
+            None
+        }
+    }
+}
+
+/// Lists Every Process, Including Zombies Waiting To Be Collected.
+pub struct Ps;
+
+impl Program for Ps {
+    fn run(&mut self, _args: Args) -> ShellExitCode {
+        print!("  PID | PPID | Thread | State      | Name\n");
+        for info in process::list() {
+            let state = match info.state {
+                ProcessState::Running if info.killed => String::from("Killed"),
+                ProcessState::Running => String::from("Running"),
+                ProcessState::Zombie(code) => alloc::format!("Zombie {}", code),
+            };
+            let thread = info.thread.map_or(String::from("-"), |id| id.0.to_string());
+            print!("{:>5} | {:>4} | {:>6} | {:<10} | {}\n",
+                info.pid,
+                info.parent,
+                thread,
+                state,
+                info.name);
+        }
+        ShellExitCode::Ok
+    }
+}
+
+/// Kills A Running Process.
+pub struct Kill;
+
+impl Program for Kill {
+    fn run(&mut self, args: Args) -> ShellExitCode {
+        let pid = match pid_argument(&args, "kill") {
+            Some(pid) => pid,
+            None => return ShellExitCode::BadArguments,
+        };
+        match process::kill(pid) {
+            Ok(()) => ShellExitCode::Ok,
+            Err(e) => {
+                print!("Can't Kill {}: {}\n", pid, e);
+                ShellExitCode::BadArguments
+            }
+        }
+    }
+}
+
+/// Waits For A Background Process To Exit & Prints Its Exit Code.
+pub struct Wait;
+
+impl Program for Wait {
+    fn run(&mut self, args: Args) -> ShellExitCode {
+        let pid = match pid_argument(&args, "wait") {
+            Some(pid) => pid,
+            None => return ShellExitCode::BadArguments,
+        };
+        match process::wait(pid) {
+            Ok(code) => {
+                print!("[{}] Exited With Code {}\n", pid, code);
+                if code == 0 { ShellExitCode::Ok } else { ShellExitCode::Failed }
+            }
+            Err(e) => {
+                print!("Can't Wait For {}: {}\n", pid, e);
+                ShellExitCode::BadArguments
+            }
+        }
+    }
+}
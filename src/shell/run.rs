@@ -0,0 +1,58 @@
+use crate::sys::{loader, process};
+
+use super::*;
+
+/// Runs A Program From MFS In User Mode & Reports How It Exited.
+/// Ending The Command With `&` Runs It In The Background, Collect It With `wait`.
+pub struct Run;
+
+impl Program for Run {
+    fn run(&mut self, mut args: Args) -> ShellExitCode {
+        let background = args.last().map_or(false, |arg| arg == "&");
+        if background {
+            args.pop();
+        }
+        if args.len() < 2 {
+            print!("Usage: run <file> [args] [&]\n");
+            return ShellExitCode::BadArguments;
+        }
+        let data = match loader::read_file(&args[1]) {
+            Ok(data) => data,
+            Err(e) => {
+                print!("Can't Read '{}': {}\n", args[1], e);
+                return ShellExitCode::NoSuchProgram;
+            }
+        };
+        let argv: Vec<&str> = args[1..].iter().map(String::as_str).collect();
+        let image = match loader::load_bytes(&data, &argv, &[]) {
+            Ok(image) => image,
+            Err(e) => {
+                print!("Can't Load '{}': {}\n", args[1], e);
+                return ShellExitCode::BadArguments;
+            }
+        };
+        if background {
+            return match process::spawn(image) {
+                Ok(pid) => {
+                    print!("[{}] Started '{}'\n", pid, args[1]);
+                    ShellExitCode::Ok
+                }
+                Err(e) => {
+                    print!("Can't Run '{}': {}\n", args[1], e);
+                    ShellExitCode::Failed
+                }
+            };
+        }
+        match image.run() {
+            Ok(0) => ShellExitCode::Ok,
+            Ok(code) => {
+                print!("'{}' Exited With Code {}\n", args[1], code);
+                ShellExitCode::Failed
+            }
+            Err(e) => {
+                print!("Can't Run '{}': {}\n", args[1], e);
+                ShellExitCode::Failed
+            }
+        }
+    }
+}
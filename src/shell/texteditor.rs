@@ -1,29 +1,41 @@
 use alloc::format;
 
-use crate::sys::{storage::mfs::{self, file::{File, SeekFrom}, api::FileIO}, timer, input::{DELETE, BACKSPACE}};
+use crate::sys::{fd, storage::mfs::file::SeekFrom, task, input::{DELETE, BACKSPACE}};
+use crate::KResult;
 
 use super::*;
 
 pub struct TextEditor {
     text: String, 
-    file: File,
+    name: String,
+    file: usize,
 }
 
 impl TextEditor {
     pub fn load_or_create(args: Args) -> TextEditor {
-        let mut text: String = String::new();
-        let mut file: Option<File> = None; 
-
         let path = args.get(1).unwrap();
+        let file = fd::open(path, fd::OPEN_CREATE).unwrap();
 
-        if let Some(mut fle) = mfs::open_file(&path) {
-            text = fle.read_to_string();
-            file = Some(fle);
-        } else {
-            file = mfs::create_file(&path);
+        let mut data = Vec::new();
+        let mut buffer = [0; 512];
+        while let Ok(read) = fd::read(file, &mut buffer) {
+            if read == 0 {
+                break;
+            }
+            data.extend_from_slice(&buffer[..read]);
         }
+        let text = String::from_utf8_lossy(&data).to_string();
+
+        Self {file, name: path.clone(), text}
+    }
 
-        Self {file: file.unwrap(), text}
+    /// Replace The File's Contents With The Text.
+    fn save(&self) -> KResult<()> {
+        let descriptor = fd::get(self.file)?;
+        let mut resource = descriptor.lock();
+        resource.seek(SeekFrom::Start(0))?;
+        resource.write(self.text.as_bytes())?;
+        Ok(())
     }
 
 }
@@ -32,31 +44,25 @@ impl Program for TextEditor {
     fn run(&mut self, args: Args) -> ShellExitCode {
         clear!(Color::Blue, Color::White);
         terminal::home();
-        terminal::put_string(0, 0, &format!("TED - {}", self.file.name()), (Color::Blue, Color::White));
+        terminal::put_string(0, 0, &format!("TED - {}", self.name), (Color::Blue, Color::White));
         terminal::put_string(0,1, &self.text, (Color::White, Color::Blue));
         while(true) {
-            if let Some(chr) = input::read_key() {
-                match chr {
-                    BACKSPACE => {self.text.pop();}
-                    DELETE => {self.file.seek(SeekFrom::Start(0));self.file.write(self.text.as_bytes());  break;}
-                    _ => {self.text.push(chr)}
-                }
-                clear!(Color::Blue, Color::White);
-                terminal::home();
-                terminal::put_string(0, 0, &format!("TED - {}", self.file.name()), (Color::Blue, Color::White));
-                terminal::put_string(0,1, &self.text, (Color::White, Color::Blue));
-
-
-                terminal::put_string(0, 24, "Press DEL To Exit...", (Color::Blue, Color::White));
-
-            } else {
-                timer::sleep_ticks(16);
+            let chr = task::block_on(input::next_key());
+            match chr {
+                BACKSPACE => {self.text.pop();}
+                DELETE => {let _ = self.save(); break;}
+                _ => {self.text.push(chr)}
             }
+            clear!(Color::Blue, Color::White);
+            terminal::home();
+            terminal::put_string(0, 0, &format!("TED - {}", self.name), (Color::Blue, Color::White));
+            terminal::put_string(0,1, &self.text, (Color::White, Color::Blue));
+
 
-            
-            
+            terminal::put_string(0, 24, "Press DEL To Exit...", (Color::Blue, Color::White));
         }
 
+        let _ = fd::close(self.file);
         ShellExitCode::Ok
     }
 }
@@ -0,0 +1,34 @@
+use crate::sys::{thread::{self, ThreadState}, timer};
+
+use super::*;
+
+/// Lists Every Kernel Thread & Its State.
+pub struct Threads;
+
+impl Program for Threads {
+    fn run(&mut self, _args: Args) -> ShellExitCode {
+        let current = thread::current().map(|(id, _)| id);
+        print!("  ID | Name             | State          | Switches\n");
+        for info in thread::list() {
+            let state = match info.state {
+                ThreadState::Ready => String::from("Ready"),
+                ThreadState::Running => String::from("Running"),
+                ThreadState::Sleeping(until) => {
+                    alloc::format!("Sleeping {}ms", until.saturating_sub(timer::ticks()))
+                }
+                ThreadState::Blocked(until) => {
+                    alloc::format!("Blocked {}ms", until.saturating_sub(timer::ticks()))
+                }
+                ThreadState::Joining(id) => alloc::format!("Joining {}", id.0),
+                ThreadState::Exited => String::from("Exited"),
+            };
+            print!("{}{:>3} | {:<16} | {:<14} | {:>8}\n",
+                if Some(info.id) == current { "*" } else { " " },
+                info.id.0,
+                info.name,
+                state,
+                info.switches);
+        }
+        ShellExitCode::Ok
+    }
+}
@@ -2,6 +2,8 @@
 
 
 use alloc::string::String;
+pub mod acpi;
+pub mod fd;
 pub mod interrupt;
 pub mod mem;
 pub mod serial;
@@ -12,7 +14,15 @@ pub mod timer;
 pub mod vga;
 pub mod debugger;
 pub mod input;
+pub mod loader;
 pub mod config;
+pub mod qemu;
+pub mod rtc;
+pub mod task;
+pub mod thread;
+pub mod process;
+pub mod user;
+pub mod workqueue;
 
 static mut current_dir: String = String::new();
 
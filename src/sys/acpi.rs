@@ -0,0 +1,191 @@
+//! Minimal ACPI Table Discovery.
+//! Finds The RSDP, Walks The RSDT/XSDT & Parses The Tables The Kernel Needs.
+//! <https://wiki.osdev.org/RSDP>
+
+use alloc::vec::Vec;
+use core::ptr::read_unaligned;
+use x86_64::PhysAddr;
+
+use super::mem::mapper::physical_memory_offset;
+
+const RSDP_SIGNATURE: &[u8; 8] = b"RSD PTR ";
+/// The Real-Mode Segment Of The EBDA Is Stored At This Physical Address.
+const EBDA_POINTER: u64 = 0x40E;
+const BIOS_AREA_START: u64 = 0xE0000;
+const BIOS_AREA_END: u64 = 0x100000;
+/// The Size Of The Common System Description Table Header.
+const SDT_HEADER_SIZE: u64 = 36;
+
+/// Read A Value From Physical Memory Through The Physical Memory Mapping.
+fn read_phys<T: Copy>(addr: u64) -> T {
+    unsafe { read_unaligned((addr + physical_memory_offset()) as *const T) }
+}
+
+/// Returns True If The Bytes At `addr..addr + len` Sum To Zero.
+fn checksum(addr: u64, len: u64) -> bool {
+    (0..len).fold(0u8, |sum, i| sum.wrapping_add(read_phys::<u8>(addr + i))) == 0
+}
+
+fn find_rsdp_in(start: u64, end: u64) -> Option<u64> {
+    (start..end)
+        .step_by(16)
+        .find(|&addr| &read_phys::<[u8; 8]>(addr) == RSDP_SIGNATURE && checksum(addr, 20))
+}
+
+/// Search The EBDA & The BIOS Area For The RSDP.
+fn find_rsdp() -> Option<u64> {
+    let ebda = (read_phys::<u16>(EBDA_POINTER) as u64) << 4;
+    if ebda != 0 {
+        if let Some(rsdp) = find_rsdp_in(ebda, ebda + 1024) {
+            return Some(rsdp);
+        }
+    }
+    find_rsdp_in(BIOS_AREA_START, BIOS_AREA_END)
+}
+
+/// Returns The Physical Addresses Of Every Table Listed In The RSDT/XSDT.
+fn tables() -> Vec<u64> {
+    let rsdp = match find_rsdp() {
+        Some(rsdp) => rsdp,
+        None => return Vec::new(),
+    };
+
+    // Revision 2+ Provides The 64-Bit XSDT.
+    let (root, entry_size) = if read_phys::<u8>(rsdp + 15) >= 2 {
+        (read_phys::<u64>(rsdp + 24), 8)
+    } else {
+        (read_phys::<u32>(rsdp + 16) as u64, 4)
+    };
+
+    let len = read_phys::<u32>(root + 4) as u64;
+    if !checksum(root, len) {
+        return Vec::new();
+    }
+
+    (root + SDT_HEADER_SIZE..root + len)
+        .step_by(entry_size)
+        .map(|entry| {
+            if entry_size == 8 {
+                read_phys::<u64>(entry)
+            } else {
+                read_phys::<u32>(entry) as u64
+            }
+        })
+        .collect()
+}
+
+/// Find A Table By Its Signature, i.e. "APIC" Or "HPET".
+/// Returns The Physical Address Of The Table's Header.
+pub fn find_table(signature: &[u8; 4]) -> Option<PhysAddr> {
+    tables()
+        .into_iter()
+        .find(|&table| &read_phys::<[u8; 4]>(table) == signature && checksum(table, read_phys::<u32>(table + 4) as u64))
+        .map(PhysAddr::new)
+}
+
+/// Locate The HPET Table & Return The Physical Address Of The HPET's Registers.
+pub fn hpet_address() -> Option<u64> {
+    let table = find_table(b"HPET")?.as_u64();
+    // The Generic Address Structure Follows The Event Timer Block ID,
+    // Its 64-Bit Address Is 4 Bytes In.
+    Some(read_phys::<u64>(table + SDT_HEADER_SIZE + 8))
+}
+
+/// An IO-APIC Listed In The MADT.
+#[derive(Debug, Clone, Copy)]
+pub struct IoApicInfo {
+    /// The IO-APIC's ID.
+    pub id: u8,
+    /// The Physical Address Of Its Registers.
+    pub address: u64,
+    /// The First Global System Interrupt It Handles.
+    pub gsi_base: u32,
+}
+
+/// Remaps A Legacy ISA IRQ Onto A Different Global System Interrupt.
+#[derive(Debug, Clone, Copy)]
+pub struct InterruptOverride {
+    /// The Legacy IRQ.
+    pub irq: u8,
+    /// The Global System Interrupt It Is Wired To.
+    pub gsi: u32,
+    /// MPS INTI Flags, Bits 0:1 Polarity, Bits 2:3 Trigger Mode.
+    pub flags: u16,
+}
+
+impl InterruptOverride {
+    /// Returns True If The Line Is Active Low.
+    pub fn active_low(&self) -> bool {
+        self.flags & 0b11 == 0b11
+    }
+
+    /// Returns True If The Line Is Level Triggered.
+    pub fn level_triggered(&self) -> bool {
+        (self.flags >> 2) & 0b11 == 0b11
+    }
+}
+
+/// The Multiple APIC Description Table.
+#[derive(Debug, Clone)]
+pub struct Madt {
+    /// The Physical Address Of The Local APIC.
+    pub local_apic_address: u64,
+    /// The APIC IDs Of Every Processor.
+    pub local_apics: Vec<u8>,
+    /// Every IO-APIC In The System.
+    pub io_apics: Vec<IoApicInfo>,
+    /// Legacy IRQs That Are Not Identity Mapped To GSIs.
+    pub overrides: Vec<InterruptOverride>,
+}
+
+impl Madt {
+    /// Returns The Override For The Given Legacy IRQ, If Any.
+    pub fn override_for(&self, irq: u8) -> Option<&InterruptOverride> {
+        self.overrides.iter().find(|o| o.irq == irq)
+    }
+}
+
+/// Locate & Parse The MADT.
+pub fn madt() -> Option<Madt> {
+    let table = find_table(b"APIC")?.as_u64();
+    let len = read_phys::<u32>(table + 4) as u64;
+
+    let mut madt = Madt {
+        local_apic_address: read_phys::<u32>(table + SDT_HEADER_SIZE) as u64,
+        local_apics: Vec::new(),
+        io_apics: Vec::new(),
+        overrides: Vec::new(),
+    };
+
+    // Entries Start After The Header, The LAPIC Address & The Flags.
+    let mut entry = table + SDT_HEADER_SIZE + 8;
+    while entry + 2 <= table + len {
+        let kind = read_phys::<u8>(entry);
+        let entry_len = read_phys::<u8>(entry + 1) as u64;
+        if entry_len < 2 {
+            break;
+        }
+        match kind {
+            // Processor Local APIC, Only Enabled Processors.
+            0 if read_phys::<u32>(entry + 4) & 1 == 1 => {
+                madt.local_apics.push(read_phys::<u8>(entry + 3));
+            }
+            1 => madt.io_apics.push(IoApicInfo {
+                id: read_phys::<u8>(entry + 2),
+                address: read_phys::<u32>(entry + 4) as u64,
+                gsi_base: read_phys::<u32>(entry + 8),
+            }),
+            2 => madt.overrides.push(InterruptOverride {
+                irq: read_phys::<u8>(entry + 3),
+                gsi: read_phys::<u32>(entry + 4),
+                flags: read_phys::<u16>(entry + 8),
+            }),
+            // Local APIC Address Override.
+            5 => madt.local_apic_address = read_phys::<u64>(entry + 4),
+            _ => {}
+        }
+        entry += entry_len;
+    }
+
+    Some(madt)
+}
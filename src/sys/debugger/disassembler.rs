@@ -1,14 +1,22 @@
 //! x64 Disassembler
 use alloc::{format, string::String, vec};
+use core::fmt;
 use iced_x86::*;
 
 use crate::{sys::mem::mapper::physical_memory_offset};
 
+/// The Longest Possible x86 Instruction.
+pub const MAX_INSTRUCTION_LEN: usize = 15;
+
 /// Disassemble x86 Instructions Into The NASM Format.
+/// `addr` Is A Physical Address.
 pub fn disassemble(addr: usize, len: usize) -> String {
+    disassemble_virt(addr + physical_memory_offset() as usize, len)
+}
 
-    let addr = addr + physical_memory_offset() as usize;
-
+/// Disassemble x86 Instructions Into The NASM Format.
+/// `addr` Is A Virtual Address, The Caller Must Make Sure `addr..addr + len` Is Mapped.
+pub fn disassemble_virt(addr: usize, len: usize) -> String {
     let mut bytes = vec![0; len];
     for idx in 0..len {
         let ptr = addr as *const u8;
@@ -38,4 +46,20 @@ pub fn disassemble(addr: usize, len: usize) -> String {
     }
 
     output
-}
\ No newline at end of file
+}
+
+/// Write The Address, Mnemonic & Bytes Of The Instruction At `addr` Without Allocating,
+/// For Fault Handlers. `addr` Is A Virtual Address, `addr..addr + MAX_INSTRUCTION_LEN` Must Be Mapped.
+pub fn write_instruction(addr: usize, out: &mut impl fmt::Write) -> fmt::Result {
+    let mut bytes = [0; MAX_INSTRUCTION_LEN];
+    for (idx, byte) in bytes.iter_mut().enumerate() {
+        *byte = unsafe { (addr as *const u8).add(idx).read() };
+    }
+    let mut decoder = Decoder::with_ip(64, &bytes, addr as u64, DecoderOptions::NONE);
+    let instruction = decoder.decode();
+    write!(out, "0x{:08X} | {:?} | ", addr, instruction.mnemonic())?;
+    for b in &bytes[..instruction.len().min(MAX_INSTRUCTION_LEN)] {
+        write!(out, "{:02x} ", b)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,342 @@
+//! File Descriptors.
+//! A [Descriptor] Is A Shared Handle To Something That Can Be Read Or Written:
+//! The Keyboard, The Terminal, An MFS File Or One End Of A [pipe]. Every Process
+//! Has Its Own [FdTable], Outside User Programs The Kernel's Is Used, Which Is
+//! Where The Shell's Standard Streams Live. Duplicated Descriptors & Those A
+//! Child Inherits Share One Resource, Including Its File Offset, Like Unix.
+//!
+//! The Functions Here Work On The Current Process's Table, See [process::with_files].
+//! Reads That Block Wait With The Descriptor Unlocked, See [read_descriptor].
+
+use alloc::{collections::VecDeque, string::String, sync::Arc, vec::Vec};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use spin::Mutex;
+
+use super::process;
+use super::storage::mfs::{self, api::FileIO, file::{File, SeekFrom}};
+use super::{input, task::{self, WakerCell}};
+use crate::{eprint, print, KResult};
+
+/// The Standard Descriptors, Bound To The Keyboard & Terminal In A Fresh [FdTable].
+pub const STDIN: usize = 0;
+pub const STDOUT: usize = 1;
+pub const STDERR: usize = 2;
+/// The Most Descriptors A Process Can Have Open.
+pub const MAX_DESCRIPTORS: usize = 64;
+
+/// [open] Flag, Create The File If It Doesn't Exist.
+pub const OPEN_CREATE: u64 = 1;
+/// [open] Flag, Start At The End Of The File.
+pub const OPEN_APPEND: u64 = 2;
+
+/// The Buffer Between The Two Ends Of A [pipe].
+/// It Never Fills, So A Pipeline Can Run One Command After Another.
+#[derive(Debug, Default)]
+pub struct Pipe {
+    buffer: VecDeque<u8>,
+    /// The Write End Was Closed, Reads Return End Of File Once The Buffer Is Empty.
+    closed: bool,
+    /// Woken When Data Arrives Or The Write End Is Closed.
+    reader: WakerCell,
+}
+
+/// Resolves Once A [Pipe] Has Data Or Its Write End Was Closed.
+struct PipeReady<'a>(&'a Mutex<Pipe>);
+
+impl Future for PipeReady<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let pipe = self.0.lock();
+        if !pipe.buffer.is_empty() || pipe.closed {
+            return Poll::Ready(());
+        }
+        // Registered Under The Pipe's Lock, So A Write Can't Slip In Unnoticed.
+        pipe.reader.register(cx.waker());
+        Poll::Pending
+    }
+}
+
+/// What A Read That Can't Complete Yet Waits For.
+#[derive(Debug)]
+pub enum Blocked {
+    /// A Line Typed On The Keyboard.
+    Keyboard,
+    /// Data In, Or The Closing Of, A [pipe].
+    Pipe(Arc<Mutex<Pipe>>),
+}
+
+impl Blocked {
+    /// Wait Until Reading `descriptor` Can Make Progress, Which Must Not Be Locked.
+    fn wait(self, descriptor: &Descriptor) {
+        match self {
+            Blocked::Keyboard => {
+                let line = task::block_on(input::read_line(""));
+                if let Resource::Keyboard(pending) = &mut *descriptor.lock() {
+                    pending.extend(line.bytes());
+                    pending.push_back(b'\n');
+                }
+            }
+            Blocked::Pipe(pipe) => task::block_on(PipeReady(&pipe)),
+        }
+    }
+}
+
+/// What A Descriptor Refers To.
+#[derive(Debug)]
+pub enum Resource {
+    /// Line Buffered Keyboard Input, Holding What Was Typed But Not Yet Read.
+    Keyboard(VecDeque<u8>),
+    /// The Terminal's Output.
+    Terminal,
+    /// The Terminal's Error Output.
+    TerminalError,
+    /// A File In MFS.
+    File(File),
+    /// The Read End Of A [pipe].
+    PipeReader(Arc<Mutex<Pipe>>),
+    /// The Write End Of A [pipe].
+    PipeWriter(Arc<Mutex<Pipe>>),
+}
+
+impl Resource {
+    /// Returns True If [read](Resource::read) Can Succeed.
+    pub fn is_readable(&self) -> bool {
+        matches!(self, Resource::Keyboard(_) | Resource::File(_) | Resource::PipeReader(_))
+    }
+
+    /// Returns True If [write](Resource::write) Can Succeed.
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Resource::Terminal | Resource::TerminalError | Resource::File(_) | Resource::PipeWriter(_))
+    }
+
+    /// Read Into `buffer` Without Blocking, Returns The Number Of Bytes Read, Zero At The End Of The File.
+    /// The Keyboard Waits For A Whole Line & Pipes Until Data Arrives Or The Writer Closes,
+    /// Returned As [Blocked] So The Caller Can Wait Without Holding The Descriptor.
+    pub fn try_read(&mut self, buffer: &mut [u8]) -> KResult<Result<usize, Blocked>> {
+        match self {
+            Resource::Keyboard(pending) => {
+                if pending.is_empty() {
+                    return Ok(Err(Blocked::Keyboard));
+                }
+                let mut count = 0;
+                while count < buffer.len() {
+                    match pending.pop_front() {
+                        Some(byte) => {
+                            buffer[count] = byte;
+                            count += 1;
+                            if byte == b'\n' {
+                                break;
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                Ok(Ok(count))
+            }
+            Resource::File(file) => file.read(buffer).map(Ok).map_err(|_| "Failed To Read File"),
+            Resource::PipeReader(pipe) => {
+                let mut locked = pipe.lock();
+                if locked.buffer.is_empty() && !locked.closed {
+                    return Ok(Err(Blocked::Pipe(pipe.clone())));
+                }
+                let count = buffer.len().min(locked.buffer.len());
+                for (byte, value) in buffer.iter_mut().zip(locked.buffer.drain(..count)) {
+                    *byte = value;
+                }
+                Ok(Ok(count))
+            }
+            _ => Err("Not Open For Reading"),
+        }
+    }
+
+    /// Write `data`, Returns The Number Of Bytes Written.
+    pub fn write(&mut self, data: &[u8]) -> KResult<usize> {
+        match self {
+            Resource::Terminal => print!("{}", String::from_utf8_lossy(data)),
+            Resource::TerminalError => eprint!("{}", String::from_utf8_lossy(data)),
+            Resource::File(file) => return file.write(data).map_err(|_| "Failed To Write File"),
+            Resource::PipeWriter(pipe) => {
+                let mut pipe = pipe.lock();
+                pipe.buffer.extend(data.iter().copied());
+                pipe.reader.wake();
+            }
+            _ => return Err("Not Open For Writing"),
+        }
+        Ok(data.len())
+    }
+
+    /// Move A File's Offset, Returns The New Offset.
+    pub fn seek(&mut self, position: SeekFrom) -> KResult<u32> {
+        match self {
+            Resource::File(file) => file.seek(position).map_err(|_| "Invalid Offset"),
+            _ => Err("Not Seekable"),
+        }
+    }
+}
+
+impl Drop for Resource {
+    fn drop(&mut self) {
+        if let Resource::PipeWriter(pipe) = self {
+            let mut pipe = pipe.lock();
+            pipe.closed = true;
+            pipe.reader.wake();
+        }
+    }
+}
+
+/// A Shared Handle To A [Resource].
+pub type Descriptor = Arc<Mutex<Resource>>;
+
+/// A Process's Open Descriptors, Indexed By Number.
+#[derive(Debug, Clone, Default)]
+pub struct FdTable {
+    descriptors: Vec<Option<Descriptor>>,
+}
+
+impl FdTable {
+    /// A Table With Nothing Open.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A Table With [STDIN], [STDOUT] & [STDERR] Bound To The Keyboard & Terminal.
+    pub fn standard() -> Self {
+        let mut table = Self::new();
+        for resource in [Resource::Keyboard(VecDeque::new()), Resource::Terminal, Resource::TerminalError] {
+            table.descriptors.push(Some(Arc::new(Mutex::new(resource))));
+        }
+        table
+    }
+
+    /// The Descriptor Open On `fd`.
+    pub fn get(&self, fd: usize) -> Option<Descriptor> {
+        self.descriptors.get(fd)?.clone()
+    }
+
+    /// Add `descriptor` On The Lowest Free Number, Returns It.
+    pub fn insert(&mut self, descriptor: Descriptor) -> KResult<usize> {
+        let fd = match self.descriptors.iter().position(Option::is_none) {
+            Some(fd) => fd,
+            None if self.descriptors.len() < MAX_DESCRIPTORS => {
+                self.descriptors.push(None);
+                self.descriptors.len() - 1
+            }
+            None => return Err("Too Many Open Files"),
+        };
+        self.descriptors[fd] = Some(descriptor);
+        Ok(fd)
+    }
+
+    /// Open `resource` On The Lowest Free Number, Returns It.
+    pub fn open(&mut self, resource: Resource) -> KResult<usize> {
+        self.insert(Arc::new(Mutex::new(resource)))
+    }
+
+    /// Open `fd`'s Descriptor Again On The Lowest Free Number.
+    pub fn dup(&mut self, fd: usize) -> KResult<usize> {
+        let descriptor = self.get(fd).ok_or("Bad File Descriptor")?;
+        self.insert(descriptor)
+    }
+
+    /// Open `old`'s Descriptor On `new`, Closing Whatever Was Open There First.
+    pub fn dup2(&mut self, old: usize, new: usize) -> KResult<usize> {
+        let descriptor = self.get(old).ok_or("Bad File Descriptor")?;
+        if new >= MAX_DESCRIPTORS {
+            return Err("Bad File Descriptor");
+        }
+        if new >= self.descriptors.len() {
+            self.descriptors.resize(new + 1, None);
+        }
+        self.descriptors[new] = Some(descriptor);
+        Ok(new)
+    }
+
+    /// Close `fd`, Returns The Descriptor That Was Open On It.
+    /// The Resource Is Freed Once Nothing Else Has It Open.
+    pub fn close(&mut self, fd: usize) -> Option<Descriptor> {
+        self.descriptors.get_mut(fd)?.take()
+    }
+
+    /// Close Everything, Done When A Process Exits.
+    pub fn close_all(&mut self) {
+        self.descriptors.clear();
+    }
+}
+
+/// Open The MFS File At `path` In The Current Process, See [OPEN_CREATE] & [OPEN_APPEND].
+pub fn open(path: &str, flags: u64) -> KResult<usize> {
+    let mut file = match mfs::open_file(path) {
+        Some(file) => file,
+        None if flags & OPEN_CREATE != 0 => mfs::create_file(path).ok_or("Failed To Create File")?,
+        None => return Err("No Such File"),
+    };
+    if flags & OPEN_APPEND != 0 {
+        let size = file.size() as u32;
+        file.seek(SeekFrom::Start(size)).map_err(|_| "Invalid Offset")?;
+    }
+    process::with_files(|files| files.open(Resource::File(file)))
+}
+
+/// The Descriptor Open On `fd` In The Current Process.
+pub fn get(fd: usize) -> KResult<Descriptor> {
+    process::with_files(|files| files.get(fd)).ok_or("Bad File Descriptor")
+}
+
+/// Read From `descriptor` Into `buffer`, Blocking Until Something Can Be Read, See [Resource::try_read].
+/// Neither The Descriptor Nor Any Table Is Held While Waiting, So Others Can Still Use Them.
+pub fn read_descriptor(descriptor: &Descriptor, buffer: &mut [u8]) -> KResult<usize> {
+    loop {
+        let blocked = match descriptor.lock().try_read(buffer)? {
+            Ok(count) => return Ok(count),
+            Err(blocked) => blocked,
+        };
+        blocked.wait(descriptor);
+    }
+}
+
+/// Read From `fd` Into `buffer`, See [read_descriptor].
+pub fn read(fd: usize, buffer: &mut [u8]) -> KResult<usize> {
+    read_descriptor(&get(fd)?, buffer)
+}
+
+/// Write `data` To `fd`, See [Resource::write].
+pub fn write(fd: usize, data: &[u8]) -> KResult<usize> {
+    get(fd)?.lock().write(data)
+}
+
+/// Close `fd` In The Current Process.
+pub fn close(fd: usize) -> KResult<()> {
+    let descriptor = process::with_files(|files| files.close(fd)).ok_or("Bad File Descriptor")?;
+    // Dropped Outside The Table, Closing A Pipe Takes Its Lock.
+    drop(descriptor);
+    Ok(())
+}
+
+/// See [FdTable::dup].
+pub fn dup(fd: usize) -> KResult<usize> {
+    process::with_files(|files| files.dup(fd))
+}
+
+/// See [FdTable::dup2].
+pub fn dup2(old: usize, new: usize) -> KResult<usize> {
+    process::with_files(|files| files.dup2(old, new))
+}
+
+/// Create A Pipe In The Current Process, Returns The Read & Write Ends.
+pub fn pipe() -> KResult<(usize, usize)> {
+    let pipe = Arc::new(Mutex::new(Pipe::default()));
+    process::with_files(|files| -> KResult<(usize, usize)> {
+        let reader = files.open(Resource::PipeReader(pipe.clone()))?;
+        match files.open(Resource::PipeWriter(pipe)) {
+            Ok(writer) => Ok((reader, writer)),
+            Err(e) => {
+                files.close(reader);
+                Err(e)
+            }
+        }
+    })
+}
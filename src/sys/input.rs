@@ -1,5 +1,10 @@
 //! Keyboard Input Functions
+//! Decoded Keys Are Buffered & Wake Whatever Awaits [next_key], So Readers
+//! Sleep Instead Of Polling.
 use alloc::string::String;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 use lazy_static::lazy_static;
 use pc_keyboard::{layouts::Uk105Key, DecodedKey, HandleControl::Ignore, Keyboard, ScancodeSet1};
 use spin::Mutex;
@@ -7,9 +12,9 @@ use x86_64::instructions::port::Port;
 
 type KeyboardUk = Keyboard<Uk105Key, ScancodeSet1>;
 
-use crate::{KResult, print};
+use crate::{KResult, no_interrupt, print, sys::interrupt::IrqResult};
 
-use super::{mem::ringbuffer::RingBuffer256};
+use super::{mem::ringbuffer::RingBuffer256, task::{self, WakerCell}, workqueue};
 
 /// ASCII DELETE KEY (0x7F)
 pub const DELETE: char = '\x7f';
@@ -21,67 +26,91 @@ pub const BACKSPACE: char = '\x08';
 pub const NEW_LINE: char = '\n';
 
 lazy_static! {
-    static ref KEYBOARD_BUFFER: Mutex<Option<RingBuffer256<u8>>> = Mutex::new(None);
+    static ref KEYBOARD_BUFFER: Mutex<RingBuffer256<char>> = Mutex::new(RingBuffer256::new());
     static ref KEYBOARD: Mutex<KeyboardUk> =
         Mutex::new(KeyboardUk::new(Uk105Key, ScancodeSet1, Ignore));
 }
 
-static mut LAST_KEY: char = 0 as char;
+static KEY_WAKER: WakerCell = WakerCell::new();
 
 /// Initialize The Input System
 pub fn initialize() -> KResult<()> {
-    (*KEYBOARD_BUFFER.lock()) = Some(RingBuffer256::new());
-
-    crate::sys::interrupt::set_irq_handler(1, on_key_pressed);
-
-    Ok(())
+    crate::sys::interrupt::set_irq_handler(1, on_key_pressed)
 }
 
-/// IRQ1 handler
-pub fn on_key_pressed(_: u8) {
+/// IRQ1 handler, Reads The Scancode & Defers Decoding To The Work Queue.
+pub fn on_key_pressed(_: u8) -> IrqResult {
     let byte = unsafe { Port::<u8>::new(0x60).read() };
+    let _ = workqueue::schedule(decode_scancode, byte as usize);
+    IrqResult::Handled
+}
+
+/// Bottom Half Of [on_key_pressed].
+fn decode_scancode(byte: usize) {
+    let byte = byte as u8;
     let mut kb = KEYBOARD.lock();
     if let Ok(Some(event)) = kb.add_byte(byte) {
         if let Some(key) = kb.process_keyevent(event) {
             match key {
                 DecodedKey::RawKey(_) => { /*terminal::process_raw_key(kc)*/ }
-                DecodedKey::Unicode(codepoint) => unsafe {
-                    LAST_KEY = codepoint;
-                },
+                DecodedKey::Unicode(codepoint) => {
+                    // Keys Typed While The Buffer Is Full Are Dropped.
+                    let _ = no_interrupt!({ KEYBOARD_BUFFER.lock().write(codepoint) });
+                    KEY_WAKER.wake();
+                }
             }
         }
     }
 }
 
-/// Reads A Single Character From The Keyboard, Blocks If No Key Is Available.
+/// Reads A Single Character From The Keyboard, Returns None If No Key Is Available.
 pub fn read_key() -> Option<char> {
-    unsafe {
-        let kc = LAST_KEY;
-        LAST_KEY = '\x00';
-        if kc == '\x00' {
-            return None;
-        } else {
-            return Some(kc);
+    workqueue::run_pending();
+    no_interrupt!({ KEYBOARD_BUFFER.lock().read() })
+}
+
+/// Resolves To The Next Key Pressed, See [next_key].
+#[derive(Debug)]
+pub struct NextKey;
+
+impl Future for NextKey {
+    type Output = char;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<char> {
+        if let Some(key) = read_key() {
+            return Poll::Ready(key);
+        }
+        KEY_WAKER.register(cx.waker());
+        // A Key May Have Arrived Before The Waker Was Registered.
+        match read_key() {
+            Some(key) => Poll::Ready(key),
+            None => Poll::Pending,
         }
     }
 }
 
+/// Wait For The Next Key Press.
+pub fn next_key() -> NextKey {
+    NextKey
+}
 
-/// Read Input From The User
-pub fn input(prompt: &str) -> String {
+/// Read A Line From The User, Echoing It After The Prompt.
+pub async fn read_line(prompt: &str) -> String {
     let mut s = String::new();
     print!("{}\r", prompt);
-    'input_loop: loop {
-        if let Some(key) = read_key() {
-
-            match key {
-                NEW_LINE => break 'input_loop,
-                BACKSPACE | DELETE => {s.pop();},
-                _ => s.push(key),
-            }
-            print!("{}{}{}\r", prompt, s, " ".repeat(1));
+    loop {
+        match next_key().await {
+            NEW_LINE => break,
+            BACKSPACE | DELETE => {s.pop();},
+            key => s.push(key),
         }
+        print!("{}{}{}\r", prompt, s, " ".repeat(1));
     }
     print!("\n");
     s
+}
+
+/// Read Input From The User, Sleeping Until Each Key Arrives.
+pub fn input(prompt: &str) -> String {
+    task::block_on(read_line(prompt))
 }
\ No newline at end of file
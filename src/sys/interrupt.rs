@@ -1,6 +1,8 @@
 //! Front-end to the Interrupts Sub-System.
 //! Attempts To Provide A Single, Stable API Across Platforms.
 
+pub mod apic;
+pub mod exceptions;
 pub mod gdt;
 pub mod idt;
 pub mod pics;
@@ -8,8 +10,20 @@ pub mod tss;
 
 use crate::{no_interrupt, print, KResult};
 
+pub use idt::IrqStats;
+
 /// Abstracts An Interrupt Handler.
-pub type InterruptHandler = fn(u8);
+/// Returns Whether The Interrupt Was Raised By The Handler's Device.
+pub type InterruptHandler = fn(u8) -> IrqResult;
+
+/// The Result Of Running An [InterruptHandler].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrqResult {
+    /// The Device Raised The Interrupt & It Was Serviced.
+    Handled,
+    /// The Interrupt Belongs To Another Device On The Same Line.
+    NotHandled,
+}
 
 pub(self) const MAX_HANDLERS: usize = 256;
 
@@ -33,11 +47,62 @@ pub fn initialize() -> KResult<()> {
     Ok(())
 }
 
+/// Select The Interrupt Controller, Must Run After Memory Is Initialized.
+/// With The `apic` Feature The APIC Replaces The PICs, Falling Back To The PICs
+/// If The Machine Has No Usable MADT.
+pub fn initialize_controller() -> KResult<()> {
+    #[cfg(feature = "apic")]
+    if let Err(e) = apic::initialize() {
+        crate::serr!("APIC Unavailable ({}), Using The PICs.\n", e);
+    }
+    Ok(())
+}
+
+/// Returns The Name Of The Active Interrupt Controller.
+pub fn controller() -> &'static str {
+    if apic::is_enabled() {
+        "APIC"
+    } else {
+        "8259 PIC"
+    }
+}
+
+/// Signal End Of Interrupt For The Given IRQ To Whichever Controller Delivered It.
+pub fn end_of_interrupt(irq: usize) {
+    if apic::is_enabled() {
+        apic::end_of_interrupt();
+    } else {
+        unsafe {
+            pics::PICS
+                .lock()
+                .notify_end_of_interrupt(idt::system_index(irq) as u8);
+        }
+    }
+}
+
 /// Set The IRQ handler Function.
-pub fn set_irq_handler(irq: usize, handler: InterruptHandler) {
+/// Returns Err If The IRQ Is Not Wired Or Already Has A Handler.
+pub fn set_irq_handler(irq: usize, handler: InterruptHandler) -> KResult<()> {
     idt::set_irq_handler(irq, handler)
 }
 
-pub(self) fn default_handler(_: u8) {
+/// Add A Handler To The IRQ's Chain, For Devices Sharing A Line.
+/// Handlers Are Identified By Their Address.
+pub fn register_irq_handler(irq: usize, handler: InterruptHandler) -> KResult<()> {
+    idt::register_irq_handler(irq, handler)
+}
+
+/// Remove A Handler From The IRQ's Chain.
+pub fn unregister_irq_handler(irq: usize, handler: InterruptHandler) -> KResult<()> {
+    idt::unregister_irq_handler(irq, handler)
+}
+
+/// Returns The Counters Of The Given IRQ, None If It Is Not Wired.
+pub fn irq_stats(irq: usize) -> Option<IrqStats> {
+    idt::irq_stats(irq)
+}
+
+pub(self) fn default_handler(_: u8) -> IrqResult {
     print!(".");
+    IrqResult::NotHandled
 }
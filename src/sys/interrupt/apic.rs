@@ -0,0 +1,226 @@
+//! Local APIC & IO-APIC Driver.
+//! Replaces The 8259 PICs When Built With The `apic` Feature & The MADT Is Present.
+//! Legacy IRQs Keep Their Vectors (0x20 + IRQ), So Drivers Don't Notice The Switch.
+//! <https://wiki.osdev.org/APIC>
+
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+use x86_64::registers::model_specific::Msr;
+use x86_64::structures::idt::InterruptStackFrame;
+
+use super::idt::{system_index, IRQ_COUNT};
+use super::pics;
+use crate::sys::{acpi, mem::mapper::physical_memory_offset, timer};
+use crate::{no_interrupt, slog, KResult};
+
+/// The Vector The Local APIC Raises For Spurious Interrupts.
+pub const SPURIOUS_VECTOR: u8 = 0xFF;
+
+const IA32_APIC_BASE: u32 = 0x1B;
+const APIC_BASE_ENABLE: u64 = 1 << 11;
+
+// Local APIC Register Offsets.
+const LAPIC_ID: u64 = 0x20;
+const LAPIC_TPR: u64 = 0x80;
+const LAPIC_EOI: u64 = 0xB0;
+const LAPIC_SVR: u64 = 0xF0;
+const LAPIC_LVT_TIMER: u64 = 0x320;
+const LAPIC_TIMER_INITIAL: u64 = 0x380;
+const LAPIC_TIMER_CURRENT: u64 = 0x390;
+const LAPIC_TIMER_DIVIDE: u64 = 0x3E0;
+
+const SVR_ENABLE: u32 = 1 << 8;
+const LVT_MASKED: u32 = 1 << 16;
+const LVT_PERIODIC: u32 = 1 << 17;
+/// Divide The Bus Clock By 16.
+const TIMER_DIVIDE_16: u32 = 0b0011;
+/// The Number Of PIT Ticks The LAPIC Timer Is Calibrated Over.
+const CALIBRATION_TICKS: u64 = 50;
+
+// IO-APIC Registers.
+const IOAPIC_IOWIN: u64 = 0x10;
+const IOAPIC_VERSION: u32 = 0x01;
+const IOAPIC_REDIRECTION: u32 = 0x10;
+
+const REDIRECT_ACTIVE_LOW: u64 = 1 << 13;
+const REDIRECT_LEVEL: u64 = 1 << 14;
+const REDIRECT_MASKED: u64 = 1 << 16;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// The Virtual Address Of The Local APIC's Registers.
+static LAPIC_BASE: AtomicU64 = AtomicU64::new(0);
+/// The Virtual Address Of The First IO-APIC's Registers.
+static IOAPIC_BASE: AtomicU64 = AtomicU64::new(0);
+static IOAPIC_GSI_BASE: AtomicU32 = AtomicU32::new(0);
+/// The Redirection Flags (High Half) & GSI (Low Half) Of Each Legacy IRQ, Filled From The MADT.
+static ROUTES: [AtomicU64; IRQ_COUNT] = [const { AtomicU64::new(0) }; IRQ_COUNT];
+/// LAPIC Timer Counts Per Timer Tick, Found By Calibrating Against The PIT.
+static TIMER_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Returns True If The APIC Has Replaced The PICs.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn lapic_read(reg: u64) -> u32 {
+    unsafe { read_volatile((LAPIC_BASE.load(Ordering::Relaxed) + reg) as *const u32) }
+}
+
+fn lapic_write(reg: u64, value: u32) {
+    unsafe { write_volatile((LAPIC_BASE.load(Ordering::Relaxed) + reg) as *mut u32, value) }
+}
+
+fn ioapic_read(reg: u32) -> u32 {
+    let base = IOAPIC_BASE.load(Ordering::Relaxed);
+    unsafe {
+        write_volatile(base as *mut u32, reg);
+        read_volatile((base + IOAPIC_IOWIN) as *const u32)
+    }
+}
+
+fn ioapic_write(reg: u32, value: u32) {
+    let base = IOAPIC_BASE.load(Ordering::Relaxed);
+    unsafe {
+        write_volatile(base as *mut u32, reg);
+        write_volatile((base + IOAPIC_IOWIN) as *mut u32, value);
+    }
+}
+
+/// The APIC ID Of The Current Processor.
+pub fn lapic_id() -> u8 {
+    (lapic_read(LAPIC_ID) >> 24) as u8
+}
+
+/// Signal End Of Interrupt To The Local APIC.
+pub fn end_of_interrupt() {
+    lapic_write(LAPIC_EOI, 0);
+}
+
+/// The Number Of Redirection Entries The IO-APIC Has.
+pub fn redirection_entries() -> u32 {
+    ((ioapic_read(IOAPIC_VERSION) >> 16) & 0xFF) + 1
+}
+
+fn write_redirection(gsi: u32, entry: u64) {
+    let index = gsi - IOAPIC_GSI_BASE.load(Ordering::Relaxed);
+    ioapic_write(IOAPIC_REDIRECTION + index * 2, entry as u32);
+    ioapic_write(IOAPIC_REDIRECTION + index * 2 + 1, (entry >> 32) as u32);
+}
+
+/// Route A Legacy IRQ To Its Vector On This Processor, Honouring MADT Overrides.
+pub fn redirect(irq: usize) -> KResult<()> {
+    if irq >= IRQ_COUNT {
+        return Err("IRQ Is Not Wired Into The IDT");
+    }
+    let route = ROUTES[irq].load(Ordering::Relaxed);
+    let entry = (route >> 32)
+        | system_index(irq) as u64
+        | (lapic_id() as u64) << 56;
+    write_redirection(route as u32, entry);
+    Ok(())
+}
+
+/// Stop A Legacy IRQ From Reaching The CPU.
+pub fn mask(irq: usize) -> KResult<()> {
+    if irq >= IRQ_COUNT {
+        return Err("IRQ Is Not Wired Into The IDT");
+    }
+    let route = ROUTES[irq].load(Ordering::Relaxed);
+    write_redirection(route as u32, REDIRECT_MASKED | system_index(irq) as u64);
+    Ok(())
+}
+
+/// Measure How Many LAPIC Timer Counts Elapse Per PIT Tick.
+/// Must Run While The PIT Is Still Delivering Interrupts.
+fn calibrate_timer() -> u32 {
+    lapic_write(LAPIC_TIMER_DIVIDE, TIMER_DIVIDE_16);
+    lapic_write(LAPIC_LVT_TIMER, LVT_MASKED);
+
+    // Start On A Tick Boundary.
+    timer::sleep_ticks(1);
+    lapic_write(LAPIC_TIMER_INITIAL, u32::MAX);
+    timer::sleep_ticks(CALIBRATION_TICKS);
+    let elapsed = u32::MAX - lapic_read(LAPIC_TIMER_CURRENT);
+    lapic_write(LAPIC_TIMER_INITIAL, 0);
+
+    (elapsed as u64 / CALIBRATION_TICKS) as u32
+}
+
+/// Start The LAPIC Timer In Periodic Mode On The Timer's Vector.
+fn start_timer() {
+    lapic_write(LAPIC_TIMER_DIVIDE, TIMER_DIVIDE_16);
+    lapic_write(LAPIC_LVT_TIMER, LVT_PERIODIC | system_index(0) as u32);
+    lapic_write(LAPIC_TIMER_INITIAL, TIMER_COUNT.load(Ordering::Relaxed));
+}
+
+/// LAPIC Timer Counts Per Tick, 0 If Not Calibrated.
+pub fn timer_count() -> u32 {
+    TIMER_COUNT.load(Ordering::Relaxed)
+}
+
+/// Discover The APICs Through The MADT & Switch Interrupt Delivery Over From The PICs.
+/// The PIT Must Already Be Ticking, It Is Used To Calibrate The LAPIC Timer.
+pub fn initialize() -> KResult<()> {
+    let madt = acpi::madt().ok_or("ACPI MADT Not Found")?;
+    let ioapic = *madt.io_apics.first().ok_or("No IO-APIC Found")?;
+    let offset = physical_memory_offset();
+
+    LAPIC_BASE.store(madt.local_apic_address + offset, Ordering::Relaxed);
+    IOAPIC_BASE.store(ioapic.address + offset, Ordering::Relaxed);
+    IOAPIC_GSI_BASE.store(ioapic.gsi_base, Ordering::Relaxed);
+
+    for (irq, route) in ROUTES.iter().enumerate() {
+        let value = match madt.override_for(irq as u8) {
+            Some(o) => {
+                let mut flags = 0;
+                if o.active_low() {
+                    flags |= REDIRECT_ACTIVE_LOW;
+                }
+                if o.level_triggered() {
+                    flags |= REDIRECT_LEVEL;
+                }
+                flags << 32 | o.gsi as u64
+            }
+            None => irq as u64,
+        };
+        route.store(value, Ordering::Relaxed);
+    }
+
+    unsafe {
+        let mut base = Msr::new(IA32_APIC_BASE);
+        let value = base.read();
+        base.write(value | APIC_BASE_ENABLE);
+    }
+    lapic_write(LAPIC_SVR, SVR_ENABLE | SPURIOUS_VECTOR as u32);
+    lapic_write(LAPIC_TPR, 0);
+
+    TIMER_COUNT.store(calibrate_timer(), Ordering::Relaxed);
+
+    no_interrupt!({
+        pics::disable();
+        for gsi in ioapic.gsi_base..ioapic.gsi_base + redirection_entries() {
+            write_redirection(gsi, REDIRECT_MASKED);
+        }
+        // IRQ 0 Is Replaced By The LAPIC Timer & IRQ 2 Is The PIC Cascade.
+        for irq in (1..IRQ_COUNT).filter(|&irq| irq != 2) {
+            redirect(irq)?;
+        }
+        start_timer();
+        ENABLED.store(true, Ordering::Relaxed);
+        timer::set_tick_source(timer::TickSource::LapicTimer);
+        Ok::<(), &'static str>(())
+    })?;
+
+    slog!(
+        "APIC Enabled: LAPIC {:#x}, IO-APIC {:#x}, {} CPU(s), {} Override(s)\n",
+        madt.local_apic_address,
+        ioapic.address,
+        madt.local_apics.len(),
+        madt.overrides.len()
+    );
+    Ok(())
+}
+
+/// The Local APIC Does Not Expect An EOI For Spurious Interrupts.
+pub extern "x86-interrupt" fn spurious_handler(_stack_frame: InterruptStackFrame) {}
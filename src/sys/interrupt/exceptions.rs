@@ -0,0 +1,509 @@
+//! CPU Exception Handlers.
+//! Every Exception Produces A [FaultReport], Printed To Both VGA & Serial,
+//! The Installed [FaultPolicy] Then Decides What Happens Next.
+//! Each Vector Enters Through A Small Assembly Stub That Saves The General
+//! Purpose Registers, So The Report Shows The Interrupted Context In Full.
+//! Reporting Never Allocates Nor Waits For A Lock, So Faults In The Heap Or
+//! While Printing Are Reported Too.
+
+use core::arch::global_asm;
+use core::fmt::{self, Display, Write};
+
+use spin::Mutex;
+use x86_64::registers::control::{Cr0, Cr2, Cr3, Cr4};
+use x86_64::structures::idt::{
+    InterruptDescriptorTable, InterruptStackFrameValue, PageFaultErrorCode, SelectorErrorCode,
+};
+use x86_64::VirtAddr;
+
+use super::tss;
+use crate::sys::debugger::disassembler::{self, MAX_INSTRUCTION_LEN};
+use crate::sys::{mem::{guard::{self, GuardRegion}, mapper}, serial, terminal, thread, user};
+
+/// The Longest Printed Report, Anything After Is Cut Off.
+const REPORT_SIZE: usize = 1024;
+/// Marks Fault Output On Serial, Like `serr!`.
+const SERIAL_PREFIX: &str = "\x1b[31m[ERR]:\x1b[39m ";
+
+/// The Architectural Exception Names, Indexed By Vector.
+const EXCEPTION_NAMES: [(&str, &str); 32] = [
+    ("#DE", "Divide Error"),
+    ("#DB", "Debug"),
+    ("NMI", "Non-Maskable Interrupt"),
+    ("#BP", "Breakpoint"),
+    ("#OF", "Overflow"),
+    ("#BR", "Bound Range Exceeded"),
+    ("#UD", "Invalid Opcode"),
+    ("#NM", "Device Not Available"),
+    ("#DF", "Double Fault"),
+    ("---", "Coprocessor Segment Overrun"),
+    ("#TS", "Invalid TSS"),
+    ("#NP", "Segment Not Present"),
+    ("#SS", "Stack Segment Fault"),
+    ("#GP", "General Protection Fault"),
+    ("#PF", "Page Fault"),
+    ("---", "Reserved"),
+    ("#MF", "x87 Floating Point"),
+    ("#AC", "Alignment Check"),
+    ("#MC", "Machine Check"),
+    ("#XM", "SIMD Floating Point"),
+    ("#VE", "Virtualization"),
+    ("#CP", "Control Protection"),
+    ("---", "Reserved"),
+    ("---", "Reserved"),
+    ("---", "Reserved"),
+    ("---", "Reserved"),
+    ("---", "Reserved"),
+    ("---", "Reserved"),
+    ("#HV", "Hypervisor Injection"),
+    ("#VC", "VMM Communication"),
+    ("#SX", "Security Exception"),
+    ("---", "Reserved"),
+];
+
+/// What To Do Once A Fault Has Been Reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    /// Stop The Machine.
+    Halt,
+    /// Terminate The Task That Caused The Fault.
+    KillTask,
+    /// Return From The Handler.
+    /// Faults Re-Execute The Faulting Instruction, Only Useful For Traps.
+    Resume,
+}
+
+/// Decides What Happens After A Fault, See [set_fault_policy].
+pub type FaultPolicy = fn(&FaultReport) -> FaultAction;
+
+static POLICY: Mutex<FaultPolicy> = Mutex::new(default_policy);
+
+/// Replace The Fault Policy, Returns The Previous One.
+pub fn set_fault_policy(policy: FaultPolicy) -> FaultPolicy {
+    crate::no_interrupt!({ core::mem::replace(&mut *POLICY.lock(), policy) })
+}
+
+/// Resumes Traps, Kills User Tasks & Overflowed Threads, Halts On Everything Else.
+pub fn default_policy(report: &FaultReport) -> FaultAction {
+    match report.vector {
+        1 | 3 | 4 => FaultAction::Resume,
+        _ if report.is_user() || report.is_stack_overflow() => FaultAction::KillTask,
+        _ => FaultAction::Halt,
+    }
+}
+
+/// The General Purpose Registers At The Time Of A Fault, In The Order The Entry Stub Saves Them.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rbp: u64,
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rdx: u64,
+    pub rcx: u64,
+    pub rbx: u64,
+    pub rax: u64,
+}
+
+impl Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let registers = [
+            ("RAX", self.rax),
+            ("RBX", self.rbx),
+            ("RCX", self.rcx),
+            ("RDX", self.rdx),
+            ("RSI", self.rsi),
+            ("RDI", self.rdi),
+            ("RBP", self.rbp),
+            ("R8", self.r8),
+            ("R9", self.r9),
+            ("R10", self.r10),
+            ("R11", self.r11),
+            ("R12", self.r12),
+            ("R13", self.r13),
+            ("R14", self.r14),
+            ("R15", self.r15),
+        ];
+        // Three To A Line Fits The 80 Column Terminal.
+        for line in registers.chunks(3) {
+            for (i, (name, value)) in line.iter().enumerate() {
+                let separator = if i + 1 == line.len() { "\n" } else { " " };
+                write!(f, "{:<4}{:#018x}{}", name, value, separator)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A Fixed Buffer On The Stack For Formatting Reports, Longer Text Is Cut Off.
+struct ReportBuffer {
+    bytes: [u8; REPORT_SIZE],
+    len: usize,
+}
+
+impl ReportBuffer {
+    fn new() -> Self {
+        Self { bytes: [0; REPORT_SIZE], len: 0 }
+    }
+
+    fn as_str(&self) -> &str {
+        let bytes = &self.bytes[..self.len];
+        // Cutting Off May Have Split A Character.
+        match core::str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or(""),
+        }
+    }
+}
+
+impl Write for ReportBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let count = s.len().min(REPORT_SIZE - self.len);
+        self.bytes[self.len..self.len + count].copy_from_slice(&s.as_bytes()[..count]);
+        self.len += count;
+        Ok(())
+    }
+}
+
+/// An Error Code Decoded For Its Vector, See [FaultReport::decoded_error].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    pub vector: u8,
+    pub code: u64,
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = self.code;
+        match self.vector {
+            10 | 11 | 12 | 13 => {
+                let selector = SelectorErrorCode::new_truncate(code);
+                if selector.is_null() {
+                    write!(f, "{:#x} (Not Selector Related)", code)
+                } else {
+                    write!(f, "{:#x} {:?}", code, selector)
+                }
+            }
+            14 => write!(f, "{:#x} {:?}", code, PageFaultErrorCode::from_bits_truncate(code)),
+            30 if code == 1 => write!(f, "{:#x} (INIT Redirected)", code),
+            _ => write!(f, "{:#x}", code),
+        }
+    }
+}
+
+/// Everything Known About A Fault At The Time It Happened.
+#[derive(Debug, Clone, Copy)]
+pub struct FaultReport {
+    /// The Exception Vector (0-31).
+    pub vector: u8,
+    /// The Error Code Pushed By The CPU, If Any.
+    pub error_code: Option<u64>,
+    /// The Interrupted Context.
+    pub frame: InterruptStackFrameValue,
+    /// The General Purpose Registers Of The Interrupted Context.
+    pub registers: Registers,
+    /// The Faulting Address, Only Set For Page Faults.
+    pub cr2: Option<VirtAddr>,
+    /// The Guard Page Hit, i.e. A Stack Overflow, Only Set For Page Faults.
+    pub guard: Option<GuardRegion>,
+}
+
+impl FaultReport {
+    /// Build A Report For The Given Vector.
+    pub fn new(vector: u8, error_code: Option<u64>, frame: InterruptStackFrameValue, registers: Registers) -> Self {
+        let cr2 = if vector == 14 { Some(Cr2::read()) } else { None };
+        Self {
+            vector,
+            error_code,
+            frame,
+            registers,
+            cr2,
+            guard: cr2.and_then(guard::lookup),
+        }
+    }
+
+    /// Returns True If The Fault Was A Stack Running Into Its Guard Page.
+    pub fn is_stack_overflow(&self) -> bool {
+        matches!(self.guard, Some(g) if g.kind == guard::GuardKind::Stack)
+    }
+
+    /// The Short Mnemonic, i.e. "#GP".
+    pub fn mnemonic(&self) -> &'static str {
+        EXCEPTION_NAMES[self.vector as usize % 32].0
+    }
+
+    /// The Full Name, i.e. "General Protection Fault".
+    pub fn name(&self) -> &'static str {
+        EXCEPTION_NAMES[self.vector as usize % 32].1
+    }
+
+    /// Returns True If The Fault Happened In Ring 3.
+    pub fn is_user(&self) -> bool {
+        self.frame.code_segment & 0b11 == 0b11
+    }
+
+    /// Decode The Error Code Into Something Readable.
+    pub fn decoded_error(&self) -> Option<ErrorCode> {
+        Some(ErrorCode { vector: self.vector, code: self.error_code? })
+    }
+
+    /// Returns True If The Faulting Instruction Can Be Disassembled.
+    pub fn instruction_is_mapped(&self) -> bool {
+        let rip = self.frame.instruction_pointer;
+        mapper::translate(rip).is_some() && mapper::translate(rip + (MAX_INSTRUCTION_LEN - 1)).is_some()
+    }
+
+    /// Print The Report To Both VGA & Serial.
+    /// Serial Is Taken By Force If The Fault Interrupted A Print, VGA Is Skipped If Busy.
+    pub fn print(&self) {
+        let mut buffer = ReportBuffer::new();
+        let _ = write!(buffer, "{}", self);
+        terminal::try_eprint(format_args!("{}", buffer.as_str()));
+        serial::force_print(format_args!("{}{}", SERIAL_PREFIX, buffer.as_str()));
+    }
+}
+
+impl Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EXCEPTION {} ({}): {}", self.vector, self.mnemonic(), self.name())?;
+        write!(f, " - {}\n", if self.is_user() { "USER" } else { "KERNEL" })?;
+        if let Some(guard) = self.guard {
+            write!(f, "{}\n", guard)?;
+        }
+        if let Some(error) = self.decoded_error() {
+            write!(f, "Error Code: {}\n", error)?;
+        }
+        if let Some(cr2) = self.cr2 {
+            write!(f, "Address (CR2): {:#018x}\n", cr2.as_u64())?;
+        }
+        let rip = self.frame.instruction_pointer.as_u64();
+        if self.instruction_is_mapped() {
+            disassembler::write_instruction(rip as usize, f)?;
+            write!(f, "\n")?;
+        } else {
+            write!(f, "RIP: {:#018x} (Unmapped)\n", rip)?;
+        }
+        write!(
+            f,
+            "RSP: {:#018x} RFLAGS: {:#010x} CS: {:#06x} SS: {:#06x}\n",
+            self.frame.stack_pointer.as_u64(),
+            self.frame.cpu_flags,
+            self.frame.code_segment,
+            self.frame.stack_segment
+        )?;
+        write!(f, "{}", self.registers)?;
+        write!(
+            f,
+            "CR0: {:#010x} CR3: {:#018x} CR4: {:#010x}\n",
+            Cr0::read_raw(),
+            Cr3::read().0.start_address().as_u64(),
+            Cr4::read_raw()
+        )
+    }
+}
+
+/// What The Entry Stubs Leave On The Stack, Lowest Address First.
+#[repr(C)]
+struct ExceptionFrame {
+    registers: Registers,
+    vector: u64,
+    /// Zero For Vectors Without One, The Stub Pushes It To Keep The Layout.
+    error_code: u64,
+    frame: InterruptStackFrameValue,
+}
+
+/// Returns True If The CPU Pushes An Error Code For `vector`.
+fn has_error_code(vector: u8) -> bool {
+    matches!(vector, 8 | 10..=14 | 17 | 21 | 29 | 30)
+}
+
+/// Report A Fault & Carry Out The Policy's Decision.
+fn handle_fault(report: FaultReport) {
+    report.print();
+    // A Fault While The Policy Is Being Replaced Falls Back To The Default.
+    let policy = POLICY.try_lock().map_or(default_policy as FaultPolicy, |policy| *policy);
+    match policy(&report) {
+        FaultAction::Resume => {}
+        FaultAction::KillTask if report.is_user() && user::is_running() => {
+            serial::force_print(format_args!("{}Killing User Program.\n", SERIAL_PREFIX));
+            user::exit(user::FAULT_EXIT_BASE + report.vector as i64);
+        }
+        FaultAction::KillTask if thread::can_exit() => {
+            if let Some((id, name)) = thread::current() {
+                serial::force_print(format_args!("{}Killing Thread {} '{}'.\n", SERIAL_PREFIX, id.0, name));
+            }
+            thread::exit();
+        }
+        FaultAction::KillTask => {
+            serial::force_print(format_args!("{}No Task To Kill, Halting.\n", SERIAL_PREFIX));
+            crate::halt();
+        }
+        FaultAction::Halt => crate::halt(),
+    }
+}
+
+/// Called By The Entry Stubs, Double Faults & Machine Checks Can't Be Returned From.
+#[no_mangle]
+extern "C" fn almond_exception_dispatch(frame: &ExceptionFrame) {
+    let vector = frame.vector as u8;
+    let error_code = if has_error_code(vector) { Some(frame.error_code) } else { None };
+    let report = FaultReport::new(vector, error_code, frame.frame, frame.registers);
+    match vector {
+        8 | 18 => {
+            report.print();
+            crate::halt();
+        }
+        _ => handle_fault(report),
+    }
+}
+
+extern "C" {
+    /// The Entry Stub Of Every Vector, Indexed By Vector.
+    static almond_exception_stubs: [u64; 32];
+}
+
+// Every Stub Pushes A Zero Where The CPU Doesn't Push An Error Code, Then Its
+// Vector, So The Common Entry Always Sees An [ExceptionFrame]. The CPU Aligns
+// The Stack Before The Interrupt Frame, Which Keeps It Aligned For The Call.
+global_asm!(
+    r#"
+.macro ALMOND_EXCEPTION vector
+almond_exception_\vector:
+    push 0
+    push \vector
+    jmp almond_exception_common
+.endm
+
+.macro ALMOND_EXCEPTION_CODE vector
+almond_exception_\vector:
+    push \vector
+    jmp almond_exception_common
+.endm
+
+ALMOND_EXCEPTION 0
+ALMOND_EXCEPTION 1
+ALMOND_EXCEPTION 2
+ALMOND_EXCEPTION 3
+ALMOND_EXCEPTION 4
+ALMOND_EXCEPTION 5
+ALMOND_EXCEPTION 6
+ALMOND_EXCEPTION 7
+ALMOND_EXCEPTION_CODE 8
+ALMOND_EXCEPTION 9
+ALMOND_EXCEPTION_CODE 10
+ALMOND_EXCEPTION_CODE 11
+ALMOND_EXCEPTION_CODE 12
+ALMOND_EXCEPTION_CODE 13
+ALMOND_EXCEPTION_CODE 14
+ALMOND_EXCEPTION 15
+ALMOND_EXCEPTION 16
+ALMOND_EXCEPTION_CODE 17
+ALMOND_EXCEPTION 18
+ALMOND_EXCEPTION 19
+ALMOND_EXCEPTION 20
+ALMOND_EXCEPTION_CODE 21
+ALMOND_EXCEPTION 22
+ALMOND_EXCEPTION 23
+ALMOND_EXCEPTION 24
+ALMOND_EXCEPTION 25
+ALMOND_EXCEPTION 26
+ALMOND_EXCEPTION 27
+ALMOND_EXCEPTION 28
+ALMOND_EXCEPTION_CODE 29
+ALMOND_EXCEPTION_CODE 30
+ALMOND_EXCEPTION 31
+
+almond_exception_common:
+    push rax
+    push rbx
+    push rcx
+    push rdx
+    push rsi
+    push rdi
+    push rbp
+    push r8
+    push r9
+    push r10
+    push r11
+    push r12
+    push r13
+    push r14
+    push r15
+    mov rdi, rsp
+    cld
+    call almond_exception_dispatch
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop r11
+    pop r10
+    pop r9
+    pop r8
+    pop rbp
+    pop rdi
+    pop rsi
+    pop rdx
+    pop rcx
+    pop rbx
+    pop rax
+    add rsp, 16
+    iretq
+
+.pushsection .rodata
+.balign 8
+.global almond_exception_stubs
+almond_exception_stubs:
+.irp vector, 0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31
+    .quad almond_exception_\vector
+.endr
+.popsection
+"#
+);
+
+/// The Address Of `vector`'s Entry Stub.
+fn stub(vector: usize) -> VirtAddr {
+    VirtAddr::new(unsafe { almond_exception_stubs[vector] })
+}
+
+/// Install A Handler For Every Exception The IDT Exposes.
+/// Vectors 15, 21-28 & 31 Are Reserved & Never Raised By The CPU.
+pub fn install(idt: &mut InterruptDescriptorTable) {
+    // The Stubs Follow The Calling Convention Of Each Entry.
+    unsafe {
+        idt.divide_error.set_handler_addr(stub(0));
+        idt.debug.set_handler_addr(stub(1));
+        idt.non_maskable_interrupt.set_handler_addr(stub(2));
+        idt.breakpoint.set_handler_addr(stub(3));
+        idt.overflow.set_handler_addr(stub(4));
+        idt.bound_range_exceeded.set_handler_addr(stub(5));
+        idt.invalid_opcode.set_handler_addr(stub(6));
+        idt.device_not_available.set_handler_addr(stub(7));
+        idt.coprocessor_segment_overrun.set_handler_addr(stub(9));
+        idt.invalid_tss.set_handler_addr(stub(10));
+        idt.segment_not_present.set_handler_addr(stub(11));
+        idt.stack_segment_fault.set_handler_addr(stub(12));
+        idt.general_protection_fault.set_handler_addr(stub(13));
+        idt.x87_floating_point.set_handler_addr(stub(16));
+        idt.alignment_check.set_handler_addr(stub(17));
+        idt.machine_check.set_handler_addr(stub(18));
+        idt.simd_floating_point.set_handler_addr(stub(19));
+        idt.virtualization.set_handler_addr(stub(20));
+        idt.vmm_communication_exception.set_handler_addr(stub(29));
+        idt.security_exception.set_handler_addr(stub(30));
+        idt.double_fault
+            .set_handler_addr(stub(8))
+            .set_stack_index(tss::DOUBLE_FAULT_IST_INDEX);
+        idt.page_fault
+            .set_handler_addr(stub(14))
+            .set_stack_index(tss::PAGE_FAULT_IST_INDEX);
+    }
+}
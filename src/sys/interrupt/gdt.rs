@@ -1,4 +1,61 @@
 //! Utility Functions For Interacting With GDT.
 
+use lazy_static::lazy_static;
+use x86_64::instructions::segmentation::{Segment, CS};
+use x86_64::instructions::tables::load_tss;
+use x86_64::structures::gdt::{Descriptor, GlobalDescriptorTable, SegmentSelector};
+
+use super::tss;
+
+/// The Segment Selectors Of Every Entry In The GDT.
+///
+/// The Order Of `user_data` & `user_code` Matters, `sysret` Expects The User Data
+/// Segment Directly Before The User Code Segment.
+#[derive(Debug, Clone, Copy)]
+pub struct Selectors {
+    /// Ring 0 Code Segment.
+    pub kernel_code: SegmentSelector,
+    /// Ring 0 Data Segment.
+    pub kernel_data: SegmentSelector,
+    /// Ring 3 Data Segment.
+    pub user_data: SegmentSelector,
+    /// Ring 3 Code Segment.
+    pub user_code: SegmentSelector,
+    /// The Task State Segment.
+    pub tss: SegmentSelector,
+}
+
+lazy_static! {
+    static ref GDT: (GlobalDescriptorTable, Selectors) = {
+        let mut gdt = GlobalDescriptorTable::new();
+        let kernel_code = gdt.add_entry(Descriptor::kernel_code_segment());
+        let kernel_data = gdt.add_entry(Descriptor::kernel_data_segment());
+        let user_data = gdt.add_entry(Descriptor::user_data_segment());
+        let user_code = gdt.add_entry(Descriptor::user_code_segment());
+        let tss = gdt.add_entry(tss::descriptor());
+        (
+            gdt,
+            Selectors {
+                kernel_code,
+                kernel_data,
+                user_data,
+                user_code,
+                tss,
+            },
+        )
+    };
+}
+
 /// Reload The GDT Values.
-pub unsafe fn reload() {}
+/// Loads The GDT, Reloads CS & The Data Segments And Loads The TSS.
+pub unsafe fn reload() {
+    GDT.0.load();
+    CS::set_reg(GDT.1.kernel_code);
+    tss::set_kernel_segments();
+    load_tss(GDT.1.tss);
+}
+
+/// Returns The Selectors Of The Loaded GDT.
+pub fn selectors() -> &'static Selectors {
+    &GDT.1
+}
@@ -1,19 +1,46 @@
 //! Utility Functions For Interacting With IDT.
 
-use super::{default_handler, InterruptHandler, MAX_HANDLERS};
-use crate::{no_interrupt};
+use core::arch::x86_64::_rdtsc;
+
+use super::{apic, default_handler, end_of_interrupt, exceptions, pics, InterruptHandler, IrqResult};
+use crate::{no_interrupt, sys::{timer, user::syscall}, KResult};
 use lazy_static;
 use spin::Mutex;
-use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
+use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
+use x86_64::PrivilegeLevel;
 
 /// The Offset Of The PIC1
 /// handler_index = irq + PIC1;
 pub const PIC1_OFFSET: usize = 0x20;
 /// The Offset Of The PIC2.
 pub const PIC2_OFFSET: usize = 0x28;
+/// The Number Of Legacy IRQ Lines Wired Into The IDT.
+pub const IRQ_COUNT: usize = 16;
+/// The Maximum Number Of Handlers Sharing A Single IRQ.
+pub const MAX_SHARED_HANDLERS: usize = 4;
+
+type HandlerChain = [Option<InterruptHandler>; MAX_SHARED_HANDLERS];
+
+/// Per-IRQ Counters, See [irq_stats].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IrqStats {
+    /// The Number Of Times The IRQ Fired, Excluding Spurious IRQs.
+    pub count: u64,
+    /// The Number Of Times No Handler Claimed The IRQ.
+    pub unhandled: u64,
+    /// The Number Of Spurious IRQs, Only Possible On IRQ 7 & 15 Behind The PICs.
+    pub spurious: u64,
+    /// The Timer Tick Of The Last Occurence.
+    pub last_tick: u64,
+    /// Total Time Spent In The Handlers, In TSC Cycles.
+    pub cycles: u64,
+    /// The Number Of Registered Handlers.
+    pub handlers: usize,
+}
 
 lazy_static::lazy_static! {
-    static ref HANDLERS: Mutex<[InterruptHandler; MAX_HANDLERS]> = Mutex::new([default_handler; MAX_HANDLERS]);
+    static ref HANDLERS: Mutex<[HandlerChain; IRQ_COUNT]> = Mutex::new([[None; MAX_SHARED_HANDLERS]; IRQ_COUNT]);
+    static ref STATS: Mutex<[IrqStats; IRQ_COUNT]> = Mutex::new([IrqStats::default(); IRQ_COUNT]);
 
     static ref IDT: InterruptDescriptorTable = {
         let mut idt = InterruptDescriptorTable::new();
@@ -22,12 +49,29 @@ lazy_static::lazy_static! {
         idt[system_index(1)].set_handler_fn(irq_1);
         idt[system_index(2)].set_handler_fn(irq_2);
         idt[system_index(3)].set_handler_fn(irq_3);
+        idt[system_index(4)].set_handler_fn(irq_4);
+        idt[system_index(5)].set_handler_fn(irq_5);
+        idt[system_index(6)].set_handler_fn(irq_6);
+        idt[system_index(7)].set_handler_fn(irq_7);
+        idt[system_index(8)].set_handler_fn(irq_8);
+        idt[system_index(9)].set_handler_fn(irq_9);
+        idt[system_index(10)].set_handler_fn(irq_10);
+        idt[system_index(11)].set_handler_fn(irq_11);
+        idt[system_index(12)].set_handler_fn(irq_12);
+        idt[system_index(13)].set_handler_fn(irq_13);
         idt[system_index(14)].set_handler_fn(irq_14);
         idt[system_index(15)].set_handler_fn(irq_15);
 
-        idt.breakpoint.set_handler_fn(breakpoint_handler);
-        idt.double_fault.set_handler_fn(double_fault);
-        idt.page_fault.set_handler_fn(page_fault);
+        idt[apic::SPURIOUS_VECTOR as usize].set_handler_fn(apic::spurious_handler);
+
+        exceptions::install(&mut idt);
+
+        // Reachable From Ring 3, The Handler Saves Registers Itself.
+        unsafe {
+            idt[syscall::SYSCALL_VECTOR]
+                .set_handler_addr(syscall::int80_entry())
+                .set_privilege_level(PrivilegeLevel::Ring3);
+        }
 
         idt
     };
@@ -38,30 +82,112 @@ pub unsafe fn load() {
     IDT.load();
 }
 
-extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
-    crate::print!("EXCEPTION: BREAKPOINT\n{:#?}\n", stack_frame);
+/// Set The Handler Function For A Given IRQ.
+/// Returns Err If The IRQ Is Not Wired Or Already Has A Handler.
+pub fn set_irq_handler(irq: usize, handler: InterruptHandler) -> KResult<()> {
+    if irq >= IRQ_COUNT {
+        return Err("IRQ Is Not Wired Into The IDT");
+    }
+    no_interrupt!({
+        let mut handlers = HANDLERS.lock();
+        if handlers[irq].iter().any(Option::is_some) {
+            return Err("IRQ Handler Already Registered");
+        }
+        handlers[irq][0] = Some(handler);
+        Ok(())
+    })
 }
 
-extern "x86-interrupt" fn double_fault(stack_frame: InterruptStackFrame, _: u64) -> ! {
-    crate::print!("EXCEPTION: DOUBLE FAULT\n{:#?}\n", stack_frame);
-    crate::run!(":d {} 32", stack_frame.instruction_pointer.as_u64());
-    crate::run!(":r");
-    crate::halt();
+/// Add A Handler To The End Of The IRQ's Chain.
+/// Returns Err If The IRQ Is Not Wired, The Chain Is Full Or The Handler Is Already In It.
+/// Handlers Are Told Apart By Address, So Functions With Identical Bodies May Be Merged Into One.
+pub fn register_irq_handler(irq: usize, handler: InterruptHandler) -> KResult<()> {
+    if irq >= IRQ_COUNT {
+        return Err("IRQ Is Not Wired Into The IDT");
+    }
+    no_interrupt!({
+        let mut handlers = HANDLERS.lock();
+        let chain = &mut handlers[irq];
+        if chain.iter().flatten().any(|&h| h as usize == handler as usize) {
+            return Err("IRQ Handler Already Registered");
+        }
+        match chain.iter_mut().find(|h| h.is_none()) {
+            Some(slot) => {
+                *slot = Some(handler);
+                Ok(())
+            }
+            None => Err("IRQ Handler Chain Is Full"),
+        }
+    })
 }
 
-extern "x86-interrupt" fn page_fault(info: InterruptStackFrame, ec: PageFaultErrorCode) {
-    panic!("PAGE Fault: - {:?} - \n{:?}\n", ec, info);
-    
+/// Remove A Handler From The IRQ's Chain, Returns Err If It Was Not Registered.
+/// The Handler Is Matched By Address, The Same Way [register_irq_handler] Compares Them.
+pub fn unregister_irq_handler(irq: usize, handler: InterruptHandler) -> KResult<()> {
+    if irq >= IRQ_COUNT {
+        return Err("IRQ Is Not Wired Into The IDT");
+    }
+    no_interrupt!({
+        let mut handlers = HANDLERS.lock();
+        let chain = &mut handlers[irq];
+        match chain.iter().position(|h| matches!(h, Some(h) if *h as usize == handler as usize)) {
+            Some(index) => {
+                // Keep The Chain Packed So Dispatch Can Stop At The First Empty Slot.
+                for i in index..MAX_SHARED_HANDLERS - 1 {
+                    chain[i] = chain[i + 1];
+                }
+                chain[MAX_SHARED_HANDLERS - 1] = None;
+                Ok(())
+            }
+            None => Err("IRQ Handler Not Registered"),
+        }
+    })
 }
 
-/// Set The Handler Function For A Given IRQ.
-pub fn set_irq_handler(irq: usize, handler: InterruptHandler) {
+/// Returns The Counters Of The Given IRQ, None If It Is Not Wired.
+pub fn irq_stats(irq: usize) -> Option<IrqStats> {
+    if irq >= IRQ_COUNT {
+        return None;
+    }
     no_interrupt!({
-        //crate::print!("Handler Set To {:p} from {:p}\n", handler as *const u8, default_handler as *const u8);
-        let mut handlers = HANDLERS.lock();
-        handlers[system_index(irq)] = handler;
-        //crate::print!("Handler Set To {:p} from {:p}\n", handlers[system_index(irq)] as *const u8, default_handler as *const u8);
-    });
+        let mut stats = STATS.lock()[irq];
+        stats.handlers = HANDLERS.lock()[irq].iter().flatten().count();
+        Some(stats)
+    })
+}
+
+/// Run Every Handler In The IRQ's Chain & Update Its Counters.
+/// Called From The Generated IRQ Handlers, Returns False For Spurious IRQs,
+/// Which Must Not Receive A Normal EOI.
+fn dispatch(irq: usize) -> bool {
+    if !apic::is_enabled() && pics::is_spurious(irq) {
+        STATS.lock()[irq].spurious += 1;
+        pics::spurious_end_of_interrupt(irq);
+        return false;
+    }
+
+    let chain = HANDLERS.lock()[irq];
+    let start = unsafe { _rdtsc() };
+    let mut result = IrqResult::NotHandled;
+    if chain[0].is_none() {
+        default_handler(irq as u8);
+    }
+    for handler in chain.iter().flatten() {
+        if handler(irq as u8) == IrqResult::Handled {
+            result = IrqResult::Handled;
+        }
+    }
+    let end = unsafe { _rdtsc() };
+
+    let mut stats = STATS.lock();
+    let stats = &mut stats[irq];
+    stats.count += 1;
+    stats.last_tick = timer::ticks();
+    stats.cycles += end.wrapping_sub(start);
+    if result == IrqResult::NotHandled {
+        stats.unhandled += 1;
+    }
+    true
 }
 
 /// Converts A IRQ Into A System Interrupt Index
@@ -74,13 +200,15 @@ pub fn system_index(irq: usize) -> usize {
 macro_rules! gen_irq {
     ($handler:ident, $irq:expr) => {
         /// PRE-GENERATED IRQ HANDLER
-        pub extern "x86-interrupt" fn $handler(_stack_frame: InterruptStackFrame) {
-            let handlers = HANDLERS.lock();
-            handlers[system_index($irq)]($irq);
-            unsafe {
-                crate::sys::interrupt::pics::PICS
-                    .lock()
-                    .notify_end_of_interrupt(system_index($irq) as u8);
+        pub extern "x86-interrupt" fn $handler(stack_frame: InterruptStackFrame) {
+            if dispatch($irq) {
+                end_of_interrupt($irq);
+                // A Tick May Have Ended The Time Slice, Switch Only Once The EOI Is Sent.
+                $crate::sys::thread::preempt();
+                // Programs That Never Make A System Call Can Still Be Killed.
+                if stack_frame.code_segment & 0b11 == 0b11 {
+                    $crate::sys::user::check_killed();
+                }
             }
         }
     };
@@ -92,5 +220,13 @@ gen_irq!(irq_2, 2);
 gen_irq!(irq_3, 3);
 gen_irq!(irq_4, 4);
 gen_irq!(irq_5, 5);
+gen_irq!(irq_6, 6);
+gen_irq!(irq_7, 7);
+gen_irq!(irq_8, 8);
+gen_irq!(irq_9, 9);
+gen_irq!(irq_10, 10);
+gen_irq!(irq_11, 11);
+gen_irq!(irq_12, 12);
+gen_irq!(irq_13, 13);
 gen_irq!(irq_14, 14);
 gen_irq!(irq_15, 15);
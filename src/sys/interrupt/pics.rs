@@ -1,17 +1,67 @@
 //! Utilities For The 8259 PICs.
 //! Thanks To: https://github.com/vinc/moros/blob/trunk/src/sys/pic.rs
 
+use bit_field::BitField;
 use pic8259::ChainedPics;
 use spin::Mutex;
+use x86_64::instructions::port::Port;
 
-use super::idt::{PIC1_OFFSET, PIC2_OFFSET};
+use super::idt::{system_index, PIC1_OFFSET, PIC2_OFFSET};
 /// Represents The two 8259 Programmable Interrupt Controllers present in the x86 Architecture.
 pub static PICS: Mutex<ChainedPics> =
     Mutex::new(unsafe { ChainedPics::new(PIC1_OFFSET as u8, PIC2_OFFSET as u8) });
 
+const PIC1_COMMAND: u16 = 0x20;
+const PIC1_DATA: u16 = 0x21;
+const PIC2_COMMAND: u16 = 0xA0;
+const PIC2_DATA: u16 = 0xA1;
+/// OCW3, The Next Read From The Command Port Returns The In-Service Register.
+const READ_ISR: u8 = 0x0B;
+
 /// Initialize The PICS.
 pub fn init() {
     unsafe {
         PICS.lock().initialize();
     }
 }
+
+/// Mask Every IRQ On Both PICs, Used Once The APIC Takes Over.
+/// The PICs Stay Remapped So Stray Spurious IRQs Still Land On Their Own Vectors.
+pub fn disable() {
+    let mut pic1: Port<u8> = Port::new(PIC1_DATA);
+    let mut pic2: Port<u8> = Port::new(PIC2_DATA);
+    unsafe {
+        pic1.write(0xFF);
+        pic2.write(0xFF);
+    }
+}
+
+/// Read The Combined In-Service Register, Bit N Is Set If IRQ N Is Being Serviced.
+pub fn read_isr() -> u16 {
+    let mut pic1: Port<u8> = Port::new(PIC1_COMMAND);
+    let mut pic2: Port<u8> = Port::new(PIC2_COMMAND);
+    unsafe {
+        pic1.write(READ_ISR);
+        pic2.write(READ_ISR);
+        ((pic2.read() as u16) << 8) | pic1.read() as u16
+    }
+}
+
+/// Returns True If The IRQ Is A Spurious IRQ 7 Or 15.
+/// A Spurious IRQ Arrives Without Its In-Service Bit Set.
+pub fn is_spurious(irq: usize) -> bool {
+    match irq {
+        7 | 15 => !read_isr().get_bit(irq),
+        _ => false,
+    }
+}
+
+/// Acknowledge A Spurious IRQ.
+/// IRQ 7 Needs No EOI, IRQ 15 Needs One Sent To The Master Only, As It Came Through IRQ 2.
+pub fn spurious_end_of_interrupt(irq: usize) {
+    if irq == 15 {
+        unsafe {
+            PICS.lock().notify_end_of_interrupt(system_index(2) as u8);
+        }
+    }
+}
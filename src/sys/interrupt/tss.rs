@@ -1,7 +1,110 @@
 //! Utility Functions For Interacting With TSS.
 
+use core::ptr::{addr_of, addr_of_mut};
+
+use x86_64::instructions::segmentation::{Segment, DS, ES, SS};
+use x86_64::structures::gdt::Descriptor;
+use x86_64::structures::paging::Page;
+use x86_64::structures::tss::TaskStateSegment;
+use x86_64::VirtAddr;
+
+use super::gdt;
+use crate::sys::mem::{guard::{self, GuardKind}, mapper};
+use crate::KResult;
+
+/// The IST Entry Used By The Double Fault Handler.
+pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+/// The IST Entry Used By The Page Fault Handler.
+pub const PAGE_FAULT_IST_INDEX: u16 = 1;
+
+/// The Size Of Each Interrupt Stack. 20KiB
+pub const STACK_SIZE: usize = 4096 * 5;
+const GUARD_SIZE: usize = 4096;
+
+/// A Stack With Room For A Guard Page Below It, Unmapped By [guard_stacks].
+#[repr(C, align(4096))]
+struct Stack {
+    guard: [u8; GUARD_SIZE],
+    stack: [u8; STACK_SIZE],
+}
+
+impl Stack {
+    const fn new() -> Self {
+        Self { guard: [0; GUARD_SIZE], stack: [0; STACK_SIZE] }
+    }
+}
+
+static mut DOUBLE_FAULT_STACK: Stack = Stack::new();
+static mut PAGE_FAULT_STACK: Stack = Stack::new();
+static mut PRIVILEGE_STACK: Stack = Stack::new();
+
+/// Returns The Top Of The Stack, Stacks Grow Downwards.
+fn stack_top(stack: &'static Stack) -> VirtAddr {
+    VirtAddr::from_ptr(stack.stack.as_ptr()) + STACK_SIZE
+}
+
+/// The Kernel's Task State Segment.
+/// [set_privilege_stack] Writes It After The CPU Has Loaded It, So It Is Only
+/// Ever Accessed Through Raw Pointers.
+static mut TSS: TaskStateSegment = TaskStateSegment::new();
+
+/// Point The TSS At The Interrupt Stacks & Returns Its GDT Descriptor.
+/// Called Once, While The GDT Is Built.
+pub fn descriptor() -> Descriptor {
+    let mut tss = TaskStateSegment::new();
+    unsafe {
+        tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = stack_top(&DOUBLE_FAULT_STACK);
+        tss.interrupt_stack_table[PAGE_FAULT_IST_INDEX as usize] = stack_top(&PAGE_FAULT_STACK);
+        tss.privilege_stack_table[0] = stack_top(&PRIVILEGE_STACK);
+        addr_of_mut!(TSS).write(tss);
+        // Only The Address Is Taken, The Reference Doesn't Outlive This Call.
+        Descriptor::tss_segment(&*addr_of!(TSS))
+    }
+}
+
+/// Unmap The Guard Page Below Each Interrupt Stack.
+/// Needs The Mapper, So It Runs Once Memory Is Initialized.
+pub fn guard_stacks() -> KResult<()> {
+    let stacks: [(&'static Stack, &'static str); 3] = unsafe {
+        [
+            (&DOUBLE_FAULT_STACK, "Double Fault Handler"),
+            (&PAGE_FAULT_STACK, "Page Fault Handler"),
+            (&PRIVILEGE_STACK, "Ring 0 Entry"),
+        ]
+    };
+    for (stack, owner) in stacks {
+        let guard = VirtAddr::from_ptr(stack.guard.as_ptr());
+        // The Frame Belongs To The Kernel Image, So It Is Dropped Rather Than Freed.
+        mapper::unmap(Page::containing_address(guard))?;
+        guard::register(guard, 1, GuardKind::Stack, owner)?;
+    }
+    Ok(())
+}
+
 /// Set The TSS Segments To Go Into KernelMode
-pub unsafe fn set_kernel_segments() {}
+pub unsafe fn set_kernel_segments() {
+    let selectors = gdt::selectors();
+    DS::set_reg(selectors.kernel_data);
+    ES::set_reg(selectors.kernel_data);
+    SS::set_reg(selectors.kernel_data);
+}
 
 /// Set The TSS Segments To Go Into UserMode
-pub unsafe fn set_usermode_segments() {}
+/// Only DS & ES, CS & SS Are Loaded By `iretq` / `sysretq` On The Way Down.
+pub unsafe fn set_usermode_segments() {
+    let selectors = gdt::selectors();
+    DS::set_reg(selectors.user_data);
+    ES::set_reg(selectors.user_data);
+}
+
+/// Returns The Stack The CPU Switches To When Entering Ring 0 From Ring 3.
+pub fn privilege_stack() -> VirtAddr {
+    // The TSS Is Packed, So Its Fields Can't Be Referenced Directly.
+    unsafe { addr_of!(TSS.privilege_stack_table[0]).read_unaligned() }
+}
+
+/// Set The Stack The CPU Switches To When Entering Ring 0 From Ring 3.
+/// The CPU Only Reads It On Privilege Changes, So It Is Written In Place.
+pub fn set_privilege_stack(top: VirtAddr) {
+    unsafe { addr_of_mut!(TSS.privilege_stack_table[0]).write_unaligned(top) }
+}
@@ -0,0 +1,130 @@
+//! Program Loading.
+//! Loaders Read A Program From MFS Into A Fresh [Process], Then [exec] Runs It In
+//! Ring 3 On The Calling Thread.
+
+pub mod elf;
+pub mod flat;
+pub mod reloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::arch::x86_64::_rdtsc;
+
+use x86_64::VirtAddr;
+
+use super::mem::address_space::AddressSpace;
+use super::storage::mfs::{self, api::FileIO};
+use super::user::{self, Process};
+use crate::KResult;
+
+/// Auxiliary Vector Entry Types.
+pub const AT_NULL: u64 = 0;
+pub const AT_PHDR: u64 = 3;
+pub const AT_PHENT: u64 = 4;
+pub const AT_PHNUM: u64 = 5;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_ENTRY: u64 = 9;
+pub const AT_RANDOM: u64 = 25;
+
+/// A Loaded Program, Ready To [run](Image::run).
+#[derive(Debug)]
+pub struct Image {
+    pub process: Process,
+    pub entry: VirtAddr,
+    pub stack: VirtAddr,
+}
+
+impl Image {
+    /// Run The Program On The Calling Thread, Returns Its Exit Code.
+    pub fn run(self) -> KResult<i64> {
+        user::execute(self.process, self.entry, self.stack)
+    }
+}
+
+/// Read A Whole File From MFS.
+pub fn read_file(path: &str) -> KResult<Vec<u8>> {
+    let mut file = mfs::open_file(path).ok_or("No Such File")?;
+    let mut data = vec![0; file.size()];
+    let read = file.read(&mut data).map_err(|_| "Failed To Read File")?;
+    data.truncate(read);
+    Ok(data)
+}
+
+/// Load The Program At `path`, See [load_bytes].
+pub fn load(path: &str, args: &[&str], env: &[&str]) -> KResult<Image> {
+    load_bytes(&read_file(path)?, args, env)
+}
+
+/// Load A Program From Memory, Anything That Isn't ELF Is Loaded As A [flat] Binary.
+/// The Process Is Named After The First Argument.
+pub fn load_bytes(data: &[u8], args: &[&str], env: &[&str]) -> KResult<Image> {
+    let mut image = if elf::is_elf(data) {
+        elf::load(data, args, env)?
+    } else {
+        flat::load(data, args, env)?
+    };
+    if let Some(name) = args.first() {
+        image.process.set_name(name);
+    }
+    Ok(image)
+}
+
+/// Load & Run The Program At `path`, Returns Its Exit Code.
+pub fn exec(path: &str, args: &[&str], env: &[&str]) -> KResult<i64> {
+    load(path, args, env)?.run()
+}
+
+/// Write The Initial Stack Below `top`, Returns The Stack Pointer.
+/// The Layout Follows The System V ABI, From The Stack Pointer Up: `argc`, The
+/// `argv` & `envp` Pointers Each Ending In Null, Then The Auxiliary Vector.
+pub fn build_stack(
+    space: &mut AddressSpace,
+    top: VirtAddr,
+    args: &[&str],
+    env: &[&str],
+    auxv: &[(u64, u64)],
+) -> KResult<VirtAddr> {
+    let mut sp = top.as_u64();
+    let mut push_bytes = |space: &mut AddressSpace, bytes: &[u8], nul: bool| -> KResult<u64> {
+        sp -= bytes.len() as u64 + nul as u64;
+        space.write(VirtAddr::new(sp), bytes)?;
+        if nul {
+            space.write(VirtAddr::new(sp + bytes.len() as u64), &[0])?;
+        }
+        Ok(sp)
+    };
+
+    let mut envp = Vec::with_capacity(env.len());
+    for var in env {
+        envp.push(push_bytes(space, var.as_bytes(), true)?);
+    }
+    let mut argv = Vec::with_capacity(args.len());
+    for arg in args {
+        argv.push(push_bytes(space, arg.as_bytes(), true)?);
+    }
+    // Not Random, But Different On Every Run.
+    let seed = unsafe { _rdtsc() };
+    let mut random = [0; 16];
+    random[..8].copy_from_slice(&seed.to_le_bytes());
+    random[8..].copy_from_slice(&seed.rotate_left(29).wrapping_mul(0x9E37_79B9_7F4A_7C15).to_le_bytes());
+    let random = push_bytes(space, &random, false)?;
+
+    let mut words = Vec::new();
+    words.push(argv.len() as u64);
+    words.extend_from_slice(&argv);
+    words.push(0);
+    words.extend_from_slice(&envp);
+    words.push(0);
+    for &(key, value) in auxv {
+        words.push(key);
+        words.push(value);
+    }
+    words.extend_from_slice(&[AT_RANDOM, random, AT_NULL, 0]);
+
+    // The Stack Pointer Must Be 16 Byte Aligned On Entry.
+    let size = words.len() as u64 * 8;
+    let sp = (sp - size) & !0xF;
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    space.write(VirtAddr::new(sp), &bytes)?;
+    Ok(VirtAddr::new(sp))
+}
@@ -0,0 +1,395 @@
+//! ELF64 Parsing & Loading.
+//! Only Little Endian x86-64 Files Are Accepted, Every Offset & Size Is Checked
+//! Against The File Before Use.
+
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use x86_64::structures::paging::{Page, PageTableFlags, Size4KiB};
+use x86_64::VirtAddr;
+
+use super::{build_stack, reloc, Image, AT_ENTRY, AT_PAGESZ, AT_PHDR, AT_PHENT, AT_PHNUM};
+use crate::sys::mem::address_space::{self, AddressSpace};
+use crate::sys::user::{Personality, Process, USER_STACK_PAGES};
+use crate::KResult;
+
+pub const MAGIC: [u8; 4] = *b"\x7fELF";
+const CLASS_64: u8 = 2;
+const DATA_LITTLE_ENDIAN: u8 = 1;
+pub const MACHINE_X86_64: u16 = 0x3E;
+
+/// Object File Types.
+pub const ET_REL: u16 = 1;
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+
+/// Program Header Types.
+pub const PT_LOAD: u32 = 1;
+pub const PT_DYNAMIC: u32 = 2;
+pub const PT_INTERP: u32 = 3;
+pub const PT_PHDR: u32 = 6;
+
+/// Program Header Flags.
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+
+const HEADER_SIZE: usize = 64;
+const PROGRAM_HEADER_SIZE: usize = 56;
+const SECTION_HEADER_SIZE: usize = 64;
+const PAGE_SIZE: u64 = Page::<Size4KiB>::SIZE;
+
+fn read_u16(data: &[u8], offset: usize) -> KResult<u16> {
+    let bytes = data.get(offset..offset + 2).ok_or("ELF Truncated")?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> KResult<u32> {
+    let bytes = data.get(offset..offset + 4).ok_or("ELF Truncated")?;
+    let mut buf = [0; 4];
+    buf.copy_from_slice(bytes);
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> KResult<u64> {
+    let bytes = data.get(offset..offset + 8).ok_or("ELF Truncated")?;
+    let mut buf = [0; 8];
+    buf.copy_from_slice(bytes);
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// `offset + len` As A Range Inside `data`.
+fn range(data: &[u8], offset: u64, len: u64) -> KResult<core::ops::Range<usize>> {
+    let end = offset.checked_add(len).ok_or("ELF Offset Overflows")?;
+    if end > data.len() as u64 {
+        return Err("ELF Truncated");
+    }
+    Ok(offset as usize..end as usize)
+}
+
+/// Returns True If `data` Starts With The ELF Magic.
+pub fn is_elf(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// The File Header.
+#[derive(Debug, Clone, Copy)]
+pub struct Header {
+    pub kind: u16,
+    pub machine: u16,
+    pub entry: u64,
+    pub phoff: u64,
+    pub shoff: u64,
+    pub phentsize: u16,
+    pub phnum: u16,
+    pub shentsize: u16,
+    pub shnum: u16,
+    pub shstrndx: u16,
+}
+
+impl Header {
+    /// The Name Of The File Type.
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            ET_REL => "Relocatable",
+            ET_EXEC => "Executable",
+            ET_DYN => "Shared Object",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// A Segment, Describing Part Of The Process Image.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgramHeader {
+    pub kind: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+    pub align: u64,
+}
+
+impl ProgramHeader {
+    /// The Page Flags For The Segment's Permissions.
+    pub fn page_flags(&self) -> PageTableFlags {
+        let mut flags = PageTableFlags::empty();
+        if self.flags & PF_W != 0 {
+            flags |= PageTableFlags::WRITABLE;
+        }
+        if self.flags & PF_X == 0 {
+            flags |= PageTableFlags::NO_EXECUTE;
+        }
+        flags
+    }
+}
+
+/// The Permissions As `RWX`.
+impl Display for ProgramHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bit = |flag, c| if self.flags & flag != 0 { c } else { '-' };
+        write!(f, "{}{}{}", bit(PF_R, 'R'), bit(PF_W, 'W'), bit(PF_X, 'X'))
+    }
+}
+
+/// A Section, Used By Linkers & Relocatable Objects.
+#[derive(Debug, Clone, Copy)]
+pub struct SectionHeader {
+    pub name: u32,
+    pub kind: u32,
+    pub flags: u64,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+    pub info: u32,
+    pub align: u64,
+    pub entsize: u64,
+}
+
+/// A Parsed ELF64 File.
+#[derive(Debug, Clone, Copy)]
+pub struct Elf<'a> {
+    data: &'a [u8],
+    header: Header,
+}
+
+impl<'a> Elf<'a> {
+    /// Validate & Parse The File Header.
+    pub fn parse(data: &'a [u8]) -> KResult<Self> {
+        if data.len() < HEADER_SIZE || !is_elf(data) {
+            return Err("Not An ELF File");
+        }
+        if data[4] != CLASS_64 {
+            return Err("Not A 64 Bit ELF File");
+        }
+        if data[5] != DATA_LITTLE_ENDIAN {
+            return Err("Not A Little Endian ELF File");
+        }
+        let header = Header {
+            kind: read_u16(data, 16)?,
+            machine: read_u16(data, 18)?,
+            entry: read_u64(data, 24)?,
+            phoff: read_u64(data, 32)?,
+            shoff: read_u64(data, 40)?,
+            phentsize: read_u16(data, 54)?,
+            phnum: read_u16(data, 56)?,
+            shentsize: read_u16(data, 58)?,
+            shnum: read_u16(data, 60)?,
+            shstrndx: read_u16(data, 62)?,
+        };
+        if header.machine != MACHINE_X86_64 {
+            return Err("Not An x86-64 ELF File");
+        }
+        if header.phnum > 0 {
+            if (header.phentsize as usize) < PROGRAM_HEADER_SIZE {
+                return Err("Bad Program Header Size");
+            }
+            range(data, header.phoff, header.phentsize as u64 * header.phnum as u64)?;
+        }
+        if header.shnum > 0 {
+            if (header.shentsize as usize) < SECTION_HEADER_SIZE {
+                return Err("Bad Section Header Size");
+            }
+            range(data, header.shoff, header.shentsize as u64 * header.shnum as u64)?;
+        }
+        Ok(Self { data, header })
+    }
+
+    /// The File Header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// The Raw File.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// The Program Header At `index`.
+    pub fn program_header(&self, index: usize) -> KResult<ProgramHeader> {
+        if index >= self.header.phnum as usize {
+            return Err("No Such Program Header");
+        }
+        let at = self.header.phoff as usize + index * self.header.phentsize as usize;
+        Ok(ProgramHeader {
+            kind: read_u32(self.data, at)?,
+            flags: read_u32(self.data, at + 4)?,
+            offset: read_u64(self.data, at + 8)?,
+            vaddr: read_u64(self.data, at + 16)?,
+            filesz: read_u64(self.data, at + 32)?,
+            memsz: read_u64(self.data, at + 40)?,
+            align: read_u64(self.data, at + 48)?,
+        })
+    }
+
+    /// Every Program Header, The Table Was Bounds Checked By [parse](Elf::parse).
+    pub fn program_headers(&self) -> impl Iterator<Item = ProgramHeader> + '_ {
+        (0..self.header.phnum as usize).filter_map(move |i| self.program_header(i).ok())
+    }
+
+    /// The Section Header At `index`.
+    pub fn section_header(&self, index: usize) -> KResult<SectionHeader> {
+        if index >= self.header.shnum as usize {
+            return Err("No Such Section Header");
+        }
+        let at = self.header.shoff as usize + index * self.header.shentsize as usize;
+        Ok(SectionHeader {
+            name: read_u32(self.data, at)?,
+            kind: read_u32(self.data, at + 4)?,
+            flags: read_u64(self.data, at + 8)?,
+            addr: read_u64(self.data, at + 16)?,
+            offset: read_u64(self.data, at + 24)?,
+            size: read_u64(self.data, at + 32)?,
+            link: read_u32(self.data, at + 40)?,
+            info: read_u32(self.data, at + 44)?,
+            align: read_u64(self.data, at + 48)?,
+            entsize: read_u64(self.data, at + 56)?,
+        })
+    }
+
+    /// Every Section Header.
+    pub fn section_headers(&self) -> impl Iterator<Item = SectionHeader> + '_ {
+        (0..self.header.shnum as usize).filter_map(move |i| self.section_header(i).ok())
+    }
+
+    /// The Contents Of A Section, Empty For `SHT_NOBITS`.
+    pub fn section_data(&self, section: &SectionHeader) -> KResult<&'a [u8]> {
+        const SHT_NOBITS: u32 = 8;
+        if section.kind == SHT_NOBITS {
+            return Ok(&[]);
+        }
+        Ok(&self.data[range(self.data, section.offset, section.size)?])
+    }
+
+    /// Read A Null Terminated String From The String Table Section `table`.
+    pub fn string(&self, table: usize, offset: u32) -> Option<&'a str> {
+        let table = self.section_data(&self.section_header(table).ok()?).ok()?;
+        let bytes = table.get(offset as usize..)?;
+        let end = bytes.iter().position(|&b| b == 0)?;
+        core::str::from_utf8(&bytes[..end]).ok()
+    }
+
+    /// The Name Of A Section.
+    pub fn section_name(&self, section: &SectionHeader) -> Option<&'a str> {
+        self.string(self.header.shstrndx as usize, section.name)
+    }
+
+    /// The Bytes Of A Segment Stored In The File, `filesz` Long.
+    pub fn segment_data(&self, segment: &ProgramHeader) -> KResult<&'a [u8]> {
+        Ok(&self.data[range(self.data, segment.offset, segment.filesz)?])
+    }
+
+    /// The Virtual Address Of The Program Headers, If A Segment Loads Them.
+    pub fn program_headers_addr(&self) -> Option<u64> {
+        let phoff = self.header.phoff;
+        self.program_headers().find_map(|ph| match ph.kind {
+            PT_PHDR => Some(ph.vaddr),
+            PT_LOAD if ph.offset <= phoff && phoff < ph.offset.saturating_add(ph.filesz) => Some(ph.vaddr + phoff - ph.offset),
+            _ => None,
+        })
+    }
+}
+
+/// Combine The Flags Of Two Segments Sharing A Page.
+fn merge_flags(a: PageTableFlags, b: PageTableFlags) -> PageTableFlags {
+    let mut flags = (a | b) & PageTableFlags::WRITABLE;
+    if a.contains(PageTableFlags::NO_EXECUTE) && b.contains(PageTableFlags::NO_EXECUTE) {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    flags
+}
+
+/// Map `[start, start + len)` With `flags`, Pages Shared With An Earlier Segment Get Both Permissions.
+pub fn map_segment(space: &mut AddressSpace, start: u64, len: u64, flags: PageTableFlags) -> KResult<()> {
+    if len == 0 {
+        return Ok(());
+    }
+    if !address_space::is_user_range(start, len) {
+        return Err("Segment Outside The User Region");
+    }
+    let first = Page::<Size4KiB>::containing_address(VirtAddr::new(start));
+    let last = Page::<Size4KiB>::containing_address(VirtAddr::new(start + len - 1));
+    for page in Page::range_inclusive(first, last) {
+        match space.translate(page.start_address()) {
+            Some((_, old)) if old.contains(PageTableFlags::USER_ACCESSIBLE) => {
+                space.protect(page.start_address(), PAGE_SIZE, merge_flags(old, flags))?;
+            }
+            _ => {
+                space.map(page, flags)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Map Every `PT_LOAD` Segment Of An Executable Into `process`, Returns The Entry Point.
+/// The Program Break Is Placed After The Highest Segment.
+pub fn load_segments(elf: &Elf, process: &mut Process) -> KResult<VirtAddr> {
+    let header = elf.header();
+    match header.kind {
+        ET_EXEC => {}
+        ET_DYN => return Err("Position Independent Executables Are Not Supported"),
+        ET_REL => return Err("Relocatable Objects Have No Segments"),
+        _ => return Err("Not An Executable"),
+    }
+    let mut end = 0;
+    let mut loaded = false;
+    for segment in elf.program_headers() {
+        match segment.kind {
+            PT_LOAD => {}
+            PT_INTERP | PT_DYNAMIC => return Err("Dynamically Linked Programs Are Not Supported"),
+            _ => continue,
+        }
+        // Nothing To Map, Nor To Place The Break After.
+        if segment.memsz == 0 && segment.filesz == 0 {
+            continue;
+        }
+        if segment.memsz < segment.filesz {
+            return Err("Segment Smaller Than Its File Data");
+        }
+        if segment.align > 1 && segment.vaddr % segment.align != segment.offset % segment.align {
+            return Err("Misaligned Segment");
+        }
+        let data = elf.segment_data(&segment)?;
+        map_segment(process.space_mut(), segment.vaddr, segment.memsz, segment.page_flags())?;
+        process.space_mut().write(VirtAddr::new(segment.vaddr), data)?;
+        end = end.max(segment.vaddr + segment.memsz);
+        loaded = true;
+    }
+    if !loaded {
+        return Err("No Loadable Segments");
+    }
+    if !process.space().can_access(header.entry, 1, false) {
+        return Err("Entry Point Is Not Mapped");
+    }
+    process.set_break(VirtAddr::new(end));
+    Ok(VirtAddr::new(header.entry))
+}
+
+/// Load An ELF64 Executable Or Relocatable Object Into A Fresh Process With A
+/// Stack Holding `args` & `env`. ELF Programs Are Assumed To Be Built For Linux.
+pub fn load(data: &[u8], args: &[&str], env: &[&str]) -> KResult<Image> {
+    let elf = Elf::parse(data)?;
+    let mut process = Process::new()?;
+    process.set_personality(Personality::Linux);
+    let entry = match elf.header().kind {
+        ET_REL => reloc::load_object(&elf, &mut process)?,
+        _ => load_segments(&elf, &mut process)?,
+    };
+
+    let mut auxv = Vec::new();
+    if let Some(phdr) = elf.program_headers_addr() {
+        auxv.extend_from_slice(&[
+            (AT_PHDR, phdr),
+            (AT_PHENT, elf.header().phentsize as u64),
+            (AT_PHNUM, elf.header().phnum as u64),
+        ]);
+    }
+    auxv.extend_from_slice(&[(AT_PAGESZ, PAGE_SIZE), (AT_ENTRY, entry.as_u64())]);
+
+    let top = process.map_stack(USER_STACK_PAGES)?;
+    let stack = build_stack(process.space_mut(), top, args, env, &auxv)?;
+    Ok(Image { process, entry, stack })
+}
@@ -0,0 +1,37 @@
+//! Flat Binaries.
+//! The Whole File Is Raw Machine Code & Data, Mapped Readable, Writable &
+//! Executable At [FLAT_BASE] & Entered At Its First Byte. Programs Use The Native
+//! [syscall](crate::sys::user::syscall) ABI & Find `argc`, `argv` & `envp` On The Stack.
+
+use x86_64::structures::paging::PageTableFlags;
+use x86_64::VirtAddr;
+
+use super::{build_stack, Image, AT_ENTRY, AT_PAGESZ};
+use crate::sys::user::{Process, USER_STACK_PAGES};
+use crate::KResult;
+
+/// Where Flat Binaries Are Loaded & Entered.
+pub const FLAT_BASE: u64 = 0x4000_0000;
+/// The Largest Flat Binary, 16MiB.
+pub const MAX_FLAT_SIZE: usize = 16 << 20;
+
+/// Load A Flat Binary Into A Fresh Process With A Stack Holding `args` & `env`.
+pub fn load(data: &[u8], args: &[&str], env: &[&str]) -> KResult<Image> {
+    if data.is_empty() {
+        return Err("Empty Binary");
+    }
+    if data.len() > MAX_FLAT_SIZE {
+        return Err("Binary Too Large");
+    }
+    let mut process = Process::new()?;
+    let base = VirtAddr::new(FLAT_BASE);
+    let size = data.len() as u64;
+    process.space_mut().map_range(base, size, PageTableFlags::WRITABLE)?;
+    process.space_mut().write(base, data)?;
+    process.set_break(base + size);
+
+    let top = process.map_stack(USER_STACK_PAGES)?;
+    let auxv = [(AT_PAGESZ, 4096), (AT_ENTRY, FLAT_BASE)];
+    let stack = build_stack(process.space_mut(), top, args, env, &auxv)?;
+    Ok(Image { process, entry: base, stack })
+}
@@ -0,0 +1,196 @@
+//! Running Relocatable Objects.
+//! A Minimal Static Linker For Single `ET_REL` Files, Such As The Output Of
+//! `nasm -f elf64`. Each Allocated Section Gets Its Own Pages Above
+//! [OBJECT_BASE], Then `.rela` Sections Are Applied In Place.
+//! The Base Stays Below 4GiB, So 32 Bit Absolute Relocations Fit.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use x86_64::structures::paging::{Page, PageTableFlags, Size4KiB};
+use x86_64::VirtAddr;
+
+use super::elf::{map_segment, Elf, SectionHeader};
+use crate::sys::user::Process;
+use crate::KResult;
+
+/// Where The First Section Of An Object Is Placed.
+pub const OBJECT_BASE: u64 = 0x4000_0000;
+/// The Symbols Tried As The Entry Point, In Order.
+pub const ENTRY_SYMBOLS: [&str; 2] = ["_start", "main"];
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_RELA: u32 = 4;
+const SHT_REL: u32 = 9;
+
+const SHF_WRITE: u64 = 1;
+const SHF_ALLOC: u64 = 2;
+const SHF_EXECINSTR: u64 = 4;
+
+const SHN_UNDEF: u16 = 0;
+const SHN_ABS: u16 = 0xFFF1;
+const SHN_COMMON: u16 = 0xFFF2;
+
+const STB_LOCAL: u8 = 0;
+
+const R_X86_64_NONE: u32 = 0;
+const R_X86_64_64: u32 = 1;
+const R_X86_64_PC32: u32 = 2;
+const R_X86_64_PLT32: u32 = 4;
+const R_X86_64_32: u32 = 10;
+const R_X86_64_32S: u32 = 11;
+const R_X86_64_PC64: u32 = 24;
+
+const SYMBOL_SIZE: usize = 24;
+const RELA_SIZE: usize = 24;
+const PAGE_SIZE: u64 = Page::<Size4KiB>::SIZE;
+
+/// A Symbol Table Entry.
+#[derive(Debug, Clone, Copy)]
+struct Symbol {
+    name: u32,
+    info: u8,
+    section: u16,
+    value: u64,
+}
+
+fn symbols(elf: &Elf, table: &SectionHeader) -> KResult<Vec<Symbol>> {
+    let data = elf.section_data(table)?;
+    Ok(data
+        .chunks_exact(SYMBOL_SIZE)
+        .map(|entry| {
+            let mut value = [0; 8];
+            value.copy_from_slice(&entry[8..16]);
+            Symbol {
+                name: u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]),
+                info: entry[4],
+                section: u16::from_le_bytes([entry[6], entry[7]]),
+                value: u64::from_le_bytes(value),
+            }
+        })
+        .collect())
+}
+
+/// The Address A Symbol Ended Up At.
+fn resolve(symbol: &Symbol, addresses: &[Option<u64>]) -> KResult<u64> {
+    match symbol.section {
+        SHN_UNDEF => Err("Undefined Symbol, Objects Can't Be Linked Against Anything"),
+        SHN_ABS => Ok(symbol.value),
+        SHN_COMMON => Err("Common Symbols Are Not Supported"),
+        section => {
+            let base = addresses.get(section as usize).copied().flatten().ok_or("Symbol In Unloaded Section")?;
+            base.checked_add(symbol.value).ok_or("Symbol Out Of Range")
+        }
+    }
+}
+
+fn section_flags(section: &SectionHeader) -> PageTableFlags {
+    let mut flags = PageTableFlags::empty();
+    if section.flags & SHF_WRITE != 0 {
+        flags |= PageTableFlags::WRITABLE;
+    }
+    if section.flags & SHF_EXECINSTR == 0 {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    flags
+}
+
+/// Patch The Section Loaded At `target` & `size` Bytes Long, `offset` Bytes In, With `value + addend`.
+fn relocate(process: &mut Process, kind: u32, (target, size): (u64, u64), offset: u64, value: u64, addend: i64) -> KResult<()> {
+    if offset >= size {
+        return Err("Relocation Outside Its Section");
+    }
+    let place = target + offset;
+    let value = value.wrapping_add(addend as u64);
+    let pc_relative = value.wrapping_sub(place) as i64;
+    let bytes: Vec<u8> = match kind {
+        R_X86_64_NONE => return Ok(()),
+        R_X86_64_64 => value.to_le_bytes().to_vec(),
+        R_X86_64_PC64 => pc_relative.to_le_bytes().to_vec(),
+        R_X86_64_32 => {
+            let value = u32::try_from(value).map_err(|_| "Relocation Out Of Range")?;
+            value.to_le_bytes().to_vec()
+        }
+        R_X86_64_32S => {
+            let value = i32::try_from(value as i64).map_err(|_| "Relocation Out Of Range")?;
+            value.to_le_bytes().to_vec()
+        }
+        R_X86_64_PC32 | R_X86_64_PLT32 => {
+            let value = i32::try_from(pc_relative).map_err(|_| "Relocation Out Of Range")?;
+            value.to_le_bytes().to_vec()
+        }
+        _ => return Err("Unsupported Relocation Type"),
+    };
+    if offset + bytes.len() as u64 > size {
+        return Err("Relocation Outside Its Section");
+    }
+    let place = VirtAddr::try_new(place).map_err(|_| "Relocation Outside Its Section")?;
+    process.space_mut().write(place, &bytes)
+}
+
+/// Load A Relocatable Object Into `process`, Returns Its Entry Point.
+pub fn load_object(elf: &Elf, process: &mut Process) -> KResult<VirtAddr> {
+    let sections: Vec<SectionHeader> = elf.section_headers().collect();
+
+    // Place & Copy Every Allocated Section.
+    let mut addresses = vec![None; sections.len()];
+    let mut next = OBJECT_BASE;
+    for (index, section) in sections.iter().enumerate() {
+        if section.flags & SHF_ALLOC == 0 || section.size == 0 {
+            continue;
+        }
+        if section.align > PAGE_SIZE {
+            return Err("Section Alignment Too Large");
+        }
+        let start = next;
+        map_segment(process.space_mut(), start, section.size, section_flags(section))?;
+        process.space_mut().write(VirtAddr::new(start), elf.section_data(section)?)?;
+        addresses[index] = Some(start);
+        next = VirtAddr::new(start + section.size).align_up(PAGE_SIZE).as_u64();
+    }
+    if next == OBJECT_BASE {
+        return Err("No Loadable Sections");
+    }
+
+    let symtab = sections.iter().find(|s| s.kind == SHT_SYMTAB).ok_or("No Symbol Table")?;
+    let symbols = symbols(elf, symtab)?;
+
+    for section in sections.iter() {
+        match section.kind {
+            SHT_RELA => {}
+            SHT_REL => return Err("REL Relocations Are Not Supported"),
+            _ => continue,
+        }
+        // Relocations For Sections That Are Not Loaded, i.e. Debug Info, Are Skipped.
+        let target = match addresses.get(section.info as usize).copied().flatten() {
+            Some(address) => (address, sections[section.info as usize].size),
+            None => continue,
+        };
+        for entry in elf.section_data(section)?.chunks_exact(RELA_SIZE) {
+            let mut word = [0; 8];
+            word.copy_from_slice(&entry[0..8]);
+            let offset = u64::from_le_bytes(word);
+            word.copy_from_slice(&entry[8..16]);
+            let info = u64::from_le_bytes(word);
+            word.copy_from_slice(&entry[16..24]);
+            let addend = i64::from_le_bytes(word);
+
+            let symbol = symbols.get((info >> 32) as usize).ok_or("Bad Symbol Index")?;
+            let value = resolve(symbol, &addresses)?;
+            relocate(process, info as u32, target, offset, value, addend)?;
+        }
+    }
+
+    let entry = ENTRY_SYMBOLS
+        .iter()
+        .find_map(|name| {
+            symbols.iter().find(|s| {
+                s.info >> 4 != STB_LOCAL && s.section != SHN_UNDEF && elf.string(symtab.link as usize, s.name) == Some(*name)
+            })
+        })
+        .ok_or("No Entry Symbol, Expected `_start` Or `main`")?;
+    let entry = resolve(entry, &addresses)?;
+    let entry = VirtAddr::try_new(entry).map_err(|_| "Entry Point Is Not Canonical")?;
+    process.set_break(VirtAddr::new(next));
+    Ok(entry)
+}
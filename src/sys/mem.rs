@@ -4,13 +4,20 @@ use core::alloc::Layout;
 pub use x86_64::structures::paging::PageTableFlags;
 
 use bootloader::BootInfo;
-use x86_64::{structures::paging::PageTable, VirtAddr, PhysAddr};
+use x86_64::{structures::paging::{Page, PageTable, PhysFrame}, VirtAddr, PhysAddr};
 
 use crate::KResult;
 
 mod allocator;
+pub mod address_space;
+pub use allocator::{heap_limit, heap_size, set_heap_limit, stats, MemoryStats};
+#[cfg(feature = "heap_trace")]
+pub use allocator::trace;
 pub mod frame_allocator;
+pub mod guard;
 pub mod mapper;
+pub mod stack;
+pub mod vmm;
 
 pub mod buffer;
 pub mod ringbuffer;
@@ -35,7 +42,8 @@ pub(self) unsafe fn l4_page_table_at(offset: VirtAddr) -> &'static mut PageTable
 
 /// Initialize The Memory Subsystem.
 pub fn initialize(info: &'static BootInfo) -> KResult<()> {
-    allocator::initialize(info)
+    allocator::initialize(info)?;
+    guard::initialize()
 }
 
 /// Allocate Memory On The Kernel Heap.
@@ -49,7 +57,17 @@ pub unsafe fn free(ptr: *mut u8, layout: Layout) {
 }
 
 
-/// Map A Virtual Address To A Physical Address 
-pub fn map_virt(_vaddr: VirtAddr, _paddr: PhysAddr) {
-    
+/// Map The Page Containing A Virtual Address To The Frame Containing A Physical Address.
+pub fn map_virt(vaddr: VirtAddr, paddr: PhysAddr, flags: PageTableFlags) -> KResult<()> {
+    mapper::map(Page::containing_address(vaddr), PhysFrame::containing_address(paddr), flags)
+}
+
+/// Unmap The Page Containing A Virtual Address, Returns The Physical Address It Mapped.
+pub fn unmap_virt(vaddr: VirtAddr) -> KResult<PhysAddr> {
+    mapper::unmap(Page::containing_address(vaddr)).map(|frame| frame.start_address())
+}
+
+/// Set The Flags Of The Page Containing A Virtual Address.
+pub fn protect_virt(vaddr: VirtAddr, flags: PageTableFlags) -> KResult<()> {
+    mapper::protect(Page::containing_address(vaddr), flags)
 }
\ No newline at end of file
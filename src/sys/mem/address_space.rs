@@ -0,0 +1,277 @@
+//! User Address Spaces.
+//! Each Space Has Its Own Level 4 Table Sharing Every Kernel Entry, So The Kernel
+//! Stays Mapped (Supervisor Only) While User Code Runs. User Pages Live Below
+//! [USER_END], Any Kernel Table On The Way To One Is Copied Into The Space First,
+//! So User Mappings Never Leak Into Other Spaces.
+//! Kernel Top Level Entries Created After A Space Are Not Seen By It.
+
+use alloc::vec::Vec;
+use x86_64::{
+    registers::control::Cr3,
+    structures::paging::{
+        page_table::PageTableEntry, Page, PageTable, PageTableFlags, PageTableIndex, PhysFrame, Size4KiB,
+    },
+    PhysAddr, VirtAddr,
+};
+
+use super::{frame_allocator, mapper};
+use crate::KResult;
+
+/// The Lowest User Address, Keeping Null Pointer Dereferences Faulting.
+pub const USER_START: u64 = 0x40_0000;
+/// The End Of The User Region, The First 128 Top Level Entries.
+pub const USER_END: u64 = 0x4000_0000_0000;
+const PAGE_SIZE: u64 = Page::<Size4KiB>::SIZE;
+
+static mut KERNEL_L4: Option<PhysFrame> = None;
+
+/// The Kernel's Own Level 4 Table, Recorded The First Time It Is Needed,
+/// Which Always Happens During Boot Before Any Space Is Activated.
+pub fn kernel_l4() -> PhysFrame {
+    unsafe { *KERNEL_L4.get_or_insert_with(|| Cr3::read().0) }
+}
+
+fn table(frame: PhysFrame) -> &'static mut PageTable {
+    let virt = mapper::physical_memory_offset() + frame.start_address().as_u64();
+    unsafe { &mut *(virt as *mut PageTable) }
+}
+
+/// Returns True If `[addr, addr + len)` Is Inside The User Region.
+pub fn is_user_range(addr: u64, len: u64) -> bool {
+    addr >= USER_START && addr.checked_add(len).map_or(false, |end| end <= USER_END)
+}
+
+/// Switch Back To The Kernel's Page Tables.
+pub fn activate_kernel() {
+    let (current, flags) = Cr3::read();
+    if current != kernel_l4() {
+        unsafe { Cr3::write(kernel_l4(), flags) };
+    }
+}
+
+/// A Separate Set Of Page Tables For User Code.
+#[derive(Debug)]
+pub struct AddressSpace {
+    l4: PhysFrame,
+    /// Tables Owned By This Space, Freed On Drop.
+    tables: Vec<PhysFrame>,
+}
+
+impl AddressSpace {
+    /// Create A Space Containing Only The Kernel's Mappings.
+    pub fn new() -> KResult<Self> {
+        let l4 = Self::allocate_table()?;
+        table(l4).clone_from(table(kernel_l4()));
+        Ok(Self { l4, tables: Vec::new() })
+    }
+
+    fn allocate_table() -> KResult<PhysFrame> {
+        let frame = frame_allocator::allocate().ok_or("Out Of Physical Frames")?;
+        table(frame).zero();
+        Ok(frame)
+    }
+
+    /// Follow `entry` To The Next Table, Creating It Or Copying A Shared Kernel Table.
+    fn private_next(&mut self, entry: &mut PageTableEntry) -> KResult<&'static mut PageTable> {
+        let user = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE;
+        if entry.is_unused() {
+            let frame = Self::allocate_table()?;
+            self.tables.push(frame);
+            entry.set_frame(frame, user);
+            return Ok(table(frame));
+        }
+        if entry.flags().contains(PageTableFlags::HUGE_PAGE) {
+            return Err("Address Is Inside A Kernel Huge Page");
+        }
+        let frame = entry.frame().map_err(|_| "Invalid Page Table Entry")?;
+        if !self.tables.contains(&frame) {
+            let copy = Self::allocate_table()?;
+            table(copy).clone_from(table(frame));
+            self.tables.push(copy);
+            entry.set_frame(copy, entry.flags() | user);
+            return Ok(table(copy));
+        }
+        entry.set_flags(entry.flags() | user);
+        Ok(table(frame))
+    }
+
+    /// The Level 1 Entry For `page`, Creating Tables On The Way.
+    fn entry_mut(&mut self, page: Page) -> KResult<&'static mut PageTableEntry> {
+        let start = page.start_address().as_u64();
+        if !is_user_range(start, PAGE_SIZE) {
+            return Err("Address Is Outside The User Region");
+        }
+        let l4 = table(self.l4);
+        if page.p4_index() != PageTableIndex::new(0) && !table(kernel_l4())[page.p4_index()].is_unused() {
+            return Err("Address Is Reserved For The Kernel");
+        }
+        let l3 = self.private_next(&mut l4[page.p4_index()])?;
+        let l2 = self.private_next(&mut l3[page.p3_index()])?;
+        let l1 = self.private_next(&mut l2[page.p2_index()])?;
+        Ok(&mut l1[page.p1_index()])
+    }
+
+    /// The Level 1 Entry For `page`, None If A Table On The Way Is Missing.
+    fn entry(&self, page: Page) -> Option<&'static PageTableEntry> {
+        let mut current = table(self.l4);
+        for index in [page.p4_index(), page.p3_index(), page.p2_index()] {
+            let entry = &current[index];
+            if entry.is_unused() || entry.flags().contains(PageTableFlags::HUGE_PAGE) {
+                return None;
+            }
+            current = table(entry.frame().ok()?);
+        }
+        Some(&current[page.p1_index()])
+    }
+
+    /// Map `page` To A Fresh Zeroed Frame, User Accessible.
+    pub fn map(&mut self, page: Page, flags: PageTableFlags) -> KResult<PhysFrame> {
+        let entry = self.entry_mut(page)?;
+        if !entry.is_unused() {
+            return Err("Page Already Mapped");
+        }
+        let frame = frame_allocator::allocate().ok_or("Out Of Physical Frames")?;
+        table(frame).zero();
+        entry.set_frame(frame, flags | PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE);
+        self.flush(page);
+        Ok(frame)
+    }
+
+    /// Map Every Page Overlapping `[start, start + len)`, Skipping User Pages Already Mapped.
+    pub fn map_range(&mut self, start: VirtAddr, len: u64, flags: PageTableFlags) -> KResult<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        let first = Page::<Size4KiB>::containing_address(start);
+        let last = Page::<Size4KiB>::containing_address(start + (len - 1));
+        for page in Page::range_inclusive(first, last) {
+            match self.translate(page.start_address()) {
+                Some((_, mapped)) if mapped.contains(PageTableFlags::USER_ACCESSIBLE) => {}
+                _ => {
+                    self.map(page, flags)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The Level 1 Entry Of A Page Mapped By This Space.
+    fn user_entry_mut(&mut self, page: Page) -> KResult<&'static mut PageTableEntry> {
+        let entry = self.entry_mut(page)?;
+        if !entry.flags().contains(PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE) {
+            return Err("Page Not Mapped");
+        }
+        Ok(entry)
+    }
+
+    /// Unmap A User Page & Free Its Frame.
+    pub fn unmap(&mut self, page: Page) -> KResult<()> {
+        let entry = self.user_entry_mut(page)?;
+        let frame = entry.frame().map_err(|_| "Page Not Mapped")?;
+        entry.set_unused();
+        self.flush(page);
+        frame_allocator::free(frame)
+    }
+
+    /// Replace The Flags Of Every User Page Overlapping `[start, start + len)`.
+    pub fn protect(&mut self, start: VirtAddr, len: u64, flags: PageTableFlags) -> KResult<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        let first = Page::<Size4KiB>::containing_address(start);
+        let last = Page::<Size4KiB>::containing_address(start + (len - 1));
+        for page in Page::range_inclusive(first, last) {
+            let entry = self.user_entry_mut(page)?;
+            let frame = entry.frame().map_err(|_| "Page Not Mapped")?;
+            entry.set_frame(frame, flags | PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE);
+            self.flush(page);
+        }
+        Ok(())
+    }
+
+    /// The Physical Address & Flags Behind A User Address.
+    pub fn translate(&self, addr: VirtAddr) -> Option<(PhysAddr, PageTableFlags)> {
+        let entry = self.entry(Page::containing_address(addr))?;
+        let frame = entry.frame().ok()?;
+        Some((frame.start_address() + (addr.as_u64() % PAGE_SIZE), entry.flags()))
+    }
+
+    /// Returns True If Every Page In The Range Is User Accessible, & Writable If Asked.
+    pub fn can_access(&self, addr: u64, len: u64, write: bool) -> bool {
+        if !is_user_range(addr, len) {
+            return false;
+        }
+        let mut needed = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+        if write {
+            needed |= PageTableFlags::WRITABLE;
+        }
+        let end = addr + len;
+        let mut page = addr - addr % PAGE_SIZE;
+        while page < end {
+            match self.translate(VirtAddr::new(page)) {
+                Some((_, flags)) if flags.contains(needed) => page += PAGE_SIZE,
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Copy `data` Into Mapped User Memory, Works Whether Or Not The Space Is Active.
+    pub fn write(&mut self, addr: VirtAddr, data: &[u8]) -> KResult<()> {
+        let mut done = 0;
+        while done < data.len() {
+            let at = addr + done as u64;
+            let (phys, _) = self.translate(at).ok_or("Page Not Mapped")?;
+            let chunk = (PAGE_SIZE - at.as_u64() % PAGE_SIZE).min((data.len() - done) as u64) as usize;
+            let dest = (mapper::physical_memory_offset() + phys.as_u64()) as *mut u8;
+            unsafe { core::ptr::copy_nonoverlapping(data[done..].as_ptr(), dest, chunk) };
+            done += chunk;
+        }
+        Ok(())
+    }
+
+    /// Load This Space Into CR3.
+    pub fn activate(&self) {
+        let (current, flags) = Cr3::read();
+        if current != self.l4 {
+            unsafe { Cr3::write(self.l4, flags) };
+        }
+    }
+
+    /// The Frame Holding The Level 4 Table.
+    pub fn l4_frame(&self) -> PhysFrame {
+        self.l4
+    }
+
+    fn flush(&self, page: Page) {
+        if Cr3::read().0 == self.l4 {
+            x86_64::instructions::tlb::flush(page.start_address());
+        }
+    }
+}
+
+impl Drop for AddressSpace {
+    fn drop(&mut self) {
+        if Cr3::read().0 == self.l4 {
+            activate_kernel();
+        }
+        // Only Leaf Entries Marked User Were Mapped By This Space, Copied Kernel
+        // Entries Keep Pointing At Kernel Frames.
+        for &frame in self.tables.iter() {
+            for entry in table(frame).iter() {
+                let flags = entry.flags();
+                if flags.contains(PageTableFlags::USER_ACCESSIBLE) && !flags.contains(PageTableFlags::HUGE_PAGE) {
+                    if let Ok(leaf) = entry.frame() {
+                        if !self.tables.contains(&leaf) {
+                            let _ = frame_allocator::free(leaf);
+                        }
+                    }
+                }
+            }
+        }
+        for &frame in self.tables.iter() {
+            let _ = frame_allocator::free(frame);
+        }
+        let _ = frame_allocator::free(self.l4);
+    }
+}
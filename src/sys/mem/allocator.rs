@@ -1,12 +1,28 @@
 pub mod null_allocator;
 use core::alloc::{GlobalAlloc, Layout};
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 pub mod bump;
 pub mod linked_list;
+pub mod slab;
+#[cfg(feature = "heap_trace")]
+pub mod trace;
 
 pub const HEAP_START: usize = 0x_4444_4444_0000;
-pub const HEAP_SIZE: usize = 1024 * 1024 * 2; // 1MiB
+/// The Heap Mapped At Boot, 2MiB.
+pub const HEAP_SIZE: usize = 1024 * 1024 * 2;
+/// The Default Ceiling The Heap Can Grow To, 256MiB.
+pub const HEAP_DEFAULT_LIMIT: usize = 1024 * 1024 * 256;
+/// The Smallest Amount The Heap Grows By, 256KiB.
+const HEAP_GROW_STEP: usize = 1024 * 256;
+const PAGE_SIZE: usize = 4096;
 
+/// The End Of The Mapped Heap.
+static HEAP_END: AtomicUsize = AtomicUsize::new(HEAP_START);
+static HEAP_LIMIT: AtomicUsize = AtomicUsize::new(HEAP_DEFAULT_LIMIT);
+
+// Every Allocator Holds Its Lock With Interrupts Disabled. A Thread Preempted
+// While Holding It Would Leave Any Allocation Made Inside `no_interrupt!` Spinning Forever.
 #[cfg_attr(feature = "list_allocator", global_allocator)]
 static LINKED_LIST_ALLOCATOR: Locked<linked_list::LinkedListAllocator> =
     Locked::new(linked_list::LinkedListAllocator::new());
@@ -14,21 +30,148 @@ static LINKED_LIST_ALLOCATOR: Locked<linked_list::LinkedListAllocator> =
 #[cfg_attr(feature = "bump_allocator", global_allocator)]
 static BUMP: Locked<BumpAllocator> = Locked::new(BumpAllocator::new());
 
+#[cfg_attr(feature = "slab_allocator", global_allocator)]
+static SLAB: Locked<slab::SlabAllocator> = Locked::new(slab::SlabAllocator::new());
+
 #[alloc_error_handler]
 fn alloc_error_handler(layout: alloc::alloc::Layout) -> ! {
-    panic!("== Out Of Memory ==\nAttempted To Allocate {} Bytes.", layout.size());
+    panic!(
+        "== Out Of Memory ==\nAttempted To Allocate {} Bytes, Heap Is {} Of {} Bytes.",
+        layout.size(),
+        heap_size(),
+        heap_limit()
+    );
+}
+
+/// Map At Least `bytes` More Heap Directly After The Current End.
+/// Returns The Start & Size Of The New Region, None If The Ceiling Or Physical Memory Is Hit.
+/// Called With The Allocator Locked, So It Must Not Allocate.
+fn grow(bytes: usize) -> Option<(usize, usize)> {
+    let start = HEAP_END.load(Ordering::Relaxed);
+    let size = align_up(bytes.max(HEAP_GROW_STEP), PAGE_SIZE);
+    if start + size > HEAP_START + HEAP_LIMIT.load(Ordering::Relaxed) {
+        return None;
+    }
+    for addr in (start..start + size).step_by(PAGE_SIZE) {
+        let page = Page::containing_address(VirtAddr::new(addr as u64));
+        if mapper::map_alloc(page, PageTableFlags::PRESENT | PageTableFlags::WRITABLE).is_err() {
+            // Undo The Partial Growth, The Frames May Be Needed Elsewhere.
+            for addr in (start..addr).step_by(PAGE_SIZE) {
+                let _ = mapper::unmap_free(Page::containing_address(VirtAddr::new(addr as u64)));
+            }
+            return None;
+        }
+    }
+    HEAP_END.store(start + size, Ordering::Relaxed);
+    Some((start, size))
+}
+
+/// The Number Of Bytes Currently Mapped For The Heap.
+pub fn heap_size() -> usize {
+    HEAP_END.load(Ordering::Relaxed) - HEAP_START
 }
 
-#[derive(Debug, Clone, Copy)]
+/// The Most The Heap Can Grow To.
+pub fn heap_limit() -> usize {
+    HEAP_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Set The Ceiling The Heap Can Grow To, Returns Err If The Heap Is Already Larger.
+pub fn set_heap_limit(bytes: usize) -> KResult<()> {
+    if bytes < heap_size() {
+        return Err("Heap Is Already Larger Than The Limit");
+    }
+    HEAP_LIMIT.store(bytes, Ordering::Relaxed);
+    Ok(())
+}
+
+/// A Snapshot Of Heap Usage, See [stats].
+#[derive(Debug, Clone, Copy, Default)]
 pub struct MemoryStats {
+    /// Bytes Currently Allocated, As Requested By Callers.
     pub allocated: usize,
+    /// The Most Bytes Ever Allocated At Once.
+    pub peak: usize,
+    /// Live Allocations.
+    pub allocations: usize,
+    /// Allocations Made Since Boot.
+    pub total_allocations: u64,
+    /// Bytes Currently Mapped For The Heap.
+    pub heap_size: usize,
+    /// Bytes The Allocator Could Still Hand Out Without Growing.
+    pub free: usize,
+    /// The Largest Single Allocation Possible Without Growing.
+    pub largest_free: usize,
+    /// Percentage Of Free Memory Outside The Largest Free Block.
+    pub fragmentation: usize,
+}
+
+impl MemoryStats {
+    fn new(counters: &AllocCounters, free: usize, largest_free: usize) -> Self {
+        Self {
+            allocated: counters.allocated,
+            peak: counters.peak,
+            allocations: counters.allocations,
+            total_allocations: counters.total_allocations,
+            heap_size: heap_size(),
+            free,
+            largest_free,
+            fragmentation: if free == 0 { 0 } else { 100 - largest_free * 100 / free },
+        }
+    }
+}
+
+/// Usage Counters Every Allocator Backend Keeps Under Its Lock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllocCounters {
+    allocated: usize,
+    peak: usize,
+    allocations: usize,
+    total_allocations: u64,
+}
+
+impl AllocCounters {
+    pub const fn new() -> Self {
+        Self { allocated: 0, peak: 0, allocations: 0, total_allocations: 0 }
+    }
+
+    /// Record A Successful Allocation.
+    pub fn on_alloc(&mut self, ptr: *mut u8, layout: Layout) {
+        self.allocated += layout.size();
+        self.peak = self.peak.max(self.allocated);
+        self.allocations += 1;
+        self.total_allocations += 1;
+        #[cfg(feature = "heap_trace")]
+        trace::record(ptr, layout.size());
+        #[cfg(not(feature = "heap_trace"))]
+        let _ = ptr;
+    }
+
+    /// Record A Free.
+    pub fn on_free(&mut self, ptr: *mut u8, layout: Layout) {
+        self.allocated -= layout.size();
+        self.allocations -= 1;
+        #[cfg(feature = "heap_trace")]
+        trace::forget(ptr);
+        #[cfg(not(feature = "heap_trace"))]
+        let _ = ptr;
+    }
+
+    /// Bytes Currently Allocated.
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+}
+
+/// Implemented By Every Allocator Backend.
+pub trait HeapStats {
+    /// Current Usage Of The Backend.
+    fn stats(&self) -> MemoryStats;
 }
 
 use bootloader::BootInfo;
 use x86_64::{
-    structures::paging::{
-        mapper::MapToError, FrameAllocator, Mapper, Page, PageTableFlags, Size4KiB,
-    },
+    structures::paging::{Page, PageTableFlags},
     VirtAddr,
 };
 
@@ -36,12 +179,9 @@ use crate::{print, sys::terminal::Spinner, KResult, Locked};
 
 use self::bump::BumpAllocator;
 
-use super::{frame_allocator::BootInfoFrameAllocator, mapper::init_mapper};
+use super::{frame_allocator, mapper};
 
-fn init_kheap(
-    mapper: &mut impl Mapper<Size4KiB>,
-    frame_allocator: &mut impl FrameAllocator<Size4KiB>,
-) -> Result<(), MapToError<Size4KiB>> {
+fn init_kheap() -> KResult<()> {
     let mut spinner = Spinner::new();
     let page_range = {
         let heap_start = VirtAddr::new(HEAP_START as u64);
@@ -53,11 +193,7 @@ fn init_kheap(
     print!("\n");
     let mut i = 0;
     for page in page_range {
-        let frame = frame_allocator
-            .allocate_frame()
-            .ok_or(MapToError::FrameAllocationFailed)?;
-        let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
-        unsafe { mapper.map_to(page, frame, flags, frame_allocator)?.flush() };
+        mapper::map_alloc(page, PageTableFlags::PRESENT | PageTableFlags::WRITABLE)?;
         i += 1;
         if i % 64 == 0 && i > 0 {
             spinner.update();
@@ -66,17 +202,19 @@ fn init_kheap(
     }
     print!("\n");
 
+    HEAP_END.store(HEAP_START + HEAP_SIZE, Ordering::Relaxed);
     Ok(())
 }
 
 /// Initialize The Memory Subsystem.
 pub fn initialize(info: &'static BootInfo) -> KResult<()> {
-    let mut mapper = init_mapper(info);
+    mapper::init_mapper(info);
+    frame_allocator::initialize(&info.memory_map, info.physical_memory_offset)?;
+    init_kheap()?;
     unsafe {
-        let mut frame_alloc: BootInfoFrameAllocator = BootInfoFrameAllocator::from(info);
-        init_kheap(&mut mapper, &mut frame_alloc).expect("Failed To Init Kernel Heap.");
         LINKED_LIST_ALLOCATOR.lock().init(HEAP_START, HEAP_SIZE);
         BUMP.lock().init(HEAP_START, HEAP_START + HEAP_SIZE);
+        SLAB.lock().init(HEAP_START, HEAP_SIZE);
     }
 
     Ok(())
@@ -90,15 +228,25 @@ fn align_up(addr: usize, align: usize) -> usize {
 }
 
 #[cfg(feature = "bump_allocator")]
-fn current_allocator() -> &'static impl GlobalAlloc {
+fn current_allocator() -> &'static (impl GlobalAlloc + HeapStats) {
     &BUMP
 }
 
 #[cfg(feature = "list_allocator")]
-fn current_allocator() -> &'static impl GlobalAlloc {
+fn current_allocator() -> &'static (impl GlobalAlloc + HeapStats) {
     &LINKED_LIST_ALLOCATOR
 }
 
+#[cfg(feature = "slab_allocator")]
+fn current_allocator() -> &'static (impl GlobalAlloc + HeapStats) {
+    &SLAB
+}
+
+/// Usage Of The Active Heap Allocator.
+pub fn stats() -> MemoryStats {
+    crate::no_interrupt!({ current_allocator().stats() })
+}
+
 /// Allocate Memory On The Kernel Heap.
 pub unsafe fn malloc(layout: Layout) -> *mut u8 {
     let allocator = current_allocator();
@@ -3,7 +3,7 @@ use core::ptr;
 
 use crate::Locked;
 
-use super::align_up;
+use super::{align_up, AllocCounters, HeapStats, MemoryStats};
 
 #[derive(Debug)]
 pub struct BumpAllocator {
@@ -11,12 +11,14 @@ pub struct BumpAllocator {
     end: usize,
     next: usize,
     allocations: usize,
+    counters: AllocCounters,
 }
 
 impl BumpAllocator {
     pub const fn new() -> BumpAllocator {
         BumpAllocator {
             allocations: 0,
+            counters: AllocCounters::new(),
             end: 0,
             next: 0,
             start: 0,
@@ -28,35 +30,49 @@ impl BumpAllocator {
         self.start = heap_start;
         self.next = self.start;
     }
+
+    /// Move The End Of The Heap, The New Memory Must Directly Follow The Old End.
+    pub unsafe fn extend(&mut self, new_end: usize) {
+        self.end = new_end;
+    }
 }
 
 unsafe impl GlobalAlloc for Locked<BumpAllocator> {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        let mut bump = self.lock(); // get a mutable reference
+        crate::no_interrupt!({
+            let mut bump = self.lock(); // get a mutable reference
 
-        let alloc_start = align_up(bump.next, layout.align());
+            let alloc_start = align_up(bump.next, layout.align());
 
-        let alloc_end = match alloc_start.checked_add(layout.size()) {
-            Some(end) => end,
-            None => return ptr::null_mut(),
-        };
+            let alloc_end = match alloc_start.checked_add(layout.size()) {
+                Some(end) => end,
+                None => return ptr::null_mut(),
+            };
+
+            if alloc_end > bump.end {
+                match super::grow(alloc_end - bump.end) {
+                    Some((start, size)) if start == bump.end => bump.extend(start + size),
+                    _ => return ptr::null_mut(), // out of memory
+                }
+            }
 
-        if alloc_end > bump.end {
-            ptr::null_mut() // out of memory
-        } else {
             bump.next = alloc_end;
             bump.allocations += 1;
+            bump.counters.on_alloc(alloc_start as *mut u8, layout);
             alloc_start as *mut u8
-        }
+        })
     }
 
-    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
-        let mut bump = self.lock(); // get a mutable reference
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        crate::no_interrupt!({
+            let mut bump = self.lock(); // get a mutable reference
 
-        bump.allocations -= 1;
-        if bump.allocations == 0 {
-            bump.next = bump.start;
-        }
+            bump.counters.on_free(ptr, layout);
+            bump.allocations -= 1;
+            if bump.allocations == 0 {
+                bump.next = bump.start;
+            }
+        })
     }
 
     unsafe fn alloc_zeroed(&self, layout: core::alloc::Layout) -> *mut u8 {
@@ -91,3 +107,15 @@ unsafe impl GlobalAlloc for Locked<BumpAllocator> {
         new_ptr
     }
 }
+
+impl HeapStats for Locked<BumpAllocator> {
+    fn stats(&self) -> MemoryStats {
+        crate::no_interrupt!({
+            let bump = self.lock();
+            // Freed Memory Below `next` Can't Be Reused Until Everything Is Freed.
+            let tail = bump.end - bump.next;
+            let dead = (bump.next - bump.start) - bump.counters.allocated();
+            MemoryStats::new(&bump.counters, tail + dead, tail)
+        })
+    }
+}
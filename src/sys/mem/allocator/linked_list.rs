@@ -1,6 +1,6 @@
 use core::alloc::{GlobalAlloc, Layout};
 
-use crate::{sys::mem::allocator::align_up, Locked};
+use crate::{sys::mem::allocator::{align_up, AllocCounters, HeapStats, MemoryStats}, Locked};
 #[derive(Debug)]
 pub struct ListNode {
     pub size: usize,
@@ -29,18 +29,36 @@ impl ListNode {
 #[derive(Debug)]
 pub struct LinkedListAllocator {
     head: ListNode,
+    counters: AllocCounters,
 }
 
 impl LinkedListAllocator {
     pub const fn new() -> Self {
         let node = ListNode::new(0);
-        Self { head: node }
+        Self { head: node, counters: AllocCounters::new() }
     }
 
     pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
         self.add_free_region(heap_start, heap_size);
     }
 
+    /// Returns The Total Size Of Every Free Region & The Size Of The Largest.
+    pub fn free_regions(&self) -> (usize, usize) {
+        let mut current = &self.head.next;
+        let (mut total, mut largest) = (0, 0);
+        while let Some(region) = current {
+            total += region.size;
+            largest = largest.max(region.size);
+            current = &region.next;
+        }
+        (total, largest)
+    }
+
+    /// Hand A Newly Mapped Region Over To The Allocator.
+    pub unsafe fn extend(&mut self, start: usize, size: usize) {
+        self.add_free_region(start, size);
+    }
+
     pub unsafe fn add_free_region(&mut self, addr: usize, size: usize) {
         assert_eq!(align_up(addr, core::mem::align_of::<ListNode>()), addr);
         assert!(size >= core::mem::size_of::<ListNode>());
@@ -114,17 +132,24 @@ impl LinkedListAllocator {
     }
 }
 
-unsafe impl GlobalAlloc for Locked<LinkedListAllocator> {
-    unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+impl LinkedListAllocator {
+    /// Allocate Without Locking, For Allocators Built On Top Of This One.
+    pub unsafe fn allocate(&mut self, layout: Layout) -> *mut u8 {
         // perform layout adjustments
         let (size, align) = LinkedListAllocator::size_align(layout);
-        let mut allocator = self.lock();
 
-        if let Some((region, alloc_start)) = allocator.find_region(size, align) {
+        let found = self.find_region(size, align).or_else(|| {
+            // Out Of Space, Grow The Heap By Enough To Fit The Allocation On Its Own.
+            let (start, grown) = super::grow(size + align)?;
+            self.extend(start, grown);
+            self.find_region(size, align)
+        });
+
+        if let Some((region, alloc_start)) = found {
             let alloc_end = alloc_start.checked_add(size).expect("overflow");
             let excess_size = region.end_addr() - alloc_end;
             if excess_size > 0 {
-                allocator.add_free_region(alloc_end, excess_size);
+                self.add_free_region(alloc_end, excess_size);
             }
             alloc_start as *mut u8
         } else {
@@ -132,11 +157,42 @@ unsafe impl GlobalAlloc for Locked<LinkedListAllocator> {
         }
     }
 
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+    /// Free Without Locking, See [LinkedListAllocator::allocate].
+    pub unsafe fn deallocate(&mut self, ptr: *mut u8, layout: Layout) {
         // perform layout adjustments
         let (size, _) = LinkedListAllocator::size_align(layout);
 
-        let mut allocator = self.lock();
-        allocator.add_free_region(ptr as usize, size);
+        self.add_free_region(ptr as usize, size);
+    }
+}
+
+unsafe impl GlobalAlloc for Locked<LinkedListAllocator> {
+    unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+        crate::no_interrupt!({
+            let mut allocator = self.lock();
+            let ptr = allocator.allocate(layout);
+            if !ptr.is_null() {
+                allocator.counters.on_alloc(ptr, layout);
+            }
+            ptr
+        })
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+        crate::no_interrupt!({
+            let mut allocator = self.lock();
+            allocator.counters.on_free(ptr, layout);
+            allocator.deallocate(ptr, layout)
+        })
+    }
+}
+
+impl HeapStats for Locked<LinkedListAllocator> {
+    fn stats(&self) -> MemoryStats {
+        crate::no_interrupt!({
+            let allocator = self.lock();
+            let (free, largest) = allocator.free_regions();
+            MemoryStats::new(&allocator.counters, free, largest)
+        })
     }
 }
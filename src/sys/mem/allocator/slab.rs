@@ -0,0 +1,131 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
+
+use crate::Locked;
+
+use super::{linked_list::LinkedListAllocator, AllocCounters, HeapStats, MemoryStats};
+
+/// The Block Sizes Served From Slabs, Anything Larger Goes To The Fallback.
+const SIZE_CLASSES: [usize; 9] = [8, 16, 32, 64, 128, 256, 512, 1024, 2048];
+/// Slabs Are Carved From Page Aligned Chunks Of The Fallback, So Every Block
+/// Is Aligned To Its Own Size.
+const SLAB_SIZE: usize = 4096 * 4;
+const SLAB_ALIGN: usize = 4096;
+
+struct FreeBlock {
+    next: Option<&'static mut FreeBlock>,
+}
+
+/// Serves Small Allocations From Per Size Class Free Lists.
+pub struct SlabAllocator {
+    heads: [Option<&'static mut FreeBlock>; SIZE_CLASSES.len()],
+    fallback: LinkedListAllocator,
+    counters: AllocCounters,
+}
+
+impl SlabAllocator {
+    pub const fn new() -> Self {
+        const EMPTY: Option<&'static mut FreeBlock> = None;
+        Self {
+            heads: [EMPTY; SIZE_CLASSES.len()],
+            fallback: LinkedListAllocator::new(),
+            counters: AllocCounters::new(),
+        }
+    }
+
+    pub unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
+        self.fallback.init(heap_start, heap_size);
+    }
+
+    /// The Size Class Serving The Layout, None If It Is Too Large.
+    fn class(layout: &Layout) -> Option<usize> {
+        let size = layout.size().max(layout.align());
+        SIZE_CLASSES.iter().position(|&class| class >= size)
+    }
+
+    /// Carve A New Slab Into Blocks For The Given Class.
+    unsafe fn refill(&mut self, class: usize) -> bool {
+        let slab = self
+            .fallback
+            .allocate(Layout::from_size_align_unchecked(SLAB_SIZE, SLAB_ALIGN));
+        if slab.is_null() {
+            return false;
+        }
+        let size = SIZE_CLASSES[class];
+        for offset in (0..SLAB_SIZE).step_by(size).rev() {
+            let block = slab.add(offset) as *mut FreeBlock;
+            block.write(FreeBlock { next: self.heads[class].take() });
+            self.heads[class] = Some(&mut *block);
+        }
+        true
+    }
+
+    /// Bytes Sitting In Slab Free Lists.
+    fn slab_free(&self) -> usize {
+        let mut total = 0;
+        for (class, head) in self.heads.iter().enumerate() {
+            let mut current = head;
+            while let Some(block) = current {
+                total += SIZE_CLASSES[class];
+                current = &block.next;
+            }
+        }
+        total
+    }
+
+    pub unsafe fn allocate(&mut self, layout: Layout) -> *mut u8 {
+        match Self::class(&layout) {
+            Some(class) => {
+                if self.heads[class].is_none() && !self.refill(class) {
+                    return ptr::null_mut();
+                }
+                let block = self.heads[class].take().unwrap();
+                self.heads[class] = block.next.take();
+                block as *mut FreeBlock as *mut u8
+            }
+            None => self.fallback.allocate(layout),
+        }
+    }
+
+    pub unsafe fn deallocate(&mut self, ptr: *mut u8, layout: Layout) {
+        match Self::class(&layout) {
+            Some(class) => {
+                let block = ptr as *mut FreeBlock;
+                block.write(FreeBlock { next: self.heads[class].take() });
+                self.heads[class] = Some(&mut *block);
+            }
+            None => self.fallback.deallocate(ptr, layout),
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for Locked<SlabAllocator> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        crate::no_interrupt!({
+            let mut allocator = self.lock();
+            let ptr = allocator.allocate(layout);
+            if !ptr.is_null() {
+                allocator.counters.on_alloc(ptr, layout);
+            }
+            ptr
+        })
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        crate::no_interrupt!({
+            let mut allocator = self.lock();
+            allocator.counters.on_free(ptr, layout);
+            allocator.deallocate(ptr, layout)
+        })
+    }
+}
+
+impl HeapStats for Locked<SlabAllocator> {
+    fn stats(&self) -> MemoryStats {
+        crate::no_interrupt!({
+            let allocator = self.lock();
+            let (fallback_free, largest) = allocator.fallback.free_regions();
+            MemoryStats::new(&allocator.counters, fallback_free + allocator.slab_free(), largest)
+        })
+    }
+}
@@ -0,0 +1,97 @@
+//! Allocation Call Site Tracking, Enabled By The `heap_trace` Feature.
+//! Every Live Allocation Is Recorded With The Return Addresses Found By Walking
+//! The Frame Pointer Chain, So Leaks Can Be Traced Back To Their Caller.
+//! Needs `-C force-frame-pointers=yes`, Set In `.cargo/config.toml`.
+
+use core::arch::asm;
+
+use spin::Mutex;
+
+/// The Maximum Number Of Live Allocations Tracked, Later Ones Are Counted As Untracked.
+pub const MAX_TRACKED: usize = 1024;
+/// The Number Of Return Addresses Kept Per Allocation.
+pub const TRACE_DEPTH: usize = 4;
+/// The Number Of Slots [for_each] Copies Out Per Lock, Kept Small As It Lives On The Stack.
+const BATCH_SIZE: usize = 32;
+/// Frames Belonging To The Allocator Itself, Skipped When Recording.
+const SKIP_FRAMES: usize = 2;
+
+/// A Live Allocation.
+#[derive(Debug, Clone, Copy)]
+pub struct Record {
+    pub ptr: usize,
+    pub size: usize,
+    /// Return Addresses, Innermost First, 0 Where The Chain Ended.
+    pub callers: [usize; TRACE_DEPTH],
+}
+
+struct Table {
+    records: [Option<Record>; MAX_TRACKED],
+    untracked: usize,
+}
+
+static TABLE: Mutex<Table> = Mutex::new(Table { records: [None; MAX_TRACKED], untracked: 0 });
+
+/// Walk The Frame Pointer Chain.
+fn backtrace() -> [usize; TRACE_DEPTH] {
+    let mut callers = [0; TRACE_DEPTH];
+    let mut rbp: usize;
+    unsafe {
+        asm!("mov {}, rbp", out(reg) rbp, options(nomem, nostack, preserves_flags));
+    }
+    for depth in 0..SKIP_FRAMES + TRACE_DEPTH {
+        if rbp == 0 || rbp % 8 != 0 {
+            break;
+        }
+        let (next, ret) = unsafe { (*(rbp as *const usize), *((rbp + 8) as *const usize)) };
+        if depth >= SKIP_FRAMES {
+            callers[depth - SKIP_FRAMES] = ret;
+        }
+        // The Chain Only Ever Moves Up The Stack.
+        if next <= rbp {
+            break;
+        }
+        rbp = next;
+    }
+    callers
+}
+
+/// Record An Allocation, Called With The Allocator Locked.
+pub fn record(ptr: *mut u8, size: usize) {
+    let callers = backtrace();
+    let mut table = TABLE.lock();
+    match table.records.iter_mut().find(|r| r.is_none()) {
+        Some(slot) => *slot = Some(Record { ptr: ptr as usize, size, callers }),
+        None => table.untracked += 1,
+    }
+}
+
+/// Forget A Freed Allocation.
+pub fn forget(ptr: *mut u8) {
+    let mut table = TABLE.lock();
+    match table.records.iter_mut().find(|r| matches!(r, Some(r) if r.ptr == ptr as usize)) {
+        Some(slot) => *slot = None,
+        None => table.untracked = table.untracked.saturating_sub(1),
+    }
+}
+
+/// Call `f` On Every Live Allocation, Returns The Number Not Tracked.
+/// Records Are Copied Out A Batch At A Time & `f` Runs With The Table Unlocked,
+/// So It May Allocate. Allocations Made Meanwhile May Or May Not Be Seen.
+pub fn for_each(mut f: impl FnMut(&Record)) -> usize {
+    let mut slot = 0;
+    loop {
+        let mut batch = [None; BATCH_SIZE];
+        let (next, untracked) = crate::no_interrupt!({
+            let table = TABLE.lock();
+            let end = MAX_TRACKED.min(slot + BATCH_SIZE);
+            batch[..end - slot].copy_from_slice(&table.records[slot..end]);
+            (end, table.untracked)
+        });
+        batch.iter().flatten().for_each(|r| f(r));
+        if next == MAX_TRACKED {
+            return untracked;
+        }
+        slot = next;
+    }
+}
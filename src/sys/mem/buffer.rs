@@ -1,28 +1,140 @@
-//! A Physically Continous Buffer
+//! Physically Contiguous Buffers For DMA.
+//! Buffers Are Carved Straight From The [frame allocator](super::frame_allocator)
+//! & Accessed Through The Physical Memory Mapping, So A Device Given
+//! [Buffer::phys_addr] Sees The Same Bytes As The Kernel.
 
+use core::{
+    marker::PhantomData,
+    mem::{align_of, size_of},
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+use x86_64::{structures::paging::PhysFrame, PhysAddr};
+
+use super::{frame_allocator, mapper};
 use crate::KResult;
-use alloc::vec;
-use vec::Vec;
 
-/// A Continous Buffer Of Type T
+const FRAME_SIZE: usize = 4096;
+const FOUR_GIB: u64 = 1 << 32;
+
+/// Where A Device Can Reach A Buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaConstraints {
+    /// The Physical Alignment In Bytes, A Power Of Two.
+    pub align: usize,
+    /// The Buffer Must End Below 4GiB, For Devices With 32 Bit Address Registers.
+    pub below_4gib: bool,
+    /// The Buffer Must Not Cross A Multiple Of This Many Bytes, A Power Of Two.
+    pub boundary: Option<usize>,
+}
+
+impl DmaConstraints {
+    /// Page Aligned, Anywhere In Physical Memory.
+    pub const ANY: Self = Self { align: FRAME_SIZE, below_4gib: false, boundary: None };
+    /// Page Aligned & Addressable With 32 Bits.
+    pub const BELOW_4GIB: Self = Self { align: FRAME_SIZE, below_4gib: true, boundary: None };
+    /// An IDE Bus Master Region, Below 4GiB & Never Crossing 64KiB.
+    pub const BUS_MASTER: Self = Self { align: FRAME_SIZE, below_4gib: true, boundary: Some(0x10000) };
+}
+
+impl Default for DmaConstraints {
+    fn default() -> Self {
+        Self::ANY
+    }
+}
+
+/// A Physically Contiguous Buffer Of `len` T's, Freed On Drop.
 #[derive(Debug)]
-#[allow(unused)]
 pub struct Buffer<T> {
-    contents: Vec<T>,
+    frame: PhysFrame,
+    frames: usize,
+    len: usize,
+    _marker: PhantomData<T>,
 }
 
+unsafe impl<T: Send> Send for Buffer<T> {}
+unsafe impl<T: Sync> Sync for Buffer<T> {}
+
 impl<T: Copy> Buffer<T> {
-    /// Create A New Continous Buffer in memory, Returns Err If No Free Memory.
-    pub fn new(instance: T, size: usize) -> KResult<Buffer<T>> {
-        for _ in 0..1024 {
-            let v = vec![instance; size];
-            let start = (&v[0] as *const T) as usize;
-            let end = (&v[v.len() - 1] as *const T) as usize;
-            if (end - start) == size {
-                return Ok(Buffer { contents: v });
-            };
-            drop(v);
+    /// Create A Buffer Of `len` Copies Of `instance` Anywhere In Physical Memory,
+    /// Returns Err If No Free Memory.
+    pub fn new(instance: T, len: usize) -> KResult<Buffer<T>> {
+        Self::with_constraints(instance, len, DmaConstraints::ANY)
+    }
+
+    /// Create A Buffer Of `len` Copies Of `instance` That Meets `constraints`.
+    pub fn with_constraints(instance: T, len: usize, constraints: DmaConstraints) -> KResult<Buffer<T>> {
+        let bytes = len.checked_mul(size_of::<T>()).ok_or("Buffer Is Too Large")?;
+        if bytes == 0 {
+            return Err("Buffer Must Be Non-Empty");
+        }
+        if !constraints.align.is_power_of_two() {
+            return Err("Alignment Must Be A Power Of Two");
+        }
+
+        let mut align = constraints.align.max(align_of::<T>());
+        if let Some(boundary) = constraints.boundary {
+            if !boundary.is_power_of_two() || bytes > boundary {
+                return Err("Buffer Cannot Fit Inside The Boundary");
+            }
+            // A Buffer Aligned To Its Own Size Rounded Up Never Crosses A Larger Boundary.
+            align = align.max(bytes.next_power_of_two());
+        }
+
+        let frames = (bytes + FRAME_SIZE - 1) / FRAME_SIZE;
+        let align_frames = (align / FRAME_SIZE).max(1);
+        let frame = frame_allocator::allocate_contiguous(frames, align_frames)
+            .ok_or("Out Of Contiguous Physical Memory")?;
+
+        // The Search Is Lowest First, So If This Run Is Too High Every Other One Is Too.
+        if constraints.below_4gib && frame.start_address().as_u64() + bytes as u64 > FOUR_GIB {
+            frame_allocator::free_contiguous(frame, frames)?;
+            return Err("No Contiguous Physical Memory Below 4GiB");
         }
-        Err("Unable To Create A Large Enough Buffer.")
+
+        let buffer = Buffer { frame, frames, len, _marker: PhantomData };
+        // The Frames Hold Garbage, So Write Each Element Before Any Slice Refers To Them.
+        let ptr = buffer.as_ptr();
+        for i in 0..len {
+            unsafe { ptr.add(i).write(instance) };
+        }
+        Ok(buffer)
+    }
+}
+
+impl<T> Buffer<T> {
+    /// The Physical Address To Hand To The Device.
+    pub fn phys_addr(&self) -> PhysAddr {
+        self.frame.start_address()
+    }
+
+    /// The Size Of The Contents In Bytes.
+    pub fn size(&self) -> usize {
+        self.len * size_of::<T>()
+    }
+
+    fn as_ptr(&self) -> *mut T {
+        (mapper::physical_memory_offset() + self.phys_addr().as_u64()) as *mut T
+    }
+}
+
+impl<T> Deref for Buffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for Buffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.as_ptr(), self.len) }
+    }
+}
+
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        let _ = frame_allocator::free_contiguous(self.frame, self.frames);
     }
 }
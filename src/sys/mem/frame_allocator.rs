@@ -1,10 +1,21 @@
 //! Utilities for Allocating Frames.
-use bootloader::{bootinfo::MemoryMap, BootInfo};
+//! Physical Frames Are Tracked In A Bitmap, One Bit Per 4KiB Frame, Built From
+//! The Bootloader's Memory Map. The Bitmap Itself Lives In The First Usable Region
+//! Large Enough To Hold It.
+use core::ops::Range;
+
+use bootloader::bootinfo::{MemoryMap, MemoryRegionType};
+use spin::Mutex;
 use x86_64::{
-    structures::paging::{FrameAllocator, PhysFrame, Size4KiB},
+    structures::paging::{FrameAllocator, FrameDeallocator, PhysFrame, Size4KiB},
     PhysAddr,
 };
 
+use crate::{no_interrupt, KResult};
+
+const FRAME_SIZE: u64 = 4096;
+const BITS: usize = u64::BITS as usize;
+
 /// An Empty Frame Allocator, Returns None on every Allocation.
 #[derive(Debug)]
 pub struct EmptyFrameAllocator;
@@ -15,53 +26,219 @@ unsafe impl FrameAllocator<Size4KiB> for EmptyFrameAllocator {
     }
 }
 
-/// A FrameAllocator that returns usable frames from the bootloader's memory map.
-#[derive(Debug, Clone, Copy)]
-pub struct BootInfoFrameAllocator {
+/// Physical Memory Usage, In Frames.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    /// Usable Frames Reported By The Bootloader.
+    pub total: usize,
+    /// Frames Available For Allocation.
+    pub free: usize,
+    /// Frames Handed Out, Including Those Holding The Bitmap.
+    pub used: usize,
+}
+
+/// A Bitmap Of Every Frame Up To The End Of The Last Usable Region, Set Bits Are In Use.
+#[derive(Debug)]
+pub struct BitmapFrameAllocator {
+    bitmap: &'static mut [u64],
+    /// Kept To Reject Frees Of Frames Outside The Usable Regions.
     memory_map: &'static MemoryMap,
-    next: usize,
+    /// Frames Holding The Bitmap Itself.
+    reserved: Range<usize>,
+    frames: usize,
+    total: usize,
+    free: usize,
+    /// Where The Next Single Frame Search Starts.
+    hint: usize,
 }
 
-impl BootInfoFrameAllocator {
-    /// Create a FrameAllocator from the passed memory map.
+impl BitmapFrameAllocator {
+    /// Build The Bitmap From The Memory Map.
     ///
     /// This function is unsafe because the caller must guarantee that the passed
-    /// memory map is valid. The main requirement is that all frames that are marked
-    /// as `USABLE` in it are really unused.
-    pub unsafe fn init(memory_map: &'static MemoryMap) -> Self {
-        BootInfoFrameAllocator {
-            memory_map,
-            next: 0,
+    /// memory map is valid, that every frame marked `USABLE` is really unused & that
+    /// all of physical memory is mapped at `physical_offset`.
+    pub unsafe fn init(memory_map: &'static MemoryMap, physical_offset: u64) -> KResult<Self> {
+        let usable = || memory_map.iter().filter(|r| r.region_type == MemoryRegionType::Usable);
+
+        let frames = usable().map(|r| r.range.end_frame_number).max().ok_or("No Usable Memory")? as usize;
+        let words = (frames + BITS - 1) / BITS;
+        let bitmap_frames = (words * 8 + FRAME_SIZE as usize - 1) / FRAME_SIZE as usize;
+
+        let home = usable()
+            .find(|r| (r.range.end_frame_number - r.range.start_frame_number) as usize >= bitmap_frames)
+            .ok_or("No Region Large Enough For The Frame Bitmap")?;
+        let bitmap_addr = home.range.start_addr();
+        let bitmap = core::slice::from_raw_parts_mut((bitmap_addr + physical_offset) as *mut u64, words);
+        bitmap.fill(u64::MAX);
+
+        let bitmap_start = (bitmap_addr / FRAME_SIZE) as usize;
+        let reserved = bitmap_start..bitmap_start + bitmap_frames;
+        let mut allocator = Self { bitmap, memory_map, reserved, frames, total: 0, free: 0, hint: 0 };
+        for region in usable() {
+            for frame in region.range.start_frame_number..region.range.end_frame_number {
+                allocator.clear(frame as usize);
+                allocator.total += 1;
+            }
         }
+        allocator.free = allocator.total;
+
+        for frame in allocator.reserved.clone() {
+            allocator.set(frame);
+            allocator.free -= 1;
+        }
+        // Never Hand Out Frame 0, A Null Physical Address Is Almost Always A Bug.
+        if !allocator.is_used(0) {
+            allocator.set(0);
+            allocator.free -= 1;
+        }
+        Ok(allocator)
     }
 
-    /// Returns A BootFrameAllocator From The [BootInfo]
-    pub unsafe fn from(boot_info: &'static BootInfo) -> Self {
-        Self::init(&boot_info.memory_map)
+    fn is_used(&self, frame: usize) -> bool {
+        self.bitmap[frame / BITS] & (1 << (frame % BITS)) != 0
     }
-}
 
-use bootloader::bootinfo::MemoryRegionType;
-
-impl BootInfoFrameAllocator {
-    /// Returns an iterator over the usable frames specified in the memory map.
-    fn usable_frames(&self) -> impl Iterator<Item = PhysFrame> {
-        // get usable regions from memory map
-        let regions = self.memory_map.iter();
-        let usable_regions = regions.filter(|r| r.region_type == MemoryRegionType::Usable);
-        // map each region to its address range
-        let addr_ranges = usable_regions.map(|r| r.range.start_addr()..r.range.end_addr());
-        // transform to an iterator of frame start addresses
-        let frame_addresses = addr_ranges.flat_map(|r| r.step_by(4096));
-        // create `PhysFrame` types from the start addresses
-        frame_addresses.map(|addr| PhysFrame::containing_address(PhysAddr::new(addr)))
+    /// Whether `frame` Lies In A Usable Region & Outside The Bitmap, Frame 0 Is Never Allocatable.
+    fn is_allocatable(&self, frame: usize) -> bool {
+        let number = frame as u64;
+        frame != 0
+            && !self.reserved.contains(&frame)
+            && self.memory_map.iter().any(|r| {
+                r.region_type == MemoryRegionType::Usable
+                    && (r.range.start_frame_number..r.range.end_frame_number).contains(&number)
+            })
     }
+
+    fn set(&mut self, frame: usize) {
+        self.bitmap[frame / BITS] |= 1 << (frame % BITS);
+    }
+
+    fn clear(&mut self, frame: usize) {
+        self.bitmap[frame / BITS] &= !(1 << (frame % BITS));
+    }
+
+    fn frame_at(index: usize) -> PhysFrame {
+        PhysFrame::containing_address(PhysAddr::new(index as u64 * FRAME_SIZE))
+    }
+
+    /// Allocate A Single Frame.
+    pub fn allocate(&mut self) -> Option<PhysFrame> {
+        let words = self.bitmap.len();
+        let start = self.hint / BITS;
+        for offset in 0..words {
+            let word = (start + offset) % words;
+            if self.bitmap[word] != u64::MAX {
+                let index = word * BITS + (!self.bitmap[word]).trailing_zeros() as usize;
+                if index >= self.frames {
+                    continue;
+                }
+                self.set(index);
+                self.free -= 1;
+                self.hint = index;
+                return Some(Self::frame_at(index));
+            }
+        }
+        None
+    }
+
+    /// Allocate `count` Physically Contiguous Frames, The First Aligned To `align` Frames.
+    pub fn allocate_contiguous(&mut self, count: usize, align: usize) -> Option<PhysFrame> {
+        if count == 0 || align == 0 || count > self.free {
+            return None;
+        }
+        let mut start = 0;
+        while start + count <= self.frames {
+            match (start..start + count).rev().find(|&frame| self.is_used(frame)) {
+                // Restart The Search Past The Last Used Frame In The Window.
+                Some(used) => start = (used + 1 + align - 1) / align * align,
+                None => {
+                    for frame in start..start + count {
+                        self.set(frame);
+                    }
+                    self.free -= count;
+                    return Some(Self::frame_at(start));
+                }
+            }
+        }
+        None
+    }
+
+    /// Return `count` Frames Starting At `frame`.
+    /// Returns Err If Any Of Them Were Not Allocated, Or Are Reserved, MMIO Or Kernel Memory.
+    pub fn free_contiguous(&mut self, frame: PhysFrame, count: usize) -> KResult<()> {
+        let start = (frame.start_address().as_u64() / FRAME_SIZE) as usize;
+        if start + count > self.frames || (start..start + count).any(|f| !self.is_allocatable(f)) {
+            return Err("Frame Is Not Allocatable");
+        }
+        if (start..start + count).any(|f| !self.is_used(f)) {
+            return Err("Frame Was Not Allocated");
+        }
+        for index in start..start + count {
+            self.clear(index);
+        }
+        self.free += count;
+        self.hint = self.hint.min(start);
+        Ok(())
+    }
+
+    /// Current Usage.
+    pub fn stats(&self) -> FrameStats {
+        FrameStats { total: self.total, free: self.free, used: self.total - self.free }
+    }
+}
+
+static FRAME_ALLOCATOR: Mutex<Option<BitmapFrameAllocator>> = Mutex::new(None);
+
+/// Build The Global Frame Allocator From The Memory Map.
+pub fn initialize(memory_map: &'static MemoryMap, physical_offset: u64) -> KResult<()> {
+    let allocator = unsafe { BitmapFrameAllocator::init(memory_map, physical_offset)? };
+    no_interrupt!({ *FRAME_ALLOCATOR.lock() = Some(allocator) });
+    Ok(())
 }
 
-unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
+/// Allocate A Single Frame From The Global Allocator.
+pub fn allocate() -> Option<PhysFrame> {
+    no_interrupt!({ FRAME_ALLOCATOR.lock().as_mut()?.allocate() })
+}
+
+/// Allocate `count` Contiguous Frames, Aligned To `align` Frames.
+pub fn allocate_contiguous(count: usize, align: usize) -> Option<PhysFrame> {
+    no_interrupt!({ FRAME_ALLOCATOR.lock().as_mut()?.allocate_contiguous(count, align) })
+}
+
+/// Return A Single Frame To The Global Allocator.
+pub fn free(frame: PhysFrame) -> KResult<()> {
+    free_contiguous(frame, 1)
+}
+
+/// Return `count` Contiguous Frames To The Global Allocator.
+pub fn free_contiguous(frame: PhysFrame, count: usize) -> KResult<()> {
+    no_interrupt!({
+        match FRAME_ALLOCATOR.lock().as_mut() {
+            Some(allocator) => allocator.free_contiguous(frame, count),
+            None => Err("Frame Allocator Not Initialized"),
+        }
+    })
+}
+
+/// Usage Of The Global Allocator.
+pub fn stats() -> FrameStats {
+    no_interrupt!({ FRAME_ALLOCATOR.lock().as_ref().map(|a| a.stats()).unwrap_or_default() })
+}
+
+/// Hands Out Frames From The Global Allocator, For Use With The Page Table Mapper.
+#[derive(Debug, Clone, Copy)]
+pub struct GlobalFrameAllocator;
+
+unsafe impl FrameAllocator<Size4KiB> for GlobalFrameAllocator {
     fn allocate_frame(&mut self) -> Option<PhysFrame> {
-        let frame = self.usable_frames().nth(self.next);
-        self.next += 1;
-        frame
+        allocate()
+    }
+}
+
+impl FrameDeallocator<Size4KiB> for GlobalFrameAllocator {
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame) {
+        let _ = free(frame);
     }
 }
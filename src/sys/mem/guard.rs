@@ -0,0 +1,129 @@
+//! Guard Pages.
+//! Unmapped Pages Sit Below Every Kernel Stack & Around The Heap, So Running Off
+//! The End Faults Instead Of Silently Corrupting Whatever Lies Next To It.
+//! Every Guard Is Recorded Here So The Page Fault Handler Can Name The Culprit.
+
+use core::arch::asm;
+use core::fmt::{self, Display};
+
+use spin::Mutex;
+use x86_64::{
+    structures::paging::{Page, Size4KiB},
+    VirtAddr,
+};
+
+use super::{allocator, mapper};
+use crate::{no_interrupt, KResult};
+
+/// The Maximum Number Of Registered Guard Regions.
+pub const MAX_GUARDS: usize = 64;
+/// How Far Below The Boot Stack Pointer To Look For The Bootloader's Guard Page.
+const MAX_BOOT_STACK_PAGES: u64 = 1024;
+const PAGE_SIZE: u64 = Page::<Size4KiB>::SIZE;
+
+/// What A Guard Region Protects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardKind {
+    /// The Page Below A Stack, Hit When The Stack Overflows.
+    Stack,
+    /// The Unmapped Space Either Side Of The Kernel Heap.
+    Heap,
+}
+
+/// A Range Of Pages That Must Never Be Mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardRegion {
+    pub start: VirtAddr,
+    pub pages: u64,
+    pub kind: GuardKind,
+    /// The Task Owning The Stack, Or What Else The Guard Protects.
+    pub owner: &'static str,
+}
+
+impl GuardRegion {
+    /// Returns True If `addr` Falls Inside The Guard.
+    pub fn contains(&self, addr: VirtAddr) -> bool {
+        addr >= self.start && addr < self.start + self.pages * PAGE_SIZE
+    }
+}
+
+impl Display for GuardRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            GuardKind::Stack => write!(f, "Stack Overflow In Task '{}'", self.owner),
+            GuardKind::Heap => write!(f, "Access Outside The {}", self.owner),
+        }
+    }
+}
+
+static GUARDS: Mutex<[Option<GuardRegion>; MAX_GUARDS]> = Mutex::new([None; MAX_GUARDS]);
+
+/// Record `pages` Unmapped Pages At `start` As A Guard.
+/// Returns Err If Any Of Them Are Mapped Or The Table Is Full.
+pub fn register(start: VirtAddr, pages: u64, kind: GuardKind, owner: &'static str) -> KResult<()> {
+    if (0..pages).any(|i| mapper::translate(start + i * PAGE_SIZE).is_some()) {
+        return Err("Guard Page Is Mapped");
+    }
+    let region = GuardRegion { start, pages, kind, owner };
+    no_interrupt!({
+        let mut guards = GUARDS.lock();
+        let slot = guards.iter_mut().find(|g| g.is_none()).ok_or("Guard Table Is Full")?;
+        *slot = Some(region);
+        Ok(())
+    })
+}
+
+/// Forget The Guard Starting At `start`.
+pub fn unregister(start: VirtAddr) -> KResult<()> {
+    no_interrupt!({
+        let mut guards = GUARDS.lock();
+        let slot = guards
+            .iter_mut()
+            .find(|g| matches!(g, Some(g) if g.start == start))
+            .ok_or("No Guard At Address")?;
+        *slot = None;
+        Ok(())
+    })
+}
+
+/// Find The Guard Containing `addr`.
+/// Never Blocks, So It Is Safe To Call From The Page Fault Handler.
+pub fn lookup(addr: VirtAddr) -> Option<GuardRegion> {
+    // Everything Between The End Of The Heap & Its Ceiling Is Kept Unmapped For Growth.
+    let heap_end = allocator::HEAP_START + allocator::heap_size();
+    let ceiling = allocator::HEAP_START + allocator::heap_limit() + PAGE_SIZE as usize;
+    if (heap_end..ceiling).contains(&(addr.as_u64() as usize)) {
+        let pages = (ceiling - heap_end) as u64 / PAGE_SIZE;
+        return Some(GuardRegion {
+            start: VirtAddr::new(heap_end as u64),
+            pages,
+            kind: GuardKind::Heap,
+            owner: "Kernel Heap",
+        });
+    }
+    let guards = GUARDS.try_lock()?;
+    guards.iter().flatten().find(|g| g.contains(addr)).copied()
+}
+
+/// The Bootloader Leaves One Unmapped Page Below The Boot Stack, Find It.
+fn boot_stack_guard() -> Option<VirtAddr> {
+    let rsp: u64;
+    unsafe {
+        asm!("mov {}, rsp", out(reg) rsp, options(nomem, nostack, preserves_flags));
+    }
+    let top = Page::<Size4KiB>::containing_address(VirtAddr::new(rsp));
+    (1..MAX_BOOT_STACK_PAGES)
+        .map(|i| top - i)
+        .find(|page| mapper::translate(page.start_address()).is_none())
+        .map(|page| page.start_address())
+}
+
+/// Register The Guards That Exist From Boot.
+/// Must Run Once The Heap Is Mapped.
+pub fn initialize() -> KResult<()> {
+    let below_heap = VirtAddr::new((allocator::HEAP_START as u64) - PAGE_SIZE);
+    register(below_heap, 1, GuardKind::Heap, "Kernel Heap")?;
+    let boot_guard = boot_stack_guard().ok_or("Unable To Find The Boot Stack's Guard Page")?;
+    register(boot_guard, 1, GuardKind::Stack, "kernel")?;
+    crate::sys::interrupt::tss::guard_stacks()
+}
@@ -1,45 +1,123 @@
-//! Utilities For The [OffsetPageTable] Mapper
+//! Utilities For The [OffsetPageTable] Mapper.
+//! The Kernel Owns A Single Mapper, Every Change To The Page Tables Goes Through It.
 
 use bootloader::BootInfo;
+use spin::Mutex;
 use x86_64::{
-    structures::paging::{FrameAllocator, Mapper, OffsetPageTable, Page, PhysFrame, Size4KiB},
+    instructions::tlb,
+    structures::paging::{
+        mapper::{MapToError, UnmapError},
+        Mapper, OffsetPageTable, Page, PageTableFlags, PhysFrame, Size4KiB, Translate,
+    },
     PhysAddr, VirtAddr,
 };
 
+use super::frame_allocator::{self, GlobalFrameAllocator};
+use crate::{no_interrupt, KResult};
+
 type TableMapper = OffsetPageTable<'static>;
 static mut PHYSICAL_OFFSET: u64 = 0;
+static MAPPER: Mutex<Option<TableMapper>> = Mutex::new(None);
 
 /// Initialize The Mapper
-pub fn init_mapper(boot_info: &'static BootInfo) -> TableMapper {
+pub fn init_mapper(boot_info: &'static BootInfo) {
     let offset = VirtAddr::new(boot_info.physical_memory_offset);
     unsafe {
         PHYSICAL_OFFSET = boot_info.physical_memory_offset;
-        OffsetPageTable::new(super::l4_page_table_at(offset), offset)
+        let mapper = OffsetPageTable::new(super::l4_page_table_at(offset), offset);
+        no_interrupt!({ *MAPPER.lock() = Some(mapper) });
+    }
+}
+
+/// Run `f` With The Kernel's Mapper, Returns Err If It Is Not Initialized.
+pub fn with_mapper<T>(f: impl FnOnce(&mut TableMapper) -> KResult<T>) -> KResult<T> {
+    no_interrupt!({
+        match MAPPER.lock().as_mut() {
+            Some(mapper) => f(mapper),
+            None => Err("Mapper Not Initialized"),
+        }
+    })
+}
+
+fn map_error(error: MapToError<Size4KiB>) -> &'static str {
+    match error {
+        MapToError::FrameAllocationFailed => "Out Of Physical Frames",
+        MapToError::ParentEntryHugePage => "Address Is Inside A Huge Page",
+        MapToError::PageAlreadyMapped(_) => "Page Already Mapped",
     }
 }
 
-/// Map A Page To A Given Physical Address, Using The Given Frame Allocator.
-pub unsafe fn map_to(
-    frame_allocator: &mut impl FrameAllocator<Size4KiB>,
-    address: PhysAddr,
-    page: Page,
-) {
-    use x86_64::structures::paging::PageTableFlags as Flags;
-    let frame = PhysFrame::containing_address(address);
-    let flags = Flags::PRESENT | Flags::WRITABLE;
-    let mut mapper = {
-        let offset = VirtAddr::new(PHYSICAL_OFFSET);
-        OffsetPageTable::new(super::l4_page_table_at(offset), offset)
-    };
-    let map_to_result = {
-        // FIXME: this is not safe, we do it only for testing
-        mapper.map_to(page, frame, flags, frame_allocator)
-    };
-    map_to_result.expect("map_to failed").flush();
+fn unmap_error(error: UnmapError) -> &'static str {
+    match error {
+        UnmapError::PageNotMapped => "Page Not Mapped",
+        UnmapError::ParentEntryHugePage => "Address Is Inside A Huge Page",
+        UnmapError::InvalidFrameAddress(_) => "Page Maps An Invalid Frame",
+    }
+}
+
+/// Map A Page To The Given Frame & Flush It From The TLB.
+pub fn map(page: Page, frame: PhysFrame, flags: PageTableFlags) -> KResult<()> {
+    with_mapper(|mapper| unsafe {
+        mapper
+            .map_to(page, frame, flags | PageTableFlags::PRESENT, &mut GlobalFrameAllocator)
+            .map_err(map_error)?
+            .flush();
+        Ok(())
+    })
 }
 
+/// Map A Page To A Freshly Allocated Frame, Returns The Frame.
+pub fn map_alloc(page: Page, flags: PageTableFlags) -> KResult<PhysFrame> {
+    let frame = frame_allocator::allocate().ok_or("Out Of Physical Frames")?;
+    if let Err(e) = map(page, frame, flags) {
+        frame_allocator::free(frame)?;
+        return Err(e);
+    }
+    Ok(frame)
+}
+
+/// Unmap A Page & Flush It From The TLB, Returns The Frame It Mapped.
+/// The Frame Is Not Freed.
+pub fn unmap(page: Page) -> KResult<PhysFrame> {
+    with_mapper(|mapper| {
+        let (frame, flush) = mapper.unmap(page).map_err(unmap_error)?;
+        flush.flush();
+        Ok(frame)
+    })
+}
+
+/// Unmap A Page & Return Its Frame To The Frame Allocator.
+pub fn unmap_free(page: Page) -> KResult<()> {
+    frame_allocator::free(unmap(page)?)
+}
+
+/// Replace The Flags Of A Mapped Page & Flush It From The TLB.
+pub fn protect(page: Page, flags: PageTableFlags) -> KResult<()> {
+    with_mapper(|mapper| unsafe {
+        mapper
+            .update_flags(page, flags | PageTableFlags::PRESENT)
+            .map_err(|_| "Page Not Mapped")?
+            .flush();
+        Ok(())
+    })
+}
+
+/// Flush Every Page In The Range From The TLB.
+pub fn flush_range(start: VirtAddr, pages: u64) {
+    for i in 0..pages {
+        tlb::flush(start + i * Page::<Size4KiB>::SIZE);
+    }
+}
 
 /// Get The Physical Memory Offset
 pub fn physical_memory_offset() -> u64 {
     unsafe {PHYSICAL_OFFSET}
-}
\ No newline at end of file
+}
+
+/// Translate A Virtual Address Into A Physical Address.
+/// Returns None If The Address Is Unmapped, The Mapper Is Not Initialized Yet
+/// Or The Mapper Is Busy, So It Is Safe To Call From Fault Handlers.
+pub fn translate(address: VirtAddr) -> Option<PhysAddr> {
+    let mapper = MAPPER.try_lock()?;
+    mapper.as_ref()?.translate_addr(address)
+}
@@ -5,7 +5,7 @@ use crate::KResult;
 /// A [RingBuffer] Of 256 Elements
 pub type RingBuffer256<T> = RingBuffer<T, 256>;
 
-/// Zero Allocation Ringbuffer
+/// Zero Allocation Ringbuffer, Holds Up To N - 1 Elements.
 #[derive(Debug)]
 pub struct RingBuffer<T: Copy + Default, const N: usize> {
     read_ptr: usize,
@@ -18,7 +18,7 @@ impl<T: Copy + Default, const N: usize> RingBuffer<T, N> {
     pub fn new() -> Self {
         Self {
             read_ptr: 0,
-            write_ptr: 1,
+            write_ptr: 0,
             buf: [Default::default(); N],
         }
     }
@@ -37,7 +37,7 @@ impl<T: Copy + Default, const N: usize> RingBuffer<T, N> {
 
     /// Write From The Buffer, Returns Err If The Write Would Corrupt Data Yet To Be Read
     pub fn write(&mut self, value: T) -> KResult<()> {
-        if self.read_ptr != self.write_ptr {
+        if !self.is_full() {
             self.buf[self.write_ptr] = value;
             self.write_ptr += 1;
             self.write_ptr %= self.buf.len();
@@ -49,11 +49,16 @@ impl<T: Copy + Default, const N: usize> RingBuffer<T, N> {
 
     /// Checks Whether The Buffer Is 'Full'
     pub fn is_full(&self) -> bool {
-        self.read_ptr == self.write_ptr
+        (self.write_ptr + 1) % N == self.read_ptr
     }
 
     /// Checks Whether The Buffer Is 'Empty'
     pub fn is_empty(&self) -> bool {
         self.read_ptr == self.write_ptr
     }
+
+    /// The Number Of Elements Waiting To Be Read.
+    pub fn len(&self) -> usize {
+        (self.write_ptr + N - self.read_ptr) % N
+    }
 }
@@ -0,0 +1,59 @@
+//! Kernel Stacks.
+//! Each Stack Is Allocated From The [vmm](super::vmm) With An Unmapped
+//! [guard page](super::guard) Directly Below It.
+
+use x86_64::{
+    structures::paging::{Page, PageTableFlags, Size4KiB},
+    VirtAddr,
+};
+
+use super::{guard::{self, GuardKind}, mapper, vmm};
+use crate::KResult;
+
+/// The Default Size Of A Kernel Stack, 64KiB.
+pub const DEFAULT_STACK_PAGES: u64 = 16;
+const PAGE_SIZE: u64 = Page::<Size4KiB>::SIZE;
+
+/// A Kernel Stack, Unmapped & Freed On Drop.
+#[derive(Debug)]
+pub struct KernelStack {
+    /// The Guard Page, The Stack Starts One Page Above.
+    base: VirtAddr,
+    pages: u64,
+}
+
+impl KernelStack {
+    /// Allocate A Stack Of `pages` For The Task Named `owner`.
+    pub fn new(pages: u64, owner: &'static str) -> KResult<Self> {
+        let base = vmm::allocate(pages + 1, PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE)?;
+        let stack = Self { base, pages };
+        mapper::unmap_free(Page::containing_address(base))?;
+        guard::register(base, 1, GuardKind::Stack, owner)?;
+        Ok(stack)
+    }
+
+    /// The Initial Stack Pointer, Stacks Grow Downwards.
+    pub fn top(&self) -> VirtAddr {
+        self.base + (self.pages + 1) * PAGE_SIZE
+    }
+
+    /// The Lowest Usable Address.
+    pub fn bottom(&self) -> VirtAddr {
+        self.base + PAGE_SIZE
+    }
+
+    /// The Address Of The Guard Page.
+    pub fn guard(&self) -> VirtAddr {
+        self.base
+    }
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        let _ = guard::unregister(self.base);
+        for i in 1..=self.pages {
+            let _ = mapper::unmap_free(Page::containing_address(self.base + i * PAGE_SIZE));
+        }
+        let _ = vmm::release(self.base, self.pages + 1);
+    }
+}
@@ -0,0 +1,171 @@
+//! Kernel Virtual Memory Manager.
+//! Hands Out Page Aligned Regions Of The Kernel's Virtual Address Space &
+//! Backs Them With Frames Through The [mapper](super::mapper).
+//! Free Space Is Kept In A Fixed Size, Address Ordered List Of Ranges, So
+//! Reserving Address Space Never Touches The Heap.
+
+use spin::Mutex;
+use x86_64::{
+    structures::paging::{Page, PageTableFlags, Size4KiB},
+    VirtAddr,
+};
+
+use super::mapper;
+use crate::{no_interrupt, KResult};
+
+/// The Start Of The Kernel's Dynamic Virtual Region.
+pub const VMM_START: u64 = 0x_5555_0000_0000;
+/// The Size Of The Kernel's Dynamic Virtual Region, 64GiB.
+pub const VMM_SIZE: u64 = 64 * 1024 * 1024 * 1024;
+/// The Maximum Number Of Disjoint Free Ranges.
+const MAX_RANGES: usize = 128;
+const PAGE_SIZE: u64 = Page::<Size4KiB>::SIZE;
+
+#[derive(Debug, Clone, Copy)]
+struct Range {
+    start: u64,
+    pages: u64,
+}
+
+struct RegionAllocator {
+    free: [Range; MAX_RANGES],
+    len: usize,
+}
+
+impl RegionAllocator {
+    const fn new() -> Self {
+        let mut free = [Range { start: 0, pages: 0 }; MAX_RANGES];
+        free[0] = Range { start: VMM_START, pages: VMM_SIZE / PAGE_SIZE };
+        Self { free, len: 1 }
+    }
+
+    /// First Fit.
+    fn reserve(&mut self, pages: u64) -> Option<u64> {
+        let index = (0..self.len).find(|&i| self.free[i].pages >= pages)?;
+        let range = &mut self.free[index];
+        let start = range.start;
+        range.start += pages * PAGE_SIZE;
+        range.pages -= pages;
+        if range.pages == 0 {
+            self.free.copy_within(index + 1..self.len, index);
+            self.len -= 1;
+        }
+        Some(start)
+    }
+
+    /// Return A Range, Merging It With Its Neighbours.
+    fn release(&mut self, start: u64, pages: u64) -> KResult<()> {
+        let end = start + pages * PAGE_SIZE;
+        let index = (0..self.len).find(|&i| self.free[i].start >= start).unwrap_or(self.len);
+
+        let overlaps_prev = index > 0 && {
+            let prev = self.free[index - 1];
+            prev.start + prev.pages * PAGE_SIZE > start
+        };
+        if overlaps_prev || (index < self.len && self.free[index].start < end) {
+            return Err("Region Is Already Free");
+        }
+
+        let joins_prev = index > 0 && {
+            let prev = self.free[index - 1];
+            prev.start + prev.pages * PAGE_SIZE == start
+        };
+        let joins_next = index < self.len && self.free[index].start == end;
+
+        match (joins_prev, joins_next) {
+            (true, true) => {
+                self.free[index - 1].pages += pages + self.free[index].pages;
+                self.free.copy_within(index + 1..self.len, index);
+                self.len -= 1;
+            }
+            (true, false) => self.free[index - 1].pages += pages,
+            (false, true) => {
+                self.free[index].start = start;
+                self.free[index].pages += pages;
+            }
+            (false, false) => {
+                if self.len == MAX_RANGES {
+                    return Err("Virtual Region Table Is Full");
+                }
+                self.free.copy_within(index..self.len, index + 1);
+                self.free[index] = Range { start, pages };
+                self.len += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn free_pages(&self) -> u64 {
+        self.free[..self.len].iter().map(|r| r.pages).sum()
+    }
+}
+
+static REGIONS: Mutex<RegionAllocator> = Mutex::new(RegionAllocator::new());
+
+fn check(addr: VirtAddr, pages: u64) -> KResult<()> {
+    let start = addr.as_u64();
+    if pages == 0 || start % PAGE_SIZE != 0 {
+        return Err("Region Must Be Page Aligned & Non-Empty");
+    }
+    if start < VMM_START || start + pages * PAGE_SIZE > VMM_START + VMM_SIZE {
+        return Err("Region Is Outside The VMM Area");
+    }
+    Ok(())
+}
+
+fn page_range(addr: VirtAddr, pages: u64) -> impl Iterator<Item = Page> {
+    (0..pages).map(move |i| Page::containing_address(addr + i * PAGE_SIZE))
+}
+
+/// Reserve `pages` Of Virtual Address Space Without Mapping Anything.
+pub fn reserve(pages: u64) -> KResult<VirtAddr> {
+    if pages == 0 {
+        return Err("Region Must Be Non-Empty");
+    }
+    no_interrupt!({ REGIONS.lock().reserve(pages) })
+        .map(VirtAddr::new)
+        .ok_or("Out Of Virtual Address Space")
+}
+
+/// Return Reserved Address Space, Anything Still Mapped In It Stays Mapped.
+pub fn release(addr: VirtAddr, pages: u64) -> KResult<()> {
+    check(addr, pages)?;
+    no_interrupt!({ REGIONS.lock().release(addr.as_u64(), pages) })
+}
+
+/// Reserve `pages` & Back Every Page With A Fresh Frame.
+pub fn allocate(pages: u64, flags: PageTableFlags) -> KResult<VirtAddr> {
+    let addr = reserve(pages)?;
+    for (mapped, page) in page_range(addr, pages).enumerate() {
+        if let Err(e) = mapper::map_alloc(page, flags) {
+            for page in page_range(addr, mapped as u64) {
+                mapper::unmap_free(page)?;
+            }
+            release(addr, pages)?;
+            return Err(e);
+        }
+    }
+    Ok(addr)
+}
+
+/// Unmap A Region From [allocate], Freeing Its Frames & Address Space.
+pub fn free(addr: VirtAddr, pages: u64) -> KResult<()> {
+    check(addr, pages)?;
+    for page in page_range(addr, pages) {
+        mapper::unmap_free(page)?;
+    }
+    release(addr, pages)
+}
+
+/// Change The Flags Of Every Page In A Region.
+pub fn protect(addr: VirtAddr, pages: u64, flags: PageTableFlags) -> KResult<()> {
+    for page in page_range(addr, pages) {
+        mapper::protect(page, flags)?;
+    }
+    Ok(())
+}
+
+/// The Number Of Unreserved Pages In The VMM Area.
+pub fn free_pages() -> u64 {
+    no_interrupt!({ REGIONS.lock().free_pages() })
+}
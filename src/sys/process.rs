@@ -0,0 +1,295 @@
+//! The Process Table.
+//! Every User Program Gets A [Pid] & An Entry Holding Its [Process], The Thread
+//! Running It & Its Parent. A Program That Exits Becomes A Zombie: Its Memory &
+//! Files Are Freed Straight Away, But The Entry Keeps The Exit Status Until The
+//! Parent Collects It With [wait]. Orphans Are Handed To [KERNEL_PID].
+//! Children Start With A Copy Of Their Parent's Descriptors, See [with_files].
+
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
+use core::fmt;
+
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use super::fd::FdTable;
+use super::loader::Image;
+use super::thread::{self, JoinHandle, ThreadId};
+use super::user::{self, Process};
+use crate::{no_interrupt, KResult};
+
+/// A Unique Process Identifier, Never Reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pid(pub u64);
+
+impl fmt::Display for Pid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The Kernel & Its Threads, Including The Shell. Parent Of Everything Started Outside A Process.
+pub const KERNEL_PID: Pid = Pid(0);
+/// Killed Programs Exit With This, Like A Unix Shell Reports `SIGKILL`.
+pub const KILLED_EXIT_CODE: i64 = 137;
+/// Spawned Programs That Couldn't Enter User Mode Exit With This.
+pub const START_FAILED_EXIT_CODE: i64 = -1;
+
+/// What A Process Is Doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessState {
+    /// Started & Not Yet Exited.
+    Running,
+    /// Exited With The Given Code, Waiting To Be Collected.
+    Zombie(i64),
+}
+
+/// A Snapshot Of A Process, See [list].
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: Pid,
+    pub parent: Pid,
+    pub name: String,
+    pub state: ProcessState,
+    /// The Thread Running The Program, None Before It Starts & After It Exits.
+    pub thread: Option<ThreadId>,
+    /// Set By [kill], The Program Hasn't Noticed Yet.
+    pub killed: bool,
+}
+
+struct Entry {
+    parent: Pid,
+    name: String,
+    state: ProcessState,
+    thread: Option<ThreadId>,
+    /// The Program's Resources, Only Held While It Runs.
+    process: Option<Arc<Mutex<Process>>>,
+    killed: bool,
+    /// The Thread Started By [spawn], Joined By [wait].
+    handle: Option<JoinHandle>,
+}
+
+struct Table {
+    entries: BTreeMap<Pid, Entry>,
+    next_pid: u64,
+}
+
+impl Table {
+    /// The Running Entry Bound To `id`.
+    fn running_on(&self, id: ThreadId) -> Option<(Pid, &Entry)> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| entry.thread == Some(id) && entry.state == ProcessState::Running)
+            .map(|(&pid, entry)| (pid, entry))
+    }
+}
+
+lazy_static! {
+    static ref TABLE: Mutex<Table> = Mutex::new(Table { entries: BTreeMap::new(), next_pid: KERNEL_PID.0 + 1 });
+    /// The Kernel's Descriptors, Used By The Shell & Inherited By What It Runs.
+    static ref KERNEL_FILES: Mutex<FdTable> = Mutex::new(FdTable::standard());
+}
+
+/// Add A Process That Hasn't Started Yet, Returns Its Pid.
+pub fn create(name: &str, parent: Pid) -> Pid {
+    no_interrupt!({
+        let mut table = TABLE.lock();
+        let pid = Pid(table.next_pid);
+        table.next_pid += 1;
+        table.entries.insert(pid, Entry {
+            parent,
+            name: String::from(name),
+            state: ProcessState::Running,
+            thread: None,
+            process: None,
+            killed: false,
+            handle: None,
+        });
+        pid
+    })
+}
+
+/// Bind `pid` To The Current Thread While It Runs `process`.
+pub fn attach(pid: Pid, process: Arc<Mutex<Process>>) -> KResult<()> {
+    let (id, _) = thread::current().ok_or("Threads Are Not Running")?;
+    no_interrupt!({
+        let mut table = TABLE.lock();
+        if table.running_on(id).is_some() {
+            return Err("Thread Is Already Running A User Program");
+        }
+        let entry = match table.entries.get_mut(&pid) {
+            Some(entry) => entry,
+            None => return Err("No Such Process"),
+        };
+        if entry.state != ProcessState::Running || entry.process.is_some() {
+            return Err("Process Has Already Started");
+        }
+        entry.thread = Some(id);
+        entry.process = Some(process);
+        Ok(())
+    })
+}
+
+/// Turn `pid` Into A Zombie Holding `code`, Its Children Are Handed To [KERNEL_PID].
+/// Returns The Program's Resources, So The Caller Decides When They Are Freed.
+/// The Thread From [spawn] Is Let Go, So Its Slot Is Reaped Even If Nobody Waits.
+pub fn detach(pid: Pid, code: i64) -> Option<Arc<Mutex<Process>>> {
+    let (process, handle) = no_interrupt!({
+        let mut table = TABLE.lock();
+        for entry in table.entries.values_mut().filter(|entry| entry.parent == pid) {
+            entry.parent = KERNEL_PID;
+        }
+        let entry = table.entries.get_mut(&pid)?;
+        entry.state = ProcessState::Zombie(code);
+        entry.thread = None;
+        Some((entry.process.take(), entry.handle.take()))
+    })?;
+    // Dropped Outside The Lock, A Join Handle Takes The Scheduler's.
+    drop(handle);
+    process
+}
+
+/// Drop The Entry For `pid` Whatever Its State, Used Once Nobody Can Wait For It.
+pub fn release(pid: Pid) {
+    // Dropped Outside The Lock, A Join Handle Takes The Scheduler's.
+    let entry = no_interrupt!({ TABLE.lock().entries.remove(&pid) });
+    drop(entry);
+}
+
+/// The Pid & Resources Of The Program The Current Thread Is Running.
+pub fn current_process() -> Option<(Pid, Arc<Mutex<Process>>)> {
+    let (id, _) = thread::current()?;
+    no_interrupt!({
+        let table = TABLE.lock();
+        let (pid, entry) = table.running_on(id)?;
+        Some((pid, entry.process.clone()?))
+    })
+}
+
+/// The Current Process, [KERNEL_PID] Outside User Programs.
+pub fn current() -> Pid {
+    current_process().map_or(KERNEL_PID, |(pid, _)| pid)
+}
+
+/// The Current Process & Whether It Was [kill]ed, Without Blocking.
+/// None Outside User Programs Or If The Table Is Busy, Safe From Interrupt Handlers.
+pub fn try_current() -> Option<(Pid, bool)> {
+    let (id, _) = thread::current()?;
+    let table = TABLE.try_lock()?;
+    table.running_on(id).map(|(pid, entry)| (pid, entry.killed))
+}
+
+/// Run `f` On The Current Process's Descriptors, The Kernel's Outside User Programs.
+/// Never Call From Interrupt Handlers, Nor Block In `f`.
+pub fn with_files<T>(f: impl FnOnce(&mut FdTable) -> T) -> T {
+    match current_process() {
+        Some((_, process)) => {
+            let mut process = process.lock();
+            f(process.files_mut())
+        }
+        None => f(&mut KERNEL_FILES.lock()),
+    }
+}
+
+/// Run `image` On A New Thread As A Child Of The Current Process.
+pub fn spawn(mut image: Image) -> KResult<Pid> {
+    image.process.set_files(with_files(|files| files.clone()));
+    let pid = create(image.process.name(), current());
+    let handle = thread::spawn("user", move || {
+        let Image { process, entry, stack } = image;
+        if user::execute_as(pid, process, entry, stack).is_err() {
+            detach(pid, START_FAILED_EXIT_CODE);
+        }
+    });
+    match handle {
+        Ok(handle) => {
+            // A Program That Already Exited Has Nothing Left To Join.
+            let unused = no_interrupt!({
+                match TABLE.lock().entries.get_mut(&pid) {
+                    Some(entry) if entry.state == ProcessState::Running => entry.handle.replace(handle),
+                    _ => Some(handle),
+                }
+            });
+            drop(unused);
+            Ok(pid)
+        }
+        Err(e) => {
+            release(pid);
+            Err(e)
+        }
+    }
+}
+
+/// Block Until The Child `pid` Exits, Then Remove It & Return Its Exit Code.
+pub fn wait(pid: Pid) -> KResult<i64> {
+    let parent = current();
+    let handle = no_interrupt!({
+        let mut table = TABLE.lock();
+        let entry = match table.entries.get_mut(&pid) {
+            Some(entry) => entry,
+            None => return Err("No Such Process"),
+        };
+        if entry.parent != parent {
+            return Err("Not A Child Of The Current Process");
+        }
+        match (entry.state, entry.handle.take()) {
+            // The Thread Was Let Go By [detach], It Only Has To Return.
+            (ProcessState::Zombie(_), handle) => Ok(handle),
+            (ProcessState::Running, Some(handle)) => Ok(Some(handle)),
+            (ProcessState::Running, None) => Err("Process Can't Be Waited For"),
+        }
+    })?;
+    if let Some(handle) = handle {
+        // The Thread Only Exits After Turning The Process Into A Zombie.
+        handle.join()?;
+    }
+    let entry = no_interrupt!({ TABLE.lock().entries.remove(&pid) }).ok_or("No Such Process")?;
+    match entry.state {
+        ProcessState::Zombie(code) => Ok(code),
+        ProcessState::Running => Err("Process Is Still Running"),
+    }
+}
+
+/// Make `pid` Exit With [KILLED_EXIT_CODE].
+/// It Does So On Its Next System Call Or Timer Tick In User Mode, So A Program
+/// Blocked Reading The Keyboard Exits Once The Read Completes.
+pub fn kill(pid: Pid) -> KResult<()> {
+    if pid == KERNEL_PID {
+        return Err("The Kernel Can't Be Killed");
+    }
+    no_interrupt!({
+        let mut table = TABLE.lock();
+        let entry = match table.entries.get_mut(&pid) {
+            Some(entry) => entry,
+            None => return Err("No Such Process"),
+        };
+        if entry.state != ProcessState::Running {
+            return Err("Process Has Already Exited");
+        }
+        entry.killed = true;
+        Ok(())
+    })
+}
+
+/// A Snapshot Of `pid`.
+pub fn info(pid: Pid) -> Option<ProcessInfo> {
+    list().into_iter().find(|info| info.pid == pid)
+}
+
+/// A Snapshot Of Every Process.
+pub fn list() -> Vec<ProcessInfo> {
+    no_interrupt!({
+        TABLE
+            .lock()
+            .entries
+            .iter()
+            .map(|(&pid, entry)| ProcessInfo {
+                pid,
+                parent: entry.parent,
+                name: entry.name.clone(),
+                state: entry.state,
+                thread: entry.thread,
+                killed: entry.killed,
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,25 @@
+//! Utilities For QEMU Specific Devices.
+//! Requires `-device isa-debug-exit,iobase=0xf4,iosize=0x04`.
+
+use x86_64::instructions::port::Port;
+
+const ISA_DEBUG_EXIT_PORT: u16 = 0xF4;
+
+/// Exit Codes Written To The `isa-debug-exit` Device.
+/// QEMU Exits With `(code << 1) | 1`, So Success Becomes 33.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum QemuExitCode {
+    /// All Tests Passed.
+    Success = 0x10,
+    /// A Test Failed.
+    Failed = 0x11,
+}
+
+/// Exit QEMU With The Given Code, Does Nothing If The Device Is Missing.
+pub fn exit_qemu(exit_code: QemuExitCode) {
+    unsafe {
+        let mut port: Port<u32> = Port::new(ISA_DEBUG_EXIT_PORT);
+        port.write(exit_code as u32);
+    }
+}
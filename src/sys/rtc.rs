@@ -0,0 +1,276 @@
+//! CMOS Real-Time Clock Driver & Wall-Clock Time.
+//! The RTC Is Read Once At Boot, [realtime] Then Advances It With The Monotonic Clock.
+//! <https://wiki.osdev.org/CMOS>
+
+use core::fmt::{self, Display};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use x86_64::instructions::port::Port;
+
+use super::interrupt::{self, IrqResult};
+use super::timer::Instant;
+use crate::{no_interrupt, KResult};
+
+const CMOS_ADDRESS: u16 = 0x70;
+const CMOS_DATA: u16 = 0x71;
+/// Set In The Address Port To Keep NMIs Disabled While Accessing A Register.
+/// The Register Is Selected Again Without It Afterwards, Which Re-Enables Them.
+const NMI_DISABLE: u8 = 0x80;
+
+const REG_SECONDS: u8 = 0x00;
+const REG_MINUTES: u8 = 0x02;
+const REG_HOURS: u8 = 0x04;
+const REG_DAY: u8 = 0x07;
+const REG_MONTH: u8 = 0x08;
+const REG_YEAR: u8 = 0x09;
+const REG_STATUS_A: u8 = 0x0A;
+const REG_STATUS_B: u8 = 0x0B;
+const REG_STATUS_C: u8 = 0x0C;
+/// The Century Register QEMU & Most ACPI Machines Provide.
+const REG_CENTURY: u8 = 0x32;
+
+const STATUS_A_UPDATE_IN_PROGRESS: u8 = 0x80;
+const STATUS_B_24_HOUR: u8 = 0x02;
+const STATUS_B_BINARY: u8 = 0x04;
+const STATUS_B_PERIODIC: u8 = 0x40;
+const STATUS_C_PERIODIC: u8 = 0x40;
+const HOUR_PM: u8 = 0x80;
+
+/// The RTC IRQ Line.
+pub const RTC_IRQ: usize = 8;
+/// The Base Frequency Of The RTC's Periodic Interrupt Divider.
+const PERIODIC_BASE: u32 = 32768;
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+static BOOT_TIME: AtomicU64 = AtomicU64::new(0);
+static BOOT_NANOS: AtomicU64 = AtomicU64::new(0);
+static PERIODIC_TICKS: AtomicU64 = AtomicU64::new(0);
+
+fn read_register(reg: u8) -> u8 {
+    let mut address: Port<u8> = Port::new(CMOS_ADDRESS);
+    let mut data: Port<u8> = Port::new(CMOS_DATA);
+    unsafe {
+        address.write(NMI_DISABLE | reg);
+        let value = data.read();
+        address.write(reg);
+        value
+    }
+}
+
+fn write_register(reg: u8, value: u8) {
+    let mut address: Port<u8> = Port::new(CMOS_ADDRESS);
+    let mut data: Port<u8> = Port::new(CMOS_DATA);
+    unsafe {
+        address.write(NMI_DISABLE | reg);
+        data.write(value);
+        address.write(reg);
+    }
+}
+
+fn update_in_progress() -> bool {
+    read_register(REG_STATUS_A) & STATUS_A_UPDATE_IN_PROGRESS != 0
+}
+
+fn from_bcd(value: u8) -> u8 {
+    (value & 0x0F) + (value >> 4) * 10
+}
+
+/// A Calendar Date & Time, Always UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+/// Days Since 1970-01-01 For A Civil Date.
+/// <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// The Civil Date For A Count Of Days Since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+impl DateTime {
+    /// Seconds Since The Unix Epoch.
+    pub fn unix_timestamp(&self) -> u64 {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        days as u64 * SECONDS_PER_DAY
+            + self.hour as u64 * 3600
+            + self.minute as u64 * 60
+            + self.second as u64
+    }
+
+    /// Convert Seconds Since The Unix Epoch Into A Date & Time.
+    pub fn from_unix(timestamp: u64) -> Self {
+        let (year, month, day) = civil_from_days((timestamp / SECONDS_PER_DAY) as i64);
+        let seconds = timestamp % SECONDS_PER_DAY;
+        Self {
+            year: year as u16,
+            month,
+            day,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Read The Raw Registers, Waiting Out Any Update In Progress.
+fn read_raw() -> [u8; 7] {
+    while update_in_progress() {
+        core::hint::spin_loop();
+    }
+    [
+        read_register(REG_SECONDS),
+        read_register(REG_MINUTES),
+        read_register(REG_HOURS),
+        read_register(REG_DAY),
+        read_register(REG_MONTH),
+        read_register(REG_YEAR),
+        read_register(REG_CENTURY),
+    ]
+}
+
+/// Read The Current Date & Time From The CMOS.
+/// Reads Until Two Consecutive Reads Agree, So An Update Can't Tear The Result.
+pub fn read() -> DateTime {
+    let mut raw = read_raw();
+    loop {
+        let again = read_raw();
+        if again == raw {
+            break;
+        }
+        raw = again;
+    }
+    let [mut second, mut minute, mut hour, mut day, mut month, mut year, mut century] = raw;
+
+    let status = read_register(REG_STATUS_B);
+    let pm = hour & HOUR_PM != 0;
+    hour &= !HOUR_PM;
+
+    if status & STATUS_B_BINARY == 0 {
+        second = from_bcd(second);
+        minute = from_bcd(minute);
+        hour = from_bcd(hour);
+        day = from_bcd(day);
+        month = from_bcd(month);
+        year = from_bcd(year);
+        century = from_bcd(century);
+    }
+
+    // 12 Hour Clocks Run 12, 1 .. 11.
+    if status & STATUS_B_24_HOUR == 0 {
+        hour %= 12;
+        if pm {
+            hour += 12;
+        }
+    }
+
+    let century = if century == 0 { 20 } else { century as u16 };
+    DateTime {
+        year: century * 100 + year as u16,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+    }
+}
+
+/// Read The RTC & Start Wall-Clock Time, Install The IRQ 8 Handler.
+pub fn initialize() -> KResult<()> {
+    let now = read();
+    no_interrupt!({
+        BOOT_TIME.store(now.unix_timestamp(), Ordering::Relaxed);
+        BOOT_NANOS.store(Instant::now().as_nanos(), Ordering::Relaxed);
+    });
+    interrupt::set_irq_handler(RTC_IRQ, on_rtc_interrupt)
+}
+
+/// Nanoseconds Since The Unix Epoch.
+pub fn realtime_nanos() -> u64 {
+    let elapsed = Instant::now().as_nanos() - BOOT_NANOS.load(Ordering::Relaxed);
+    BOOT_TIME.load(Ordering::Relaxed) * 1_000_000_000 + elapsed
+}
+
+/// Seconds Since The Unix Epoch.
+pub fn realtime() -> u64 {
+    realtime_nanos() / 1_000_000_000
+}
+
+/// The Current Date & Time.
+pub fn now() -> DateTime {
+    DateTime::from_unix(realtime())
+}
+
+/// Enable The Periodic Interrupt At 32768 >> (rate - 1) Hz.
+/// Rate Must Be Between 3 (8192Hz) And 15 (2Hz), Returns The Frequency.
+pub fn enable_periodic(rate: u8) -> KResult<u32> {
+    if !(3..=15).contains(&rate) {
+        return Err("RTC Rate Must Be Between 3 And 15");
+    }
+    no_interrupt!({
+        let a = read_register(REG_STATUS_A);
+        write_register(REG_STATUS_A, (a & 0xF0) | rate);
+        let b = read_register(REG_STATUS_B);
+        write_register(REG_STATUS_B, b | STATUS_B_PERIODIC);
+        // Clear Any Pending Interrupt So The Next One Fires.
+        read_register(REG_STATUS_C);
+    });
+    Ok(PERIODIC_BASE >> (rate - 1))
+}
+
+/// Stop The Periodic Interrupt.
+pub fn disable_periodic() {
+    no_interrupt!({
+        let b = read_register(REG_STATUS_B);
+        write_register(REG_STATUS_B, b & !STATUS_B_PERIODIC);
+    });
+}
+
+/// The Number Of Periodic Interrupts Since Boot.
+pub fn periodic_ticks() -> u64 {
+    PERIODIC_TICKS.load(Ordering::Relaxed)
+}
+
+/// IRQ8 Handler, Status C Must Be Read Or The RTC Won't Interrupt Again.
+fn on_rtc_interrupt(_: u8) -> IrqResult {
+    let status = read_register(REG_STATUS_C);
+    if status & STATUS_C_PERIODIC != 0 {
+        PERIODIC_TICKS.fetch_add(1, Ordering::Relaxed);
+    }
+    IrqResult::Handled
+}
@@ -23,6 +23,17 @@ pub fn _print(args: Arguments) {
     });
 }
 
+/// Print To COMM 0 From A Fault Handler, Taking The Port Even If The Fault
+/// Interrupted Whoever Held It, So The Output May Interleave With Theirs.
+pub fn force_print(args: Arguments) {
+    no_interrupt!({
+        if COMM0.is_locked() {
+            unsafe { COMM0.force_unlock() };
+        }
+        let _ = COMM0.lock().write_fmt(args);
+    });
+}
+
 /// Print To COMM 0
 #[macro_export]
 macro_rules! sprint {
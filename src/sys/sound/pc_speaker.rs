@@ -1,8 +1,14 @@
 //! API For Iteracting With The Internal PC Speaker.
 
 use bit_field::BitField;
+use spin::Mutex;
 use x86_64::instructions::port::Port;
 
+use crate::sys::timer::{self, Duration, TimerHandle};
+use crate::KResult;
+
+static PENDING_STOP: Mutex<Option<TimerHandle>> = Mutex::new(None);
+
 /// Set The PC Speaker's Pitch To The Given Frequency.
 pub fn set_pitch(freq: f64) {
     crate::sys::timer::set_frequency_ch2(freq);
@@ -29,3 +35,17 @@ pub fn stop() {
         }
     }
 }
+
+/// Play The Given Pitch & Return Straight Away, A Kernel Timer Stops It.
+/// A New Beep Replaces One Still Playing.
+pub fn beep(freq: f64, duration: Duration) -> KResult<TimerHandle> {
+    let mut pending = PENDING_STOP.lock();
+    if let Some(handle) = pending.take() {
+        handle.cancel();
+    }
+    set_pitch(freq);
+    play();
+    let handle = timer::after(duration, |_| stop(), 0)?;
+    *pending = Some(handle);
+    Ok(handle)
+}
@@ -15,7 +15,7 @@ pub mod mfs;
 
 /// Link IRQ 14 & 15 To The ATA Handlers.
 pub fn initialize() -> KResult<()> {
-    sys::interrupt::set_irq_handler(14, ata::bus_0_irq);
-    sys::interrupt::set_irq_handler(15, ata::bus_1_irq);
+    sys::interrupt::set_irq_handler(14, ata::bus_0_irq)?;
+    sys::interrupt::set_irq_handler(15, ata::bus_1_irq)?;
     Ok(())
 }
@@ -1,6 +1,10 @@
 //! Interfaces With ATA PIO-LBA24 Disks.
 //! <https://wiki.osdev.org/ATA_PIO_Mode>
 use core::fmt::Debug;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::{Context, Poll};
 
 use alloc::string::String;
 use bit_field::BitField;
@@ -9,8 +13,10 @@ use x86_64::instructions::port::{Port, PortReadOnly, PortWriteOnly};
 use crate::{
     log,
     sys::{
+        interrupt::IrqResult,
+        task::WakerCell,
         terminal::Spinner,
-        timer::{self, sleep_ticks, uptime},
+        timer::{self, sleep_ticks, uptime, Duration, Instant},
     },
     KResult,
 };
@@ -208,6 +214,33 @@ impl Disk {
         self.ata_bus.read_lba_24(self.disk, addr, buf);
         Ok(())
     }
+    /// Read A Sector, Awaiting The Drive's IRQ Instead Of Polling Its Status.
+    pub async fn read_async(&mut self, addr: SectorIndex, buf: &mut [u8]) -> KResult<()> {
+        let irq = BusIrq::new(self.bus);
+        self.ata_bus.setup(self.disk, addr);
+        self.ata_bus.write_command(Command::ReadSectors);
+        irq.await;
+        if self.ata_bus.is_err() {
+            return Err("Drive Reported An Error");
+        }
+        self.ata_bus.read_sector(buf);
+        Ok(())
+    }
+
+    /// Write A Sector, Awaiting The Drive's IRQ Once It Has Been Committed.
+    pub async fn write_async(&mut self, addr: SectorIndex, buf: &[u8]) -> KResult<()> {
+        let irq = BusIrq::new(self.bus);
+        self.ata_bus.setup(self.disk, addr);
+        self.ata_bus.write_command(Command::WriteSectors);
+        self.ata_bus.buzy_loop();
+        self.ata_bus.write_sector(buf);
+        irq.await;
+        if self.ata_bus.is_err() {
+            return Err("Drive Reported An Error");
+        }
+        Ok(())
+    }
+
     /// Write To The Given Sector Data Onto The Disk. Writes Until Buffer Is 'Empty' OR if 512 Bytes
     /// Have Been Written.
     pub fn write(&mut self, addr: SectorIndex, buf: &[u8]) -> KResult<()> {
@@ -215,18 +248,16 @@ impl Disk {
         Ok(())
     }
 
-    /// Times The Read & Write Speed Of The Disk.
+    /// Times The Read & Write Speed Of The Disk, In Bytes Per Second.
     pub fn bandwidth_test(&mut self, index: SectorIndex) -> (f64, f64) {
         let mut buffer: [u8; 512] = [0; 512];
-        let mut start = uptime();
+        let start = Instant::now();
         self.read(index, &mut buffer).expect("");
-        let mut end = uptime();
-        let read_bandwidth = 1.0 / ((end - start) / 512.0);
+        let read_bandwidth = buffer.len() as f64 / start.elapsed().as_secs_f64();
 
-        start = uptime();
+        let start = Instant::now();
         self.write(index, &buffer).expect("");
-        end = uptime();
-        let write_bandwidth = 1.0 / ((end - start) / 512.0);
+        let write_bandwidth = buffer.len() as f64 / start.elapsed().as_secs_f64();
 
         (read_bandwidth, write_bandwidth)
     }
@@ -275,6 +306,10 @@ impl Bus {
         self.write_command(Command::ReadSectors);
         self.buzy_loop();
         //print!("Transfering Data");
+        self.read_sector(buf);
+    }
+
+    fn read_sector(&mut self, buf: &mut [u8]) {
         for i in (0..512).step_by(2) {
             let data = self.read_data();
             buf[i + 0] = data.get_bits(0..8) as u8;
@@ -286,13 +321,17 @@ impl Bus {
         self.setup(drive, index);
         self.write_command(Command::WriteSectors);
         self.buzy_loop();
+        self.write_sector(buf);
+        Ok(())
+    }
+
+    fn write_sector(&mut self, buf: &[u8]) {
         for i in (0..512).step_by(2) {
             let mut data = 0 as u16;
             data.set_bits(0..8, buf[i + 0] as u16);
             data.set_bits(8..16, buf[i + 1] as u16);
             self.write_data(data);
         }
-        Ok(())
     }
 
     fn is_busy(&mut self) -> bool {
@@ -309,8 +348,9 @@ impl Bus {
         self.io_reg.status().ready
     }
 
+    /// Drives Need 400ns After A Command Before The Status Is Valid.
     fn wait(&self) {
-        timer::sleep_ticks(1);
+        timer::sleep(Duration::from_nanos(400));
     }
 
     fn buzy_loop(&mut self) {
@@ -431,11 +471,66 @@ impl Into<u8> for Command {
     }
 }
 
+static IRQ_COUNT: [AtomicU64; 2] = [AtomicU64::new(0), AtomicU64::new(0)];
+static IRQ_WAKERS: [WakerCell; 2] = [WakerCell::new(), WakerCell::new()];
+
+/// Resolves Once The Bus Raises An IRQ After The Future Was Created.
+/// Create It Before Issuing The Command, So A Fast Drive Can't Be Missed.
+#[derive(Debug)]
+struct BusIrq {
+    bus: usize,
+    seen: u64,
+}
+
+impl BusIrq {
+    fn new(bus: u8) -> Self {
+        let bus = bus as usize;
+        Self { bus, seen: IRQ_COUNT[bus].load(Ordering::SeqCst) }
+    }
+
+    fn fired(&self) -> bool {
+        IRQ_COUNT[self.bus].load(Ordering::SeqCst) != self.seen
+    }
+}
+
+impl Future for BusIrq {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.fired() {
+            return Poll::Ready(());
+        }
+        IRQ_WAKERS[self.bus].register(cx.waker());
+        if self.fired() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// The Number Of IRQs The Bus Has Raised Since Boot.
+pub fn irq_count(bus: u8) -> u64 {
+    IRQ_COUNT[bus as usize % 2].load(Ordering::SeqCst)
+}
+
 #[doc(hidden)]
-pub fn bus_0_irq(_: u8) {}
+/// Reading The Status Register Acknowledges The Drive's Interrupt.
+pub fn bus_0_irq(_: u8) -> IrqResult {
+    IoRegisters::new(0x1F0).status();
+    IRQ_COUNT[0].fetch_add(1, Ordering::SeqCst);
+    IRQ_WAKERS[0].wake();
+    IrqResult::Handled
+}
 
 #[doc(hidden)]
-pub fn bus_1_irq(_: u8) {}
+/// Reading The Status Register Acknowledges The Drive's Interrupt.
+pub fn bus_1_irq(_: u8) -> IrqResult {
+    IoRegisters::new(0x170).status();
+    IRQ_COUNT[1].fetch_add(1, Ordering::SeqCst);
+    IRQ_WAKERS[1].wake();
+    IrqResult::Handled
+}
 
 #[deprecated]
 /// Read From The Given Drive.
@@ -459,6 +554,20 @@ pub fn read_block(drive: usize, addr: SectorIndex) -> KResult<[u8; 512]> {
     }
 }
 
+/// Read A Block, Awaiting The Drive's IRQ.
+pub async fn read_block_async(drive: usize, addr: SectorIndex) -> KResult<[u8; 512]> {
+    let mut disk = Disk::new(drive).ok_or("Failed To Get Drive.")?;
+    let mut buf = [0; 512];
+    disk.read_async(addr, &mut buf).await?;
+    Ok(buf)
+}
+
+/// Write A Block, Awaiting The Drive's IRQ.
+pub async fn write_block_async(drive: usize, addr: SectorIndex, buf: &[u8]) -> KResult<()> {
+    let mut disk = Disk::new(drive).ok_or("Failed To Get Drive.")?;
+    disk.write_async(addr, buf).await
+}
+
 /// Write To The Given Drive.
 pub fn write(drive: usize, addr: SectorIndex, buf: &[u8]) -> KResult<()> {
     if let Some(mut disk) = Disk::new(drive) {
@@ -1,5 +1,7 @@
 use alloc::string::String;
 
+use crate::sys::rtc::realtime;
+
 use super::{super_block::SuperBlock, dir_entry::DirEntry, read_dir::ReadDir, api::{realpath, dirname, filename}, linked_block::LinkedBlock, FileType, bitmap_block::BitmapBlock};
 
 #[derive(Debug, Clone, Copy)]
@@ -105,7 +107,7 @@ impl Dir {
         let entry_kind = kind as u8;
         let entry_addr = entry_block.addr();
         let entry_size = 0u32;
-        let entry_time = 0u64;
+        let entry_time = realtime();
         let entry_name = truncate(name, u8::MAX as usize);
         let n = entry_name.len();
         let i = entries.block_data_offset();
@@ -124,7 +126,7 @@ impl Dir {
     }
 
     pub fn update_entry(&mut self, name: &str, size: u32) {
-        let time = 0u64;
+        let time = realtime();
         let mut entries = self.entries();
         for entry in &mut entries {
             if entry.name() == name {
@@ -0,0 +1,168 @@
+//! Cooperative Async Executor For Kernel Tasks.
+//! Tasks Are Futures Polled By The `executor` Thread Whenever Their Waker Fires,
+//! Wakers Are Usually Driven By Interrupt Handlers Through A [WakerCell].
+//! [block_on] Runs A Single Future On The Calling Thread, Parked Between Wakes.
+
+pub mod waker;
+
+use alloc::{boxed::Box, collections::BTreeMap, sync::Arc, task::Wake};
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use super::mem::ringbuffer::RingBuffer;
+use super::thread::{self, ThreadId};
+use super::timer::Duration;
+use super::workqueue;
+use crate::{no_interrupt, KResult};
+
+pub use waker::WakerCell;
+
+/// The Maximum Number Of Wakes Waiting To Be Polled.
+pub const READY_QUEUE_SIZE: usize = 256;
+/// How Long A Waiting Thread Stays Parked Before Polling Again Anyway.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A Unique Task Identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct TaskId(pub u64);
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()> + Send>>,
+    waker: Waker,
+}
+
+lazy_static! {
+    static ref READY: Mutex<RingBuffer<TaskId, READY_QUEUE_SIZE>> = Mutex::new(RingBuffer::new());
+    static ref TASKS: Mutex<BTreeMap<TaskId, Task>> = Mutex::new(BTreeMap::new());
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+/// The Thread Polling Spawned Tasks Plus One, Zero Until It Starts.
+static EXECUTOR: AtomicU64 = AtomicU64::new(0);
+
+/// Queues Its Task & Wakes The Executor Thread.
+struct TaskWaker {
+    id: TaskId,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        // A Full Queue Already Holds Wakes For Busy Tasks, Dropping One Is Harmless.
+        let _ = no_interrupt!({ READY.lock().write(self.id) });
+        match EXECUTOR.load(Ordering::Relaxed) {
+            0 => {}
+            executor => thread::wake(ThreadId(executor - 1)),
+        }
+    }
+}
+
+/// Start The Executor Thread, Must Run After Threads Are Initialized.
+pub fn initialize() -> KResult<()> {
+    let handle = thread::spawn("executor", run)?;
+    EXECUTOR.store(handle.id().0 + 1, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Run `future` To Completion On The Executor Thread.
+pub fn spawn(future: impl Future<Output = ()> + Send + 'static) -> TaskId {
+    let id = TaskId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let waker = Waker::from(Arc::new(TaskWaker { id }));
+    let task = Task { future: Box::pin(future), waker: waker.clone() };
+    no_interrupt!({ TASKS.lock().insert(id, task) });
+    waker.wake();
+    id
+}
+
+/// The Number Of Spawned Tasks That Have Not Finished.
+pub fn pending() -> usize {
+    no_interrupt!({ TASKS.lock().len() })
+}
+
+/// Poll Every Woken Task Once, Returns How Many Were Polled.
+pub fn run_ready() -> usize {
+    let mut polled = 0;
+    while let Some(id) = no_interrupt!({ READY.lock().read() }) {
+        // Taken Out While Polling, So The Task Can Spawn Others.
+        let task = no_interrupt!({ TASKS.lock().remove(&id) });
+        let mut task = match task {
+            Some(task) => task,
+            // Already Finished, The Wake Was A Duplicate.
+            None => continue,
+        };
+        polled += 1;
+        let mut context = Context::from_waker(&task.waker);
+        if task.future.as_mut().poll(&mut context).is_pending() {
+            no_interrupt!({ TASKS.lock().insert(id, task) });
+        }
+    }
+    polled
+}
+
+/// The Executor Thread.
+fn run() {
+    loop {
+        workqueue::run_pending();
+        run_ready();
+        // Checked With Interrupts Off, So A Wake Can't Slip In Before Parking.
+        no_interrupt!({
+            if READY.lock().is_empty() {
+                thread::park(IDLE_TIMEOUT);
+            }
+        });
+    }
+}
+
+/// Wakes The Thread Blocked In [block_on].
+struct ThreadWaker {
+    thread: Option<ThreadId>,
+    woken: AtomicBool,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread {
+            thread::wake(thread);
+        }
+    }
+}
+
+/// Run `future` On The Calling Thread, Parked Until It Is Woken.
+/// Before Threads Start This Halts Between Polls Instead.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let state = Arc::new(ThreadWaker {
+        thread: thread::current().map(|(id, _)| id),
+        woken: AtomicBool::new(false),
+    });
+    let waker = Waker::from(state.clone());
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        workqueue::run_pending();
+        if state.thread.is_none() {
+            crate::spin();
+            continue;
+        }
+        no_interrupt!({
+            if !state.woken.swap(false, Ordering::SeqCst) {
+                thread::park(IDLE_TIMEOUT);
+            }
+        });
+    }
+}
@@ -0,0 +1,44 @@
+//! Wakers Shared With Interrupt Handlers.
+
+use core::task::Waker;
+
+use spin::Mutex;
+
+use crate::no_interrupt;
+
+/// Holds The Waker Of Whatever Is Waiting On An Event.
+/// Futures [register](WakerCell::register) Before Returning `Pending`, The Event's
+/// Interrupt Handler Calls [wake](WakerCell::wake), Neither Allocates.
+#[derive(Debug)]
+pub struct WakerCell {
+    waker: Mutex<Option<Waker>>,
+}
+
+impl WakerCell {
+    pub const fn new() -> Self {
+        Self { waker: Mutex::new(None) }
+    }
+
+    /// Wake `waker` On The Next Event, Replacing Any Earlier Waiter.
+    pub fn register(&self, waker: &Waker) {
+        no_interrupt!({
+            let mut slot = self.waker.lock();
+            if !matches!(&*slot, Some(w) if w.will_wake(waker)) {
+                *slot = Some(waker.clone());
+            }
+        })
+    }
+
+    /// Wake The Registered Waiter, If There Is One.
+    pub fn wake(&self) {
+        if let Some(waker) = no_interrupt!({ self.waker.lock().take() }) {
+            waker.wake();
+        }
+    }
+}
+
+impl Default for WakerCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
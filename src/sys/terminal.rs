@@ -48,15 +48,31 @@ pub fn _clear(fg: Color, bg: Color) {
 
 #[doc(hidden)]
 pub fn _eprint(args: core::fmt::Arguments) {
+    no_interrupt!({ write_error(&mut WRITER.lock(), args) });
+}
+
+/// Like `eprint!`, But Gives Up If The Terminal Is Busy, For Fault Handlers.
+/// Returns False If Nothing Was Printed.
+pub fn try_eprint(args: core::fmt::Arguments) -> bool {
     no_interrupt!({
-        let mut writer = WRITER.lock();
-        let fg = writer.fg();
-        let bg = writer.bg();
-        writer.set_bg(Color::Red);
-        writer.write_fmt(args).expect("Failed To Write To VGA");
-        writer.set_bg(bg);
-        writer.set_fg(fg);
-    });
+        match WRITER.try_lock() {
+            Some(mut writer) => {
+                write_error(&mut writer, args);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Write `args` On A Red Background, Then Restore The Colors.
+fn write_error(writer: &mut TerminalWriter, args: core::fmt::Arguments) {
+    let fg = writer.fg();
+    let bg = writer.bg();
+    writer.set_bg(Color::Red);
+    writer.write_fmt(args).expect("Failed To Write To VGA");
+    writer.set_bg(bg);
+    writer.set_fg(fg);
 }
 
 /// Handles Writing To A VGA Screen Buffer.
@@ -0,0 +1,475 @@
+//! Preemptive Kernel Threads.
+//! Every Thread Runs On Its Own [KernelStack], The Timer Tick Ends Each Time Slice
+//! & The Scheduler Picks The Next Runnable Thread Round-Robin.
+//! The Boot Context Becomes The `kernel` Thread, An `idle` Thread Runs When
+//! Nothing Else Can.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::arch::global_asm;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+use x86_64::registers::control::Cr3;
+use x86_64::registers::model_specific::FsBase;
+use x86_64::structures::paging::PhysFrame;
+use x86_64::VirtAddr;
+
+use super::mem::address_space;
+use super::mem::stack::{KernelStack, DEFAULT_STACK_PAGES};
+use super::user;
+use super::timer::{self, Duration, TICK_NANOS};
+use crate::{no_interrupt, KResult};
+
+/// The Maximum Number Of Threads, Including Exited Ones Not Yet Joined.
+pub const MAX_THREADS: usize = 64;
+/// The Number Of Ticks A Thread Runs Before Being Preempted.
+pub const TIME_SLICE: u32 = 10;
+
+const KERNEL_SLOT: usize = 0;
+const IDLE_SLOT: usize = 1;
+
+/// A Unique Thread Identifier, Never Reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ThreadId(pub u64);
+
+/// What A Thread Is Doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    /// Waiting For The CPU.
+    Ready,
+    /// On The CPU.
+    Running,
+    /// Waiting Until The Given Tick.
+    Sleeping(u64),
+    /// Waiting For A [wake], Or Until The Given Tick.
+    Blocked(u64),
+    /// Waiting For Another Thread To Exit.
+    Joining(ThreadId),
+    /// Finished, Waiting To Be Joined.
+    Exited,
+}
+
+/// A Snapshot Of A Thread, See [list].
+#[derive(Debug, Clone, Copy)]
+pub struct ThreadInfo {
+    pub id: ThreadId,
+    pub name: &'static str,
+    pub state: ThreadState,
+    /// The Number Of Times The Thread Was Switched To.
+    pub switches: u64,
+}
+
+struct Thread {
+    id: ThreadId,
+    name: &'static str,
+    state: ThreadState,
+    /// The Saved Stack Pointer While Switched Out.
+    rsp: u64,
+    /// None For The Boot Thread, Which Runs On The Bootloader's Stack.
+    stack: Option<KernelStack>,
+    /// Nobody Holds A [JoinHandle], Free The Thread As Soon As It Exits.
+    detached: bool,
+    switches: u64,
+    /// The Ring 0 Stack For Entries From User Mode, See [user::kernel_stack].
+    kernel_rsp: u64,
+    /// The Active Address Space, Swapped While A User Program Runs.
+    cr3: PhysFrame,
+    /// The Thread Pointer A User Program Set With `arch_prctl`.
+    fs_base: VirtAddr,
+}
+
+impl Thread {
+    fn info(&self) -> ThreadInfo {
+        ThreadInfo { id: self.id, name: self.name, state: self.state, switches: self.switches }
+    }
+
+    fn is_runnable(&self, tick: u64) -> bool {
+        match self.state {
+            ThreadState::Ready => true,
+            ThreadState::Sleeping(until) | ThreadState::Blocked(until) => until <= tick,
+            _ => false,
+        }
+    }
+}
+
+struct Scheduler {
+    threads: [Option<Thread>; MAX_THREADS],
+    current: usize,
+    next_id: u64,
+}
+
+impl Scheduler {
+    const fn new() -> Self {
+        const EMPTY: Option<Thread> = None;
+        Self { threads: [EMPTY; MAX_THREADS], current: KERNEL_SLOT, next_id: 0 }
+    }
+
+    fn current(&mut self) -> &mut Thread {
+        self.threads[self.current].as_mut().expect("Current Thread Missing")
+    }
+
+    fn slot_of(&self, id: ThreadId) -> Option<usize> {
+        self.threads.iter().position(|t| matches!(t, Some(t) if t.id == id))
+    }
+
+    fn add(&mut self, name: &'static str, stack: Option<KernelStack>, rsp: u64) -> KResult<ThreadId> {
+        let slot = self.threads.iter().position(Option::is_none).ok_or("Thread Table Is Full")?;
+        let id = ThreadId(self.next_id);
+        self.next_id += 1;
+        // The Boot Thread's Values Are Saved On Its First Switch.
+        let kernel_rsp = stack.as_ref().map_or(0, |s| s.top().as_u64());
+        self.threads[slot] = Some(Thread {
+            id,
+            name,
+            state: ThreadState::Ready,
+            rsp,
+            stack,
+            detached: false,
+            switches: 0,
+            kernel_rsp,
+            cr3: address_space::kernel_l4(),
+            fs_base: VirtAddr::zero(),
+        });
+        Ok(id)
+    }
+
+    /// The Next Runnable Thread After The Current One, The Idle Thread If There Are None.
+    fn pick_next(&self) -> usize {
+        let tick = timer::ticks();
+        (1..=MAX_THREADS)
+            .map(|offset| (self.current + offset) % MAX_THREADS)
+            .filter(|&slot| slot != IDLE_SLOT)
+            .find(|&slot| matches!(&self.threads[slot], Some(t) if t.is_runnable(tick)))
+            .unwrap_or(IDLE_SLOT)
+    }
+
+    /// Wake Everything Joining The Given Thread.
+    fn wake_joiners(&mut self, id: ThreadId) {
+        for thread in self.threads.iter_mut().flatten() {
+            if thread.state == ThreadState::Joining(id) {
+                thread.state = ThreadState::Ready;
+            }
+        }
+    }
+}
+
+static SCHEDULER: Mutex<Scheduler> = Mutex::new(Scheduler::new());
+static STARTED: AtomicBool = AtomicBool::new(false);
+static NEED_RESCHED: AtomicBool = AtomicBool::new(false);
+static SLICE_LEFT: AtomicU32 = AtomicU32::new(TIME_SLICE);
+/// Set While The Idle Thread Runs, So Every Tick Checks For Woken Sleepers.
+static IDLE: AtomicBool = AtomicBool::new(false);
+
+extern "C" {
+    /// Save The Callee Saved Registers & RFLAGS On The Current Stack, Store Its
+    /// Pointer In `old`, Then Restore The Same From The Stack At `new`.
+    fn almond_switch_context(old: *mut u64, new: u64);
+    /// The First Return Address Of Every New Thread, Calls [almond_thread_entry] With R12.
+    fn almond_thread_trampoline();
+}
+
+global_asm!(
+    r#"
+.global almond_switch_context
+almond_switch_context:
+    pushfq
+    push rbp
+    push rbx
+    push r12
+    push r13
+    push r14
+    push r15
+    mov [rdi], rsp
+    mov rsp, rsi
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    pop rbp
+    popfq
+    ret
+
+.global almond_thread_trampoline
+almond_thread_trampoline:
+    mov rdi, r12
+    call almond_thread_entry
+    ud2
+"#
+);
+
+type ThreadMain = Box<dyn FnOnce() + Send + 'static>;
+
+#[no_mangle]
+extern "C" fn almond_thread_entry(main: *mut ThreadMain) -> ! {
+    // Threads Start From A Switch, Which Always Happens With Interrupts Off.
+    interrupts::enable();
+    let main = unsafe { Box::from_raw(main) };
+    main();
+    exit();
+}
+
+/// Register The Boot Context As The `kernel` Thread & Start The Idle Thread.
+/// Must Run After Memory Is Initialized.
+pub fn initialize() -> KResult<()> {
+    no_interrupt!({
+        let mut scheduler = SCHEDULER.lock();
+        scheduler.add("kernel", None, 0).map(|_| scheduler.current().state = ThreadState::Running)
+    })?;
+    let idle = spawn("idle", || loop {
+        crate::spin();
+    })?;
+    // Never Joined.
+    drop(idle);
+    STARTED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// A Handle To Wait For A Thread, Dropping It Lets The Thread Be Freed On Exit.
+#[derive(Debug)]
+pub struct JoinHandle {
+    id: ThreadId,
+}
+
+impl JoinHandle {
+    /// The Thread's Identifier.
+    pub fn id(&self) -> ThreadId {
+        self.id
+    }
+
+    /// Block Until The Thread Exits, Then Free It.
+    pub fn join(self) -> KResult<()> {
+        let id = self.id;
+        core::mem::forget(self);
+        loop {
+            let finished = no_interrupt!({
+                let mut scheduler = SCHEDULER.lock();
+                match scheduler.slot_of(id) {
+                    None => Err("No Such Thread"),
+                    Some(slot) if scheduler.threads[slot].as_ref().unwrap().state == ThreadState::Exited => {
+                        Ok(scheduler.threads[slot].take())
+                    }
+                    Some(_) => {
+                        scheduler.current().state = ThreadState::Joining(id);
+                        Ok(None)
+                    }
+                }
+            })?;
+            match finished {
+                // Dropped Outside The Lock, Freeing The Stack Takes The Mapper.
+                Some(thread) => {
+                    drop(thread);
+                    return Ok(());
+                }
+                None => switch(),
+            }
+        }
+    }
+}
+
+impl Drop for JoinHandle {
+    fn drop(&mut self) {
+        no_interrupt!({
+            let mut scheduler = SCHEDULER.lock();
+            if let Some(slot) = scheduler.slot_of(self.id) {
+                scheduler.threads[slot].as_mut().unwrap().detached = true;
+            }
+        });
+    }
+}
+
+/// Free Detached Threads That Have Exited.
+fn reap() {
+    let mut dead = Vec::new();
+    no_interrupt!({
+        let mut scheduler = SCHEDULER.lock();
+        for slot in scheduler.threads.iter_mut() {
+            if matches!(slot, Some(t) if t.detached && t.state == ThreadState::Exited) {
+                dead.push(slot.take());
+            }
+        }
+    });
+}
+
+/// Start A Thread Running `main` On A Fresh Stack.
+pub fn spawn(name: &'static str, main: impl FnOnce() + Send + 'static) -> KResult<JoinHandle> {
+    reap();
+    let stack = KernelStack::new(DEFAULT_STACK_PAGES, name)?;
+    let main: *mut ThreadMain = Box::into_raw(Box::new(Box::new(main) as ThreadMain));
+
+    // The Frame Popped By `almond_switch_context`, Lowest Address First.
+    let frame: [u64; 8] = [
+        0,                                      // r15
+        0,                                      // r14
+        0,                                      // r13
+        main as u64,                            // r12
+        0,                                      // rbx
+        0,                                      // rbp
+        0x2,                                    // rflags, Interrupts Off
+        almond_thread_trampoline as usize as u64, // Return Address
+    ];
+    let rsp = stack.top().as_u64() - core::mem::size_of_val(&frame) as u64;
+    unsafe {
+        (rsp as *mut [u64; 8]).write(frame);
+    }
+
+    let id = no_interrupt!({ SCHEDULER.lock().add(name, Some(stack), rsp) });
+    match id {
+        Ok(id) => Ok(JoinHandle { id }),
+        Err(e) => {
+            drop(unsafe { Box::from_raw(main) });
+            Err(e)
+        }
+    }
+}
+
+/// Switch To The Next Runnable Thread, The Caller Must Already Have Set Its Own State.
+fn switch() {
+    no_interrupt!({
+        let (old, new) = {
+            let mut scheduler = SCHEDULER.lock();
+            if scheduler.current().state == ThreadState::Running {
+                scheduler.current().state = ThreadState::Ready;
+            }
+            let next = scheduler.pick_next();
+            SLICE_LEFT.store(TIME_SLICE, Ordering::Relaxed);
+            IDLE.store(next == IDLE_SLOT, Ordering::Relaxed);
+            if next == scheduler.current {
+                scheduler.current().state = ThreadState::Running;
+                return;
+            }
+            let thread = scheduler.current();
+            thread.kernel_rsp = user::kernel_stack();
+            thread.cr3 = Cr3::read().0;
+            thread.fs_base = FsBase::read();
+            let old = &mut thread.rsp as *mut u64;
+            scheduler.current = next;
+            let thread = scheduler.current();
+            thread.state = ThreadState::Running;
+            thread.switches += 1;
+            user::almond_set_kernel_stack(thread.kernel_rsp);
+            let (cr3, flags) = Cr3::read();
+            if cr3 != thread.cr3 {
+                unsafe { Cr3::write(thread.cr3, flags) };
+            }
+            FsBase::write(thread.fs_base);
+            (old, thread.rsp)
+        };
+        unsafe { almond_switch_context(old, new) }
+    })
+}
+
+/// Give Up The Rest Of The Time Slice.
+pub fn yield_now() {
+    if STARTED.load(Ordering::Relaxed) {
+        switch();
+    }
+}
+
+/// Put The Current Thread To Sleep For At Least `duration`.
+/// Before Threads Start This Falls Back To [timer::sleep].
+pub fn sleep(duration: Duration) {
+    if !STARTED.load(Ordering::Relaxed) {
+        return timer::sleep(duration);
+    }
+    no_interrupt!({ SCHEDULER.lock().current().state = ThreadState::Sleeping(deadline(duration)) });
+    switch();
+}
+
+/// Block The Current Thread Until It Is [wake]d Or `timeout` Passes.
+/// Before Threads Start This Falls Back To [timer::sleep].
+pub fn park(timeout: Duration) {
+    if !STARTED.load(Ordering::Relaxed) {
+        return timer::sleep(timeout);
+    }
+    no_interrupt!({ SCHEDULER.lock().current().state = ThreadState::Blocked(deadline(timeout)) });
+    switch();
+}
+
+/// The Tick At Which `duration` From Now Has Passed, At Least The Next One.
+fn deadline(duration: Duration) -> u64 {
+    let ticks = (duration.as_nanos() as u64 + TICK_NANOS - 1) / TICK_NANOS;
+    timer::ticks() + ticks.max(1)
+}
+
+/// Wake A [park]ed Thread Early, Does Nothing If It Is Not Parked.
+/// Threads In [sleep] Always Sleep For The Full Duration.
+/// Safe To Call From Interrupt Handlers.
+pub fn wake(id: ThreadId) {
+    no_interrupt!({
+        let mut scheduler = SCHEDULER.lock();
+        if let Some(slot) = scheduler.slot_of(id) {
+            let thread = scheduler.threads[slot].as_mut().unwrap();
+            if let ThreadState::Blocked(_) = thread.state {
+                thread.state = ThreadState::Ready;
+                if IDLE.load(Ordering::Relaxed) {
+                    NEED_RESCHED.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    })
+}
+
+/// End The Current Thread. The `kernel` Thread Can't Exit, So It Halts Instead.
+pub fn exit() -> ! {
+    let is_kernel = no_interrupt!({
+        let mut scheduler = SCHEDULER.lock();
+        if scheduler.current == KERNEL_SLOT {
+            true
+        } else {
+            let id = scheduler.current().id;
+            scheduler.current().state = ThreadState::Exited;
+            scheduler.wake_joiners(id);
+            false
+        }
+    });
+    if is_kernel {
+        crate::halt();
+    }
+    switch();
+    unreachable!("Exited Thread Was Rescheduled");
+}
+
+/// Returns True If The Current Thread Can Be Killed, Every Thread But `kernel` & `idle`.
+pub fn can_exit() -> bool {
+    STARTED.load(Ordering::Relaxed)
+        && SCHEDULER.try_lock().map(|s| s.current > IDLE_SLOT).unwrap_or(false)
+}
+
+/// The Current Thread's Identifier & Name, None Before Threads Start.
+pub fn current() -> Option<(ThreadId, &'static str)> {
+    if !STARTED.load(Ordering::Relaxed) {
+        return None;
+    }
+    no_interrupt!({
+        let mut scheduler = SCHEDULER.lock();
+        let thread = scheduler.current();
+        Some((thread.id, thread.name))
+    })
+}
+
+/// A Snapshot Of Every Thread.
+pub fn list() -> Vec<ThreadInfo> {
+    no_interrupt!({ SCHEDULER.lock().threads.iter().flatten().map(Thread::info).collect() })
+}
+
+/// Count Down The Time Slice, Called From The Timer Tick.
+/// Never Locks, The Switch Itself Happens In [preempt].
+pub fn on_tick() {
+    let left = SLICE_LEFT.load(Ordering::Relaxed);
+    if left <= 1 || IDLE.load(Ordering::Relaxed) {
+        NEED_RESCHED.store(true, Ordering::Relaxed);
+    } else {
+        SLICE_LEFT.store(left - 1, Ordering::Relaxed);
+    }
+}
+
+/// Switch Threads If The Time Slice Ran Out.
+/// Called By The IRQ Handlers After The EOI, So The Next Tick Can Still Arrive.
+pub fn preempt() {
+    if STARTED.load(Ordering::Relaxed) && NEED_RESCHED.swap(false, Ordering::Relaxed) {
+        switch();
+    }
+}
@@ -1,16 +1,50 @@
 //! Provides Functions For Communicating With The Programmable Interrupt Timer
-//! & General Sleep Functions
+//! & General Sleep Functions.
+//! The PIT Drives The Tick, The Calibrated TSC Provides Nanosecond [Instant]s.
 
-use super::interrupt::idt::set_irq_handler;
-use crate::{no_interrupt, KResult};
-use x86_64::instructions::{hlt, port::Port};
+pub mod callback;
+pub mod hpet;
+pub mod tsc;
+
+use core::ops::{Add, Sub};
+pub use core::time::Duration;
+pub use callback::{after, cancel, every, TimerHandle};
+
+use super::interrupt::{idt::set_irq_handler, IrqResult};
+use crate::{no_interrupt, slog, KResult};
+use x86_64::instructions::port::Port;
 
 static mut TICK_COUNT: u64 = 0;
-/// The Amount Of Ticks That Occur In One Second. 10KHz
-pub const TICKS_PER_SECOND: f64 = 1000.6789606035205f64;
+static mut TICK_SOURCE: TickSource = TickSource::Pit;
+static mut CLOCK_SOURCE: ClockSource = ClockSource::Pit;
+
+/// The Device Driving The Timer Tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickSource {
+    /// PIT Channel 0 Through IRQ 0.
+    Pit,
+    /// The Local APIC Timer, Calibrated To The PIT's Rate.
+    LapicTimer,
+}
+
+/// The Reference The TSC Was Last Calibrated Against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    /// PIT Channel 2.
+    Pit,
+    /// The HPET Main Counter.
+    Hpet,
+}
+
+/// The PIT's Input Clock In Hz.
+pub const PIT_FREQUENCY: u64 = 1_193_182;
+/// The Channel 0 Divisor, Giving A Tick Of Roughly 1ms.
+pub const PIT_DIVISOR: u64 = 1193;
+/// The Amount Of Ticks That Occur In One Second, ~1KHz.
+pub const TICKS_PER_SECOND: f64 = PIT_FREQUENCY as f64 / PIT_DIVISOR as f64;
+/// The Length Of One Tick In Nanoseconds.
+pub const TICK_NANOS: u64 = PIT_DIVISOR * 1_000_000_000 / PIT_FREQUENCY;
 
-/// The Main PIT Freqency, Runs At 1.93810Mhz.
-const MASTER_RATE: f64 = 1193810.0;
 const COMMAND_PORT: u16 = 0x43;
 const CH0_DATA: u16 = 0x40;
 const CH2_DATA: u16 = 0x42;
@@ -20,7 +54,7 @@ const SET_CH2_FREQ_CMD: u8 = 0xb6;
 /// Sets The Given PIT Channel's Frequency, Matching As Close As Possible.
 pub fn set_frequency_ch0(freq: f64) {
     no_interrupt!({
-        let divisor: usize = (MASTER_RATE / freq) as usize;
+        let divisor: usize = (PIT_FREQUENCY as f64 / freq) as usize;
         let mut command_port: Port<u8> = Port::new(COMMAND_PORT);
         let mut data_port: Port<u8> = Port::new(CH0_DATA);
 
@@ -35,7 +69,7 @@ pub fn set_frequency_ch0(freq: f64) {
 /// Sets The Given PIT Channel's Frequency, Matching As Close As Possible.
 pub fn set_frequency_ch2(freq: f64) {
     no_interrupt!({
-        let divisor: usize = (MASTER_RATE / freq) as usize;
+        let divisor: usize = (PIT_FREQUENCY as f64 / freq) as usize;
         let mut command_port: Port<u8> = Port::new(COMMAND_PORT);
         let mut data_port: Port<u8> = Port::new(CH2_DATA);
 
@@ -47,21 +81,54 @@ pub fn set_frequency_ch2(freq: f64) {
     });
 }
 
-/// Setup The PIT, Set the frequency To 10KHz,
-/// Set The IRQ0 Handler.
+/// Calibrate The TSC, Set The PIT Frequency To ~1KHz
+/// & Set The IRQ0 Handler.
 pub fn initialize() -> KResult<()> {
+    tsc::calibrate_pit();
     no_interrupt!({
         set_frequency_ch0(TICKS_PER_SECOND);
-        set_irq_handler(0, on_timer_tick);
-    });
+        set_irq_handler(0, on_timer_tick)
+    })
+}
+
+/// Recalibrate The TSC Against The HPET If There Is One.
+/// Must Run After Memory Is Initialized, As The HPET Is Found Through ACPI.
+pub fn initialize_clock() -> KResult<()> {
+    if hpet::initialize().is_ok() && tsc::calibrate_hpet().is_some() {
+        unsafe {
+            CLOCK_SOURCE = ClockSource::Hpet;
+        }
+    }
+    slog!("TSC: {} Hz, Calibrated Against {:?}\n", tsc::frequency(), clock_source());
     Ok(())
 }
 
 #[doc(hidden)]
-pub fn on_timer_tick(_: u8) {
+pub fn on_timer_tick(_: u8) -> IrqResult {
     unsafe {
         TICK_COUNT += 1;
     }
+    callback::on_tick();
+    super::thread::on_tick();
+    IrqResult::Handled
+}
+
+/// Record Which Device Drives The Tick, Called By The Interrupt Controller.
+/// Both Sources Deliver On IRQ 0's Vector, So [on_timer_tick] Serves Either.
+pub fn set_tick_source(source: TickSource) {
+    unsafe {
+        TICK_SOURCE = source;
+    }
+}
+
+/// Returns The Device Driving The Tick.
+pub fn tick_source() -> TickSource {
+    unsafe { TICK_SOURCE }
+}
+
+/// Returns The Reference The TSC Was Calibrated Against.
+pub fn clock_source() -> ClockSource {
+    unsafe { CLOCK_SOURCE }
 }
 
 /// Returns The Number Of Ticks Since Boot.
@@ -69,19 +136,76 @@ pub fn ticks() -> u64 {
     unsafe { TICK_COUNT }
 }
 
-/// Sleeps For An Amount Of Ticks. One Tick = 1ms
-pub fn sleep_ticks(time: u64) {
-    let start = ticks();
+/// A Point On The Monotonic Clock, With Nanosecond Resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(u64);
+
+impl Instant {
+    /// The Current Time.
+    pub fn now() -> Self {
+        Self(tsc::nanos())
+    }
+
+    /// Nanoseconds Since The Clock Started.
+    pub fn as_nanos(&self) -> u64 {
+        self.0
+    }
+
+    /// The Time Between `earlier` & This Instant, Zero If `earlier` Is Later.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        Duration::from_nanos(self.0.saturating_sub(earlier.0))
+    }
+
+    /// The Time Since This Instant.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Instant {
+        Instant(self.0 + rhs.as_nanos() as u64)
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// Sleeps For At Least The Given Duration.
+/// Waits Are Shorter Than A Tick Spin, Longer Ones Halt Between Ticks.
+pub fn sleep(duration: Duration) {
+    let deadline = Instant::now() + duration;
     loop {
-        let now = ticks();
-        if (now - start) >= time {
+        let now = Instant::now();
+        if now >= deadline {
             break;
         }
-        hlt();
+        if deadline.duration_since(now).as_nanos() as u64 > TICK_NANOS {
+            crate::spin();
+        } else {
+            core::hint::spin_loop();
+        }
     }
 }
 
+/// Sleeps For An Amount Of Ticks. One Tick = ~1ms
+pub fn sleep_ticks(time: u64) {
+    sleep(Duration::from_nanos(time * TICK_NANOS));
+}
+
+/// Time Since Boot.
+pub fn uptime_duration() -> Duration {
+    Duration::from_nanos(Instant::now().as_nanos())
+}
+
 /// Uptime In Seconds
 pub fn uptime() -> f64 {
-    ticks() as f64 / TICKS_PER_SECOND
+    uptime_duration().as_secs_f64()
 }
@@ -0,0 +1,204 @@
+//! One-Shot & Periodic Kernel Timers.
+//! Timers Live In A Fixed Size Min-Heap Ordered By Deadline, IRQ0 Only Compares
+//! The Earliest Deadline Against The Clock & Queues [run_expired] On The Work Queue,
+//! So Callbacks Always Run With Interrupts Enabled.
+
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use spin::Mutex;
+
+use super::{Duration, Instant};
+use crate::sys::workqueue;
+use crate::{no_interrupt, KResult};
+
+/// The Maximum Number Of Armed Timers.
+pub const MAX_TIMERS: usize = 64;
+
+/// A Timer Callback, Called With The Argument It Was Armed With.
+pub type TimerFn = fn(usize);
+
+/// Identifies An Armed Timer, See [cancel].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerHandle(u64);
+
+impl TimerHandle {
+    /// Cancel The Timer, Returns False If It Already Fired Or Was Cancelled.
+    pub fn cancel(self) -> bool {
+        cancel(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Timer {
+    id: u64,
+    deadline: u64,
+    /// 0 For One-Shot Timers.
+    period: u64,
+    func: TimerFn,
+    arg: usize,
+}
+
+struct TimerHeap {
+    timers: [Option<Timer>; MAX_TIMERS],
+    len: usize,
+}
+
+impl TimerHeap {
+    const fn new() -> Self {
+        Self { timers: [None; MAX_TIMERS], len: 0 }
+    }
+
+    fn deadline(&self, index: usize) -> u64 {
+        self.timers[index].map_or(u64::MAX, |t| t.deadline)
+    }
+
+    fn sift_up(&mut self, mut index: usize) {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if self.deadline(parent) <= self.deadline(index) {
+                break;
+            }
+            self.timers.swap(parent, index);
+            index = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut index: usize) {
+        loop {
+            let mut smallest = index;
+            for child in [index * 2 + 1, index * 2 + 2] {
+                if child < self.len && self.deadline(child) < self.deadline(smallest) {
+                    smallest = child;
+                }
+            }
+            if smallest == index {
+                break;
+            }
+            self.timers.swap(smallest, index);
+            index = smallest;
+        }
+    }
+
+    fn push(&mut self, timer: Timer) -> KResult<()> {
+        if self.len == MAX_TIMERS {
+            return Err("Timer Heap Is Full");
+        }
+        self.timers[self.len] = Some(timer);
+        self.len += 1;
+        self.sift_up(self.len - 1);
+        Ok(())
+    }
+
+    fn remove(&mut self, index: usize) -> Option<Timer> {
+        if index >= self.len {
+            return None;
+        }
+        self.len -= 1;
+        self.timers.swap(index, self.len);
+        let timer = self.timers[self.len].take();
+        if index < self.len {
+            self.sift_down(index);
+            self.sift_up(index);
+        }
+        timer
+    }
+
+    fn peek(&self) -> Option<&Timer> {
+        self.timers[0].as_ref()
+    }
+}
+
+static TIMERS: Mutex<TimerHeap> = Mutex::new(TimerHeap::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// The Earliest Deadline, Read By IRQ0 Without Taking The Lock.
+static NEXT_DEADLINE: AtomicU64 = AtomicU64::new(u64::MAX);
+/// Set While [run_expired] Is Waiting On The Work Queue.
+static EXPIRY_QUEUED: AtomicBool = AtomicBool::new(false);
+
+fn update_next_deadline(heap: &TimerHeap) {
+    NEXT_DEADLINE.store(heap.deadline(0), Ordering::Relaxed);
+}
+
+fn arm(delay: Duration, period: Duration, func: TimerFn, arg: usize) -> KResult<TimerHandle> {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let timer = Timer {
+        id,
+        deadline: (Instant::now() + delay).as_nanos(),
+        period: period.as_nanos() as u64,
+        func,
+        arg,
+    };
+    no_interrupt!({
+        let mut heap = TIMERS.lock();
+        heap.push(timer)?;
+        update_next_deadline(&heap);
+        Ok(TimerHandle(id))
+    })
+}
+
+/// Call `func(arg)` Once, After `delay`.
+pub fn after(delay: Duration, func: TimerFn, arg: usize) -> KResult<TimerHandle> {
+    arm(delay, Duration::ZERO, func, arg)
+}
+
+/// Call `func(arg)` Every `period`, Until Cancelled.
+pub fn every(period: Duration, func: TimerFn, arg: usize) -> KResult<TimerHandle> {
+    if period.is_zero() {
+        return Err("Timer Period Must Be Non-Zero");
+    }
+    arm(period, period, func, arg)
+}
+
+/// Disarm A Timer, Returns False If It Already Fired Or Was Cancelled.
+pub fn cancel(handle: TimerHandle) -> bool {
+    no_interrupt!({
+        let mut heap = TIMERS.lock();
+        let index = (0..heap.len).find(|&i| matches!(heap.timers[i], Some(t) if t.id == handle.0));
+        let removed = index.and_then(|i| heap.remove(i)).is_some();
+        update_next_deadline(&heap);
+        removed
+    })
+}
+
+/// The Number Of Armed Timers.
+pub fn pending() -> usize {
+    no_interrupt!({ TIMERS.lock().len })
+}
+
+/// Called From IRQ0, Queues [run_expired] Once The Earliest Deadline Has Passed.
+pub(super) fn on_tick() {
+    if Instant::now().as_nanos() >= NEXT_DEADLINE.load(Ordering::Relaxed)
+        && !EXPIRY_QUEUED.swap(true, Ordering::Relaxed)
+        && workqueue::schedule(run_expired, 0).is_err()
+    {
+        EXPIRY_QUEUED.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Run Every Expired Timer & Re-Arm The Periodic Ones.
+fn run_expired(_: usize) {
+    EXPIRY_QUEUED.store(false, Ordering::Relaxed);
+    loop {
+        let now = Instant::now().as_nanos();
+        let expired = no_interrupt!({
+            let mut heap = TIMERS.lock();
+            let timer = match heap.peek() {
+                Some(timer) if timer.deadline <= now => heap.remove(0),
+                _ => None,
+            };
+            if let Some(mut timer) = timer {
+                if timer.period != 0 {
+                    // Skip Missed Periods Rather Than Firing A Burst.
+                    timer.deadline += timer.period * ((now - timer.deadline) / timer.period + 1);
+                    let _ = heap.push(timer);
+                }
+            }
+            update_next_deadline(&heap);
+            timer
+        });
+        match expired {
+            Some(timer) => (timer.func)(timer.arg),
+            None => break,
+        }
+    }
+}
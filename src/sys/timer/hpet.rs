@@ -0,0 +1,61 @@
+//! High Precision Event Timer, Used Only As A Free-Running Reference Counter.
+//! <https://wiki.osdev.org/HPET>
+
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::sys::{acpi, mem::mapper::physical_memory_offset};
+use crate::KResult;
+
+const REG_CAPABILITIES: u64 = 0x00;
+const REG_CONFIG: u64 = 0x10;
+const REG_COUNTER: u64 = 0xF0;
+const CONFIG_ENABLE: u64 = 1;
+/// The Specification Caps The Period At 100ns.
+const MAX_PERIOD: u64 = 100_000_000;
+
+/// The Virtual Address Of The HPET's Registers, 0 If Not Present.
+static BASE: AtomicU64 = AtomicU64::new(0);
+/// The Counter Period In Femtoseconds.
+static PERIOD: AtomicU64 = AtomicU64::new(0);
+
+fn read(reg: u64) -> u64 {
+    unsafe { read_volatile((BASE.load(Ordering::Relaxed) + reg) as *const u64) }
+}
+
+fn write(reg: u64, value: u64) {
+    unsafe { write_volatile((BASE.load(Ordering::Relaxed) + reg) as *mut u64, value) }
+}
+
+/// Find The HPET Through ACPI & Start Its Main Counter.
+pub fn initialize() -> KResult<()> {
+    let address = acpi::hpet_address().ok_or("ACPI HPET Not Found")?;
+    BASE.store(address + physical_memory_offset(), Ordering::Relaxed);
+    let period = read(REG_CAPABILITIES) >> 32;
+    if period == 0 || period > MAX_PERIOD {
+        BASE.store(0, Ordering::Relaxed);
+        return Err("HPET Reports An Invalid Period");
+    }
+    PERIOD.store(period, Ordering::Relaxed);
+    write(REG_CONFIG, read(REG_CONFIG) | CONFIG_ENABLE);
+    Ok(())
+}
+
+/// Returns True If The HPET Was Found & Started.
+pub fn is_present() -> bool {
+    BASE.load(Ordering::Relaxed) != 0
+}
+
+/// The Counter Period In Femtoseconds, None Without An HPET.
+pub fn period_fs() -> Option<u64> {
+    if is_present() {
+        Some(PERIOD.load(Ordering::Relaxed))
+    } else {
+        None
+    }
+}
+
+/// Read The Main Counter.
+pub fn counter() -> u64 {
+    read(REG_COUNTER)
+}
@@ -0,0 +1,112 @@
+//! Time Stamp Counter Calibration.
+//! The TSC Is Calibrated Against PIT Channel 2 At Boot & Again Against The HPET When
+//! One Is Found, Every [Instant](super::Instant) Is Then A Single `rdtsc`.
+//! Recalibrating Folds The Time Measured So Far Into An Offset, So Instants
+//! Taken Before & After Stay On The Same Scale.
+
+use core::arch::x86_64::_rdtsc;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use x86_64::instructions::port::Port;
+
+use super::{hpet, COMMAND_PORT, CH2_DATA, PIT_FREQUENCY};
+use crate::no_interrupt;
+
+/// PIT Channel 2's Gate & Output Live In The Keyboard Controller's Port B.
+const PORT_B: u16 = 0x61;
+const GATE_CH2: u8 = 0x01;
+const SPEAKER_ENABLE: u8 = 0x02;
+const OUT_CH2: u8 = 0x20;
+/// Channel 2, Lobyte/Hibyte, Mode 0 (Interrupt On Terminal Count).
+const CH2_ONE_SHOT_CMD: u8 = 0xB0;
+/// How Long Each Calibration Runs For.
+const CALIBRATION_MS: u64 = 10;
+
+static FREQUENCY: AtomicU64 = AtomicU64::new(0);
+static BOOT_TSC: AtomicU64 = AtomicU64::new(0);
+/// Nanoseconds Counted Before [BOOT_TSC] Was Last Moved.
+static OFFSET_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Read The Time Stamp Counter.
+#[inline(always)]
+pub fn read() -> u64 {
+    unsafe { _rdtsc() }
+}
+
+/// The Calibrated TSC Frequency In Hz, 0 Before [calibrate_pit].
+pub fn frequency() -> u64 {
+    FREQUENCY.load(Ordering::Relaxed)
+}
+
+/// The TSC Value When The Clock Started, Or Was Last Recalibrated.
+pub fn boot_tsc() -> u64 {
+    BOOT_TSC.load(Ordering::Relaxed)
+}
+
+/// Nanoseconds Since The Clock Started.
+pub fn nanos() -> u64 {
+    no_interrupt!({
+        OFFSET_NANOS.load(Ordering::Relaxed) + cycles_to_nanos(read().saturating_sub(boot_tsc()))
+    })
+}
+
+/// Convert A Number Of TSC Cycles Into Nanoseconds.
+pub fn cycles_to_nanos(cycles: u64) -> u64 {
+    match frequency() {
+        0 => 0,
+        hz => (cycles as u128 * 1_000_000_000 / hz as u128) as u64,
+    }
+}
+
+/// Count TSC Cycles While PIT Channel 2 Counts Down [CALIBRATION_MS].
+/// Polls The Channel's Output, So It Works With Interrupts Disabled.
+pub fn calibrate_pit() -> u64 {
+    let count = (PIT_FREQUENCY * CALIBRATION_MS / 1000) as u16;
+    let hz = no_interrupt!({
+        let mut port_b: Port<u8> = Port::new(PORT_B);
+        let mut command: Port<u8> = Port::new(COMMAND_PORT);
+        let mut data: Port<u8> = Port::new(CH2_DATA);
+        unsafe {
+            let saved = port_b.read();
+            // Gate Low & Speaker Off While Loading The Count.
+            port_b.write(saved & !(GATE_CH2 | SPEAKER_ENABLE));
+            command.write(CH2_ONE_SHOT_CMD);
+            data.write((count & 0xFF) as u8);
+            data.write((count >> 8) as u8);
+
+            port_b.write((saved & !SPEAKER_ENABLE) | GATE_CH2);
+            let start = read();
+            while port_b.read() & OUT_CH2 == 0 {}
+            let end = read();
+
+            port_b.write(saved);
+            (end - start) * 1000 / CALIBRATION_MS
+        }
+    });
+    FREQUENCY.store(hz, Ordering::Relaxed);
+    BOOT_TSC.compare_exchange(0, read(), Ordering::Relaxed, Ordering::Relaxed).ok();
+    hz
+}
+
+/// Refine The Calibration Against The HPET, Which Has A Known Period.
+/// Returns None If There Is No HPET.
+pub fn calibrate_hpet() -> Option<u64> {
+    let period = hpet::period_fs()?;
+    let hpet_ticks = CALIBRATION_MS * 1_000_000_000_000 / period;
+    let hz = no_interrupt!({
+        let hpet_start = hpet::counter();
+        let start = read();
+        while hpet::counter().wrapping_sub(hpet_start) < hpet_ticks {}
+        let end = read();
+        let elapsed_fs = (hpet::counter().wrapping_sub(hpet_start) as u128) * period as u128;
+        ((end - start) as u128 * 1_000_000_000_000_000 / elapsed_fs) as u64
+    });
+    no_interrupt!({
+        // Count The Time So Far At The Old Rate, Only What Follows Uses The New One.
+        let now = read();
+        OFFSET_NANOS.fetch_add(cycles_to_nanos(now.saturating_sub(boot_tsc())), Ordering::Relaxed);
+        BOOT_TSC.store(now, Ordering::Relaxed);
+        FREQUENCY.store(hz, Ordering::Relaxed);
+    });
+    Some(hz)
+}
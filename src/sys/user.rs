@@ -0,0 +1,330 @@
+//! Ring 3 User Mode.
+//! A [Process] Owns An [AddressSpace] & Its Descriptors, [execute] Runs It
+//! On The Calling Thread Until It Exits Through The [syscall] Interface Or Faults.
+//! Running Programs Are Tracked In The [process](super::process) Table.
+//!
+//! Entering User Mode Saves The Kernel's Callee Saved Registers On The Thread's
+//! Stack, Leaving Through [exit] Restores Them, So [execute] Simply Returns The
+//! Exit Code. Everything Below The Saved Registers Is Used As The Ring 0 Stack
+//! By Interrupts & System Calls From User Mode.
+
+pub mod linux;
+pub mod syscall;
+
+use alloc::{string::String, sync::Arc};
+use core::arch::global_asm;
+
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+use x86_64::registers::model_specific::FsBase;
+use x86_64::structures::paging::{Page, PageTableFlags, Size4KiB};
+use x86_64::VirtAddr;
+
+use super::fd::FdTable;
+use super::interrupt::tss;
+use super::mem::address_space::{self, AddressSpace, USER_END};
+use super::process::{self, Pid, KILLED_EXIT_CODE};
+use crate::KResult;
+
+/// The Top Of The User Stack, The Highest Page Is Left Unmapped.
+pub const USER_STACK_TOP: u64 = USER_END - PAGE_SIZE;
+/// The Default Size Of The User Stack, 64KiB.
+pub const USER_STACK_PAGES: u64 = 16;
+/// Where The Program Break Starts Unless The Loader Moves It, See [Process::set_break].
+pub const DEFAULT_BREAK: u64 = 0x1000_0000_0000;
+/// Where Anonymous Mappings Are Placed.
+pub const MMAP_BASE: u64 = 0x2000_0000_0000;
+/// Programs Killed By A Fault Exit With This Plus The Exception Vector.
+pub const FAULT_EXIT_BASE: i64 = 128;
+const PAGE_SIZE: u64 = Page::<Size4KiB>::SIZE;
+
+/// The System Call ABI A Program Expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Personality {
+    /// The Native Calls, See [syscall].
+    Almond,
+    /// Linux x86-64 Numbers Through `syscall`, i386 Numbers Through `int 0x80`, See [linux].
+    Linux,
+}
+
+/// A User Program's Resources.
+#[derive(Debug)]
+pub struct Process {
+    name: String,
+    space: AddressSpace,
+    personality: Personality,
+    files: FdTable,
+    break_start: u64,
+    brk: u64,
+    mmap_next: u64,
+}
+
+impl Process {
+    /// Create A Process With An Empty Address Space, Reading The Keyboard & Writing To The Terminal.
+    pub fn new() -> KResult<Self> {
+        Ok(Self {
+            name: String::from("user"),
+            space: AddressSpace::new()?,
+            personality: Personality::Almond,
+            files: FdTable::standard(),
+            break_start: DEFAULT_BREAK,
+            brk: DEFAULT_BREAK,
+            mmap_next: MMAP_BASE,
+        })
+    }
+
+    /// The Name Shown In The Process Table.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set The Name Shown In The Process Table, Loaders Use The Program's First Argument.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = String::from(name);
+    }
+
+    /// The Process's Address Space.
+    pub fn space(&self) -> &AddressSpace {
+        &self.space
+    }
+
+    /// The Process's Address Space.
+    pub fn space_mut(&mut self) -> &mut AddressSpace {
+        &mut self.space
+    }
+
+    /// The System Call ABI The Program Uses.
+    pub fn personality(&self) -> Personality {
+        self.personality
+    }
+
+    /// Set The System Call ABI, Loaders Pick It From The Executable Format.
+    pub fn set_personality(&mut self, personality: Personality) {
+        self.personality = personality;
+    }
+
+    /// Start The Program Break At `start`, Loaders Place It After The Image.
+    pub fn set_break(&mut self, start: VirtAddr) {
+        let start = start.align_up(PAGE_SIZE).as_u64();
+        self.break_start = start;
+        self.brk = start;
+    }
+
+    /// Map A Stack Of `pages` Below [USER_STACK_TOP], Returns Its Top.
+    pub fn map_stack(&mut self, pages: u64) -> KResult<VirtAddr> {
+        let bottom = VirtAddr::new(USER_STACK_TOP - pages * PAGE_SIZE);
+        let flags = PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+        self.space.map_range(bottom, pages * PAGE_SIZE, flags)?;
+        Ok(VirtAddr::new(USER_STACK_TOP))
+    }
+
+    /// Move The Program Break, Returns The New Break Or The Old One If It Can't Move.
+    /// Zero Or An Address Below The Start Just Returns The Current Break.
+    pub fn brk(&mut self, addr: u64) -> u64 {
+        if addr < self.break_start || addr >= MMAP_BASE {
+            return self.brk;
+        }
+        let old_end = VirtAddr::new(self.brk).align_up(PAGE_SIZE).as_u64();
+        let new_end = VirtAddr::new(addr).align_up(PAGE_SIZE).as_u64();
+        if new_end > old_end {
+            let flags = PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+            if self.space.map_range(VirtAddr::new(old_end), new_end - old_end, flags).is_err() {
+                return self.brk;
+            }
+        } else {
+            for page in (new_end..old_end).step_by(PAGE_SIZE as usize) {
+                let _ = self.space.unmap(Page::containing_address(VirtAddr::new(page)));
+            }
+        }
+        self.brk = addr;
+        addr
+    }
+
+    /// Map `len` Bytes Of Zeroed Memory, Returns Its Address.
+    pub fn mmap(&mut self, len: u64, flags: PageTableFlags) -> KResult<u64> {
+        if len == 0 {
+            return Err("Empty Mapping");
+        }
+        let len = VirtAddr::new(len).align_up(PAGE_SIZE).as_u64();
+        let start = self.mmap_next;
+        // One Unmapped Page Between Mappings Catches Overruns.
+        let next = start + len + PAGE_SIZE;
+        if next > USER_STACK_TOP - USER_STACK_PAGES * PAGE_SIZE {
+            return Err("Out Of User Address Space");
+        }
+        self.space.map_range(VirtAddr::new(start), len, flags)?;
+        self.mmap_next = next;
+        Ok(start)
+    }
+
+    /// The Process's Descriptors.
+    pub fn files(&self) -> &FdTable {
+        &self.files
+    }
+
+    /// The Process's Descriptors.
+    pub fn files_mut(&mut self) -> &mut FdTable {
+        &mut self.files
+    }
+
+    /// Replace The Process's Descriptors, Children Get A Copy Of Their Parent's.
+    pub fn set_files(&mut self, files: FdTable) {
+        self.files = files;
+    }
+}
+
+extern "C" {
+    /// Save The Callee Saved Registers, Make The Stack Below Them The Ring 0
+    /// Stack & `iretq` To `entry` On `stack`. "Returns" The Code Passed To [almond_exit_user].
+    fn almond_enter_user(entry: u64, stack: u64) -> i64;
+    /// Unwind To The Matching [almond_enter_user], Which Returns `code`.
+    fn almond_exit_user(kernel_rsp: u64, code: i64) -> !;
+}
+
+// The Selectors Pushed Are `user_data` (0x18) & `user_code` (0x20) With RPL 3,
+// See [gdt::Selectors](super::interrupt::gdt::Selectors).
+global_asm!(
+    r#"
+.global almond_enter_user
+almond_enter_user:
+    push rbp
+    push rbx
+    push r12
+    push r13
+    push r14
+    push r15
+    sub rsp, 8
+    mov r12, rdi
+    mov r13, rsi
+    mov rdi, rsp
+    call almond_set_kernel_stack
+    push 0x1b
+    push r13
+    push 0x202
+    push 0x23
+    push r12
+    xor eax, eax
+    xor ebx, ebx
+    xor ecx, ecx
+    xor edx, edx
+    xor esi, esi
+    xor edi, edi
+    xor ebp, ebp
+    xor r8d, r8d
+    xor r9d, r9d
+    xor r10d, r10d
+    xor r11d, r11d
+    xor r12d, r12d
+    xor r13d, r13d
+    xor r14d, r14d
+    xor r15d, r15d
+    iretq
+
+.global almond_exit_user
+almond_exit_user:
+    mov rsp, rdi
+    mov rax, rsi
+    add rsp, 8
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    pop rbp
+    ret
+"#
+);
+
+/// The Ring 0 Stack Of The Current Thread, Read By The `syscall` Entry.
+#[no_mangle]
+static mut ALMOND_KERNEL_RSP: u64 = 0;
+
+/// Returns The Current Thread's Ring 0 Stack.
+pub fn kernel_stack() -> u64 {
+    unsafe { ALMOND_KERNEL_RSP }
+}
+
+/// Set The Ring 0 Stack Used By Both Interrupts & `syscall` From User Mode.
+/// The Scheduler Swaps It On Every Switch.
+#[no_mangle]
+pub extern "C" fn almond_set_kernel_stack(rsp: u64) {
+    unsafe { ALMOND_KERNEL_RSP = rsp };
+    tss::set_privilege_stack(VirtAddr::new(rsp));
+}
+
+/// Enable The `syscall` Instruction.
+pub fn initialize() -> KResult<()> {
+    address_space::kernel_l4();
+    almond_set_kernel_stack(tss::privilege_stack().as_u64());
+    syscall::initialize()
+}
+
+/// Run `process` In Ring 3 From `entry` With `stack`, Returns Its Exit Code.
+/// Blocks The Calling Thread, Other Threads Keep Running. The Program Is A Child
+/// Of The Current Process, Starts With A Copy Of Its Descriptors & Is Removed
+/// From The Table Once It Exits.
+pub fn execute(mut process: Process, entry: VirtAddr, stack: VirtAddr) -> KResult<i64> {
+    process.set_files(process::with_files(|files| files.clone()));
+    let pid = process::create(process.name(), process::current());
+    let result = execute_as(pid, process, entry, stack);
+    process::release(pid);
+    result
+}
+
+/// Run `process` As `pid` On The Calling Thread, See [execute].
+/// Once It Exits `pid` Is A Zombie Holding The Exit Code, Nothing Changes If It Can't Start.
+pub fn execute_as(pid: Pid, process: Process, entry: VirtAddr, stack: VirtAddr) -> KResult<i64> {
+    let stack_ok = stack.as_u64().checked_sub(8).map_or(false, |low| address_space::is_user_range(low, 8));
+    if !address_space::is_user_range(entry.as_u64(), 1) || !stack_ok {
+        return Err("Entry Or Stack Outside The User Region");
+    }
+    let process = Arc::new(Mutex::new(process));
+    process::attach(pid, process.clone())?;
+
+    interrupts::disable();
+    process.lock().space().activate();
+    let code = unsafe {
+        tss::set_usermode_segments();
+        almond_enter_user(entry.as_u64(), stack.as_u64())
+    };
+    // Back In Ring 0 With Interrupts Off, From [exit] Or A Fault.
+    address_space::activate_kernel();
+    unsafe { tss::set_kernel_segments() };
+    // The Thread Pointer Set Through `arch_prctl` Belongs To The Program.
+    FsBase::write(VirtAddr::zero());
+    interrupts::enable();
+
+    // The Process Is Freed Here, After Its Space Was Deactivated. Its Descriptors
+    // Are Closed First, So Whoever Collects The Zombie Sees Them Closed.
+    process.lock().files_mut().close_all();
+    drop(process::detach(pid, code));
+    Ok(code)
+}
+
+/// Returns True If The Current Thread Is Running A User Program.
+/// Never Blocks, So It Is Safe To Call From Fault Handlers.
+pub fn is_running() -> bool {
+    process::try_current().is_some()
+}
+
+/// Run `f` On The Current Thread's Process, None If It Isn't Running One.
+pub fn with_process<T>(f: impl FnOnce(&mut Process) -> T) -> Option<T> {
+    let (_, process) = process::current_process()?;
+    let mut process = process.lock();
+    Some(f(&mut process))
+}
+
+/// End The Current Program If It Was [killed](process::kill).
+/// Called On The Way Back To User Mode From System Calls & Interrupts, Never Blocks.
+pub fn check_killed() {
+    if let Some((_, true)) = process::try_current() {
+        exit(KILLED_EXIT_CODE);
+    }
+}
+
+/// End The Current User Program, [execute] Returns `code`.
+/// Only Valid From A System Call Or Fault Raised In User Mode, See [is_running].
+pub fn exit(code: i64) -> ! {
+    interrupts::disable();
+    unsafe { almond_exit_user(kernel_stack(), code) }
+}
@@ -0,0 +1,329 @@
+//! The Linux System Call Personality.
+//! Enough Of The Linux ABI For Simple Statically Linked Programs, Including
+//! The Startup Code & Stdio Of musl, Built On The Same Descriptor Calls As The
+//! Native Interface. `syscall` Takes x86-64 Numbers, `int 0x80` Takes i386
+//! Numbers With Arguments In EBX, ECX, EDX, ESI, EDI & EBP, Which Is What
+//! `disk/hello.asm` Uses. Thread Local Storage Is Only Supported For x86-64,
+//! Through `arch_prctl`.
+
+use x86_64::registers::model_specific::FsBase;
+use x86_64::structures::paging::PageTableFlags;
+use x86_64::VirtAddr;
+
+use super::syscall::{
+    self, errno, process, user_cstr, user_slice, user_slice_mut, OPEN_APPEND, OPEN_CREATE, PROT_EXEC, PROT_WRITE,
+};
+use crate::sys::fd::Resource;
+use crate::sys::mem::address_space;
+use crate::sys::process;
+use crate::sys::rtc;
+use crate::sys::storage::mfs::file::SeekFrom;
+use crate::sys::timer::Instant;
+use crate::{build_name, build_version};
+
+/// The Longest Path `open` Accepts.
+const PATH_MAX: u64 = 4096;
+
+/// `open` Flags.
+const O_CREAT: u64 = 0o100;
+const O_APPEND: u64 = 0o2000;
+
+/// `lseek` Origins.
+const SEEK_SET: u64 = 0;
+const SEEK_CUR: u64 = 1;
+const SEEK_END: u64 = 2;
+
+/// `mmap` Flags.
+const MAP_FIXED: u64 = 0x10;
+const MAP_ANONYMOUS: u64 = 0x20;
+
+/// Clocks For `clock_gettime`.
+const CLOCK_REALTIME: u64 = 0;
+const CLOCK_MONOTONIC: u64 = 1;
+
+/// The Length Of Each `struct utsname` Field.
+const UTSNAME_LENGTH: usize = 65;
+
+/// `arch_prctl` Codes.
+const ARCH_SET_FS: u64 = 0x1002;
+const ARCH_GET_FS: u64 = 0x1003;
+
+/// The Most Buffers `writev` Accepts.
+const IOV_MAX: u64 = 1024;
+
+/// `ioctl` Request For The Terminal Size, Which Is The VGA Text Mode's.
+const TIOCGWINSZ: u64 = 0x5413;
+const TERMINAL_ROWS: u16 = 25;
+const TERMINAL_COLUMNS: u16 = 80;
+
+/// The Calls Both Numbering Schemes Map Onto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Call {
+    Read,
+    Write,
+    Open,
+    Close,
+    Lseek,
+    Mmap,
+    Brk,
+    Getpid,
+    Getppid,
+    Dup,
+    Dup2,
+    Pipe,
+    Writev,
+    Ioctl,
+    ArchPrctl,
+    SetTidAddress,
+    Exit,
+    ExitGroup,
+    Uname,
+    ClockGettime,
+}
+
+/// Run An x86-64 Linux System Call.
+pub fn dispatch(number: u64, args: [u64; 6]) -> Result<u64, i64> {
+    let call = match number {
+        0 => Call::Read,
+        1 => Call::Write,
+        2 => Call::Open,
+        3 => Call::Close,
+        8 => Call::Lseek,
+        9 => Call::Mmap,
+        12 => Call::Brk,
+        16 => Call::Ioctl,
+        20 => Call::Writev,
+        22 => Call::Pipe,
+        32 => Call::Dup,
+        33 => Call::Dup2,
+        39 => Call::Getpid,
+        60 => Call::Exit,
+        63 => Call::Uname,
+        110 => Call::Getppid,
+        158 => Call::ArchPrctl,
+        218 => Call::SetTidAddress,
+        228 => Call::ClockGettime,
+        231 => Call::ExitGroup,
+        _ => return Err(errno::ENOSYS),
+    };
+    run(call, args, false)
+}
+
+/// Run An i386 Linux System Call, The Arguments Are Already Zero Extended.
+pub fn dispatch_i386(number: u32, args: [u64; 6]) -> Result<u64, i64> {
+    let call = match number {
+        1 => Call::Exit,
+        3 => Call::Read,
+        4 => Call::Write,
+        5 => Call::Open,
+        6 => Call::Close,
+        19 => Call::Lseek,
+        20 => Call::Getpid,
+        41 => Call::Dup,
+        42 => Call::Pipe,
+        45 => Call::Brk,
+        54 => Call::Ioctl,
+        63 => Call::Dup2,
+        64 => Call::Getppid,
+        122 => Call::Uname,
+        146 => Call::Writev,
+        252 => Call::ExitGroup,
+        258 => Call::SetTidAddress,
+        265 => Call::ClockGettime,
+        _ => return Err(errno::ENOSYS),
+    };
+    run(call, args, true)
+}
+
+/// `compat` Selects The i386 Layout Of Signed & Structure Arguments.
+fn run(call: Call, args: [u64; 6], compat: bool) -> Result<u64, i64> {
+    if !super::is_running() {
+        return Err(errno::EPERM);
+    }
+    match call {
+        // Without Signals Or Threads Both Just End The Program.
+        Call::Exit | Call::ExitGroup => super::exit(args[0] as i32 as i64),
+        Call::Read => syscall::read(args[0] as usize, args[1], args[2]),
+        Call::Write => syscall::write(args[0] as usize, args[1], args[2]),
+        Call::Open => open(args[0], args[1]),
+        Call::Close => syscall::close(args[0] as usize),
+        Call::Lseek => {
+            let offset = if compat { args[1] as i32 as i64 } else { args[1] as i64 };
+            lseek(args[0] as usize, offset, args[2])
+        }
+        Call::Mmap => mmap(args[1], args[2], args[3]),
+        Call::Brk => process(|p| Ok(p.brk(args[0]))),
+        Call::Getpid => Ok(process::current().0),
+        Call::Getppid => syscall::parent(),
+        Call::Dup => syscall::dup(args[0] as usize),
+        Call::Dup2 => syscall::dup2(args[0] as usize, args[1] as usize),
+        Call::Pipe => syscall::pipe(args[0]),
+        Call::Writev => writev(args[0] as usize, args[1], args[2], compat),
+        Call::Ioctl => ioctl(args[0] as usize, args[1], args[2]),
+        // Only Reachable Through `syscall`, i386 Programs Use `set_thread_area`.
+        Call::ArchPrctl => arch_prctl(args[0], args[1]),
+        // Every Process Has One Thread, Whose Id Is The Pid. Nothing Waits On The Address.
+        Call::SetTidAddress => Ok(process::current().0),
+        Call::Uname => uname(args[0]),
+        Call::ClockGettime => clock_gettime(args[0], args[1], compat),
+    }
+}
+
+fn open(path: u64, flags: u64) -> Result<u64, i64> {
+    let path = process(|p| user_cstr(p, path, PATH_MAX))?;
+    let mut open_flags = 0;
+    if flags & O_CREAT != 0 {
+        open_flags |= OPEN_CREATE;
+    }
+    if flags & O_APPEND != 0 {
+        open_flags |= OPEN_APPEND;
+    }
+    syscall::open_file(path, open_flags)
+}
+
+fn lseek(fd: usize, offset: i64, whence: u64) -> Result<u64, i64> {
+    let descriptor = syscall::descriptor(fd)?;
+    let mut resource = descriptor.lock();
+    let file = match &mut *resource {
+        Resource::File(file) => file,
+        _ => return Err(errno::ESPIPE),
+    };
+    let position = match whence {
+        SEEK_SET => offset,
+        SEEK_CUR => {
+            let current = file.seek(SeekFrom::Current(0)).map_err(|_| errno::EINVAL)?;
+            current as i64 + offset
+        }
+        SEEK_END => file.size() as i64 + offset,
+        _ => return Err(errno::EINVAL),
+    };
+    if position < 0 || position > u32::MAX as i64 {
+        return Err(errno::EINVAL);
+    }
+    file.seek(SeekFrom::Start(position as u32)).map(u64::from).map_err(|_| errno::EINVAL)
+}
+
+/// Only Anonymous Mappings, The Address Is A Hint & Ignored.
+fn mmap(len: u64, prot: u64, flags: u64) -> Result<u64, i64> {
+    if flags & MAP_ANONYMOUS == 0 {
+        return Err(errno::ENODEV);
+    }
+    if flags & MAP_FIXED != 0 {
+        return Err(errno::EINVAL);
+    }
+    let mut page_flags = PageTableFlags::empty();
+    if prot & PROT_WRITE != 0 {
+        page_flags |= PageTableFlags::WRITABLE;
+    }
+    if prot & PROT_EXEC == 0 {
+        page_flags |= PageTableFlags::NO_EXECUTE;
+    }
+    process(|p| p.mmap(len, page_flags).map_err(|_| errno::ENOMEM))
+}
+
+/// The i386 `struct iovec` Holds Two 32 Bit Fields, The x86-64 One Two 64 Bit Fields.
+/// Stops At The First Short Write, Like Writing The Buffers One By One Would.
+fn writev(fd: usize, iov: u64, count: u64, compat: bool) -> Result<u64, i64> {
+    match count {
+        0 => return Ok(0),
+        count if count > IOV_MAX => return Err(errno::EINVAL),
+        _ => {}
+    }
+    let entry_size: u64 = if compat { 8 } else { 16 };
+    let vectors = process(|p| user_slice(p, iov, count * entry_size))?;
+    let field = |bytes: &[u8]| {
+        let mut word = [0; 8];
+        word[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(word)
+    };
+    let mut written = 0;
+    for entry in vectors.chunks_exact(entry_size as usize) {
+        let (base, len) = entry.split_at(entry.len() / 2);
+        let len = field(len);
+        match syscall::write(fd, field(base), len) {
+            Ok(count) => {
+                written += count;
+                if count < len {
+                    break;
+                }
+            }
+            Err(error) if written == 0 => return Err(error),
+            Err(_) => break,
+        }
+    }
+    Ok(written)
+}
+
+/// Only `TIOCGWINSZ`, Which Stdio Uses To Tell Terminals Apart.
+fn ioctl(fd: usize, request: u64, arg: u64) -> Result<u64, i64> {
+    let descriptor = syscall::descriptor(fd)?;
+    let is_terminal = matches!(
+        &*descriptor.lock(),
+        Resource::Keyboard(_) | Resource::Terminal | Resource::TerminalError
+    );
+    if !is_terminal || request != TIOCGWINSZ {
+        return Err(errno::ENOTTY);
+    }
+    process(|p| {
+        let data = user_slice_mut(p, arg, 8)?;
+        data[..2].copy_from_slice(&TERMINAL_ROWS.to_le_bytes());
+        data[2..4].copy_from_slice(&TERMINAL_COLUMNS.to_le_bytes());
+        data[4..].fill(0);
+        Ok(0)
+    })
+}
+
+/// The Scheduler Saves The FS Base With Each Thread.
+fn arch_prctl(code: u64, addr: u64) -> Result<u64, i64> {
+    match code {
+        ARCH_SET_FS => {
+            // The Kernel Never Uses FS, So Any User Address Will Do.
+            if addr >= address_space::USER_END {
+                return Err(errno::EPERM);
+            }
+            FsBase::write(VirtAddr::new(addr));
+            Ok(0)
+        }
+        ARCH_GET_FS => process(|p| {
+            let data = user_slice_mut(p, addr, 8)?;
+            data.copy_from_slice(&FsBase::read().as_u64().to_le_bytes());
+            Ok(0)
+        }),
+        _ => Err(errno::EINVAL),
+    }
+}
+
+fn uname(buffer: u64) -> Result<u64, i64> {
+    let fields = ["Almond", build_name!(), build_version!(), build_version!(), "x86_64", "(none)"];
+    process(|p| {
+        let data = user_slice_mut(p, buffer, (fields.len() * UTSNAME_LENGTH) as u64)?;
+        data.fill(0);
+        for (field, value) in data.chunks_mut(UTSNAME_LENGTH).zip(fields.iter()) {
+            let len = value.len().min(UTSNAME_LENGTH - 1);
+            field[..len].copy_from_slice(&value.as_bytes()[..len]);
+        }
+        Ok(0)
+    })
+}
+
+/// The i386 `struct timespec` Holds Two 32 Bit Fields, The x86-64 One Two 64 Bit Fields.
+fn clock_gettime(clock: u64, buffer: u64, compat: bool) -> Result<u64, i64> {
+    let nanos = match clock {
+        CLOCK_REALTIME => rtc::realtime_nanos(),
+        CLOCK_MONOTONIC => Instant::now().as_nanos(),
+        _ => return Err(errno::EINVAL),
+    };
+    let (seconds, nanos) = (nanos / 1_000_000_000, nanos % 1_000_000_000);
+    process(|p| {
+        if compat {
+            let data = user_slice_mut(p, buffer, 8)?;
+            data[..4].copy_from_slice(&(seconds as u32).to_le_bytes());
+            data[4..].copy_from_slice(&(nanos as u32).to_le_bytes());
+        } else {
+            let data = user_slice_mut(p, buffer, 16)?;
+            data[..8].copy_from_slice(&seconds.to_le_bytes());
+            data[8..].copy_from_slice(&nanos.to_le_bytes());
+        }
+        Ok(0)
+    })
+}
@@ -0,0 +1,404 @@
+//! The System Call Interface.
+//! User Code Enters Through `syscall` Or `int 0x80`, Both Save Every Register In
+//! A [SyscallFrame] & Call [dispatch]. The Number Goes In RAX, Arguments In
+//! RDI, RSI, RDX, R10, R8 & R9, The Result Comes Back In RAX.
+//! Failures Return A Negated [errno] Value.
+//! Processes With The Linux [Personality] Are Handed To [linux](super::linux) Instead.
+
+use alloc::sync::Arc;
+use core::arch::global_asm;
+
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+use x86_64::registers::model_specific::{Efer, EferFlags, LStar, SFMask, Star};
+use x86_64::registers::rflags::RFlags;
+use x86_64::structures::paging::PageTableFlags;
+use x86_64::VirtAddr;
+
+use super::{linux, Personality, Process};
+use crate::sys::fd::{self, Descriptor, Pipe, Resource};
+use crate::sys::interrupt::gdt;
+use crate::sys::process;
+use crate::sys::storage::mfs::{self, file::SeekFrom};
+use crate::sys::thread;
+use crate::KResult;
+
+/// The Interrupt Vector Of The Legacy Entry Path.
+pub const SYSCALL_VECTOR: usize = 0x80;
+
+/// How User Code Entered The Kernel, Linux Uses Different Numbers For Each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Entry {
+    Syscall = 0,
+    Interrupt = 1,
+}
+
+/// `exit(code) -> !`
+pub const EXIT: u64 = 0;
+/// `write(fd, buffer, len) -> written`
+pub const WRITE: u64 = 1;
+/// `read(fd, buffer, len) -> read`
+pub const READ: u64 = 2;
+/// `open(path, path_len, flags) -> fd`
+pub const OPEN: u64 = 3;
+/// `close(fd) -> 0`
+pub const CLOSE: u64 = 4;
+/// `brk(addr) -> break`
+pub const BRK: u64 = 5;
+/// `mmap(addr, len, prot) -> addr`, Anonymous Only, The Address Is A Hint.
+pub const MMAP: u64 = 6;
+/// `yield() -> 0`
+pub const YIELD: u64 = 7;
+/// `getpid() -> pid`
+pub const GETPID: u64 = 8;
+/// `getppid() -> pid`
+pub const GETPPID: u64 = 9;
+/// `dup(fd) -> fd`
+pub const DUP: u64 = 10;
+/// `dup2(old, new) -> new`
+pub const DUP2: u64 = 11;
+/// `pipe(fds) -> 0`, Stores The Read & Write Ends As Two 32 Bit Numbers.
+pub const PIPE: u64 = 12;
+
+/// `open` Flags.
+pub use crate::sys::fd::{OPEN_APPEND, OPEN_CREATE};
+
+/// `mmap` Protection Bits.
+pub const PROT_READ: u64 = 1;
+pub const PROT_WRITE: u64 = 2;
+pub const PROT_EXEC: u64 = 4;
+
+/// Error Numbers, Matching Linux.
+pub mod errno {
+    pub const EPERM: i64 = 1;
+    pub const ENOENT: i64 = 2;
+    pub const EIO: i64 = 5;
+    pub const EBADF: i64 = 9;
+    pub const ENOMEM: i64 = 12;
+    pub const EFAULT: i64 = 14;
+    pub const ENODEV: i64 = 19;
+    pub const EINVAL: i64 = 22;
+    pub const EMFILE: i64 = 24;
+    pub const ENOTTY: i64 = 25;
+    pub const ESPIPE: i64 = 29;
+    pub const ENAMETOOLONG: i64 = 36;
+    pub const ENOSYS: i64 = 38;
+}
+
+/// The User's Registers At The Time Of The Call, Lowest Address First.
+/// The Last Five Fields Are An Interrupt Frame, Built By Hand For `syscall`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyscallFrame {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rbp: u64,
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rdx: u64,
+    pub rcx: u64,
+    pub rbx: u64,
+    pub rax: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub rflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+}
+
+extern "C" {
+    fn almond_syscall_entry();
+    fn almond_int80_entry();
+}
+
+/// The User Stack Pointer, Only Held Between `syscall` & The Switch To The Ring 0 Stack.
+#[no_mangle]
+static mut ALMOND_USER_RSP: u64 = 0;
+
+// `syscall` Leaves RSP Untouched, So The Entry Switches To The Thread's Ring 0
+// Stack Itself Before Saving Anything. Interrupts Stay Masked Until Then.
+global_asm!(
+    r#"
+.macro ALMOND_PUSH_REGS
+    push rax
+    push rbx
+    push rcx
+    push rdx
+    push rsi
+    push rdi
+    push rbp
+    push r8
+    push r9
+    push r10
+    push r11
+    push r12
+    push r13
+    push r14
+    push r15
+.endm
+
+.macro ALMOND_POP_REGS
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop r11
+    pop r10
+    pop r9
+    pop r8
+    pop rbp
+    pop rdi
+    pop rsi
+    pop rdx
+    pop rcx
+    pop rbx
+    pop rax
+.endm
+
+.global almond_syscall_entry
+almond_syscall_entry:
+    mov [rip + ALMOND_USER_RSP], rsp
+    mov rsp, [rip + ALMOND_KERNEL_RSP]
+    and rsp, -16
+    push 0x1b
+    push qword ptr [rip + ALMOND_USER_RSP]
+    push r11
+    push 0x23
+    push rcx
+    ALMOND_PUSH_REGS
+    mov rdi, rsp
+    xor esi, esi
+    call almond_syscall_dispatch
+    cli
+    ALMOND_POP_REGS
+    mov rcx, [rsp]
+    mov r11, [rsp + 16]
+    mov rsp, [rsp + 24]
+    sysretq
+
+.global almond_int80_entry
+almond_int80_entry:
+    ALMOND_PUSH_REGS
+    mov rdi, rsp
+    mov esi, 1
+    call almond_syscall_dispatch
+    cli
+    ALMOND_POP_REGS
+    iretq
+"#
+);
+
+/// Point `syscall` At The Entry & Mask Interrupts Until It Has Switched Stacks.
+pub fn initialize() -> KResult<()> {
+    let selectors = gdt::selectors();
+    Star::write(selectors.user_code, selectors.user_data, selectors.kernel_code, selectors.kernel_data)?;
+    LStar::write(VirtAddr::new(almond_syscall_entry as usize as u64));
+    SFMask::write(RFlags::INTERRUPT_FLAG | RFlags::TRAP_FLAG | RFlags::DIRECTION_FLAG);
+    unsafe { Efer::update(|flags| *flags |= EferFlags::SYSTEM_CALL_EXTENSIONS) };
+    Ok(())
+}
+
+/// The Address Of The `int 0x80` Handler, Installed In The IDT With DPL 3.
+pub fn int80_entry() -> VirtAddr {
+    VirtAddr::new(almond_int80_entry as usize as u64)
+}
+
+#[no_mangle]
+extern "C" fn almond_syscall_dispatch(frame: &mut SyscallFrame, entry: Entry) {
+    // System Calls May Block, The Entry Paths Disable Interrupts Again Before Returning.
+    interrupts::enable();
+    let personality = super::with_process(|p| p.personality()).unwrap_or(Personality::Almond);
+    let result = match (personality, entry) {
+        (Personality::Linux, Entry::Interrupt) => {
+            let args = [frame.rbx, frame.rcx, frame.rdx, frame.rsi, frame.rdi, frame.rbp];
+            linux::dispatch_i386(frame.rax as u32, args.map(|arg| arg as u32 as u64))
+        }
+        (Personality::Linux, Entry::Syscall) => {
+            linux::dispatch(frame.rax, [frame.rdi, frame.rsi, frame.rdx, frame.r10, frame.r8, frame.r9])
+        }
+        (Personality::Almond, _) => {
+            dispatch(frame.rax, [frame.rdi, frame.rsi, frame.rdx, frame.r10, frame.r8, frame.r9])
+        }
+    };
+    super::check_killed();
+    frame.rax = match result {
+        Ok(value) => value,
+        Err(errno) => (-errno) as u64,
+    };
+}
+
+/// Run System Call `number` For The Current Process.
+pub fn dispatch(number: u64, args: [u64; 6]) -> Result<u64, i64> {
+    if !super::is_running() {
+        return Err(errno::EPERM);
+    }
+    match number {
+        EXIT => super::exit(args[0] as i64),
+        WRITE => write(args[0] as usize, args[1], args[2]),
+        READ => read(args[0] as usize, args[1], args[2]),
+        OPEN => open(args[0], args[1], args[2]),
+        CLOSE => close(args[0] as usize),
+        BRK => process(|p| Ok(p.brk(args[0]))),
+        MMAP => mmap(args[1], args[2]),
+        YIELD => {
+            thread::yield_now();
+            Ok(0)
+        }
+        GETPID => Ok(process::current().0),
+        GETPPID => parent(),
+        DUP => dup(args[0] as usize),
+        DUP2 => dup2(args[0] as usize, args[1] as usize),
+        PIPE => pipe(args[0]),
+        _ => Err(errno::ENOSYS),
+    }
+}
+
+/// The Parent Of The Current Process.
+pub(super) fn parent() -> Result<u64, i64> {
+    let info = process::info(process::current()).ok_or(errno::EPERM)?;
+    Ok(info.parent.0)
+}
+
+/// Run `f` On The Current Process.
+pub(super) fn process<T>(f: impl FnOnce(&mut Process) -> Result<T, i64>) -> Result<T, i64> {
+    super::with_process(f).unwrap_or(Err(errno::EPERM))
+}
+
+/// Borrow User Memory, The Process's Space Is Active During System Calls.
+pub(super) fn user_slice<'a>(process: &Process, addr: u64, len: u64) -> Result<&'a [u8], i64> {
+    if !process.space().can_access(addr, len, false) {
+        return Err(errno::EFAULT);
+    }
+    Ok(unsafe { core::slice::from_raw_parts(addr as *const u8, len as usize) })
+}
+
+/// Read A Null Terminated String From User Memory, At Most `max` Bytes Long.
+pub(super) fn user_cstr<'a>(process: &Process, addr: u64, max: u64) -> Result<&'a str, i64> {
+    for len in 0..max {
+        if (len == 0 || (addr + len) % 4096 == 0) && !process.space().can_access(addr + len, 1, false) {
+            return Err(errno::EFAULT);
+        }
+        if unsafe { *((addr + len) as *const u8) } == 0 {
+            let bytes = user_slice(process, addr, len)?;
+            return core::str::from_utf8(bytes).map_err(|_| errno::EINVAL);
+        }
+    }
+    Err(errno::ENAMETOOLONG)
+}
+
+/// Borrow Writable User Memory.
+pub(super) fn user_slice_mut<'a>(process: &Process, addr: u64, len: u64) -> Result<&'a mut [u8], i64> {
+    if !process.space().can_access(addr, len, true) {
+        return Err(errno::EFAULT);
+    }
+    Ok(unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, len as usize) })
+}
+
+/// The Descriptor Open On `fd` In The Current Process.
+pub(super) fn descriptor(fd: usize) -> Result<Descriptor, i64> {
+    process(|p| p.files().get(fd).ok_or(errno::EBADF))
+}
+
+// Neither Holds The Process While Reading Or Writing, Both May Block.
+pub(super) fn write(fd: usize, buffer: u64, len: u64) -> Result<u64, i64> {
+    let descriptor = descriptor(fd)?;
+    if len == 0 {
+        return Ok(0);
+    }
+    let data = process(|p| user_slice(p, buffer, len))?;
+    let mut resource = descriptor.lock();
+    if !resource.is_writable() {
+        return Err(errno::EBADF);
+    }
+    resource.write(data).map(|n| n as u64).map_err(|_| errno::EIO)
+}
+
+pub(super) fn read(fd: usize, buffer: u64, len: u64) -> Result<u64, i64> {
+    let descriptor = descriptor(fd)?;
+    if len == 0 {
+        return Ok(0);
+    }
+    let data = process(|p| user_slice_mut(p, buffer, len))?;
+    if !descriptor.lock().is_readable() {
+        return Err(errno::EBADF);
+    }
+    fd::read_descriptor(&descriptor, data).map(|n| n as u64).map_err(|_| errno::EIO)
+}
+
+/// Open An MFS File In The Current Process, See [OPEN_CREATE] & [OPEN_APPEND].
+pub(super) fn open_file(path: &str, flags: u64) -> Result<u64, i64> {
+    let mut file = match mfs::open_file(path) {
+        Some(file) => file,
+        None if flags & OPEN_CREATE != 0 => mfs::create_file(path).ok_or(errno::EIO)?,
+        None => return Err(errno::ENOENT),
+    };
+    if flags & OPEN_APPEND != 0 {
+        let size = file.size() as u32;
+        file.seek(SeekFrom::Start(size)).map_err(|_| errno::EIO)?;
+    }
+    process(|p| p.files_mut().open(Resource::File(file)).map(|fd| fd as u64).map_err(|_| errno::EMFILE))
+}
+
+fn open(path: u64, path_len: u64, flags: u64) -> Result<u64, i64> {
+    let path = process(|p| user_slice(p, path, path_len))?;
+    let path = core::str::from_utf8(path).map_err(|_| errno::EINVAL)?;
+    open_file(path, flags)
+}
+
+pub(super) fn close(fd: usize) -> Result<u64, i64> {
+    let descriptor = process(|p| p.files_mut().close(fd).ok_or(errno::EBADF))?;
+    // Dropped Without The Process Held, Closing A Pipe Takes Its Lock.
+    drop(descriptor);
+    Ok(0)
+}
+
+pub(super) fn dup(fd: usize) -> Result<u64, i64> {
+    process(|p| {
+        p.files().get(fd).ok_or(errno::EBADF)?;
+        p.files_mut().dup(fd).map(|fd| fd as u64).map_err(|_| errno::EMFILE)
+    })
+}
+
+pub(super) fn dup2(old: usize, new: usize) -> Result<u64, i64> {
+    process(|p| {
+        p.files().get(old).ok_or(errno::EBADF)?;
+        p.files_mut().dup2(old, new).map(|fd| fd as u64).map_err(|_| errno::EBADF)
+    })
+}
+
+/// Write The Read & Write Ends Of A New Pipe To `fds` As Two 32 Bit Numbers.
+pub(super) fn pipe(fds: u64) -> Result<u64, i64> {
+    process(|p| {
+        let out = user_slice_mut(p, fds, 8)?;
+        let pipe = Arc::new(Mutex::new(Pipe::default()));
+        let reader = p.files_mut().open(Resource::PipeReader(pipe.clone())).map_err(|_| errno::EMFILE)?;
+        let writer = match p.files_mut().open(Resource::PipeWriter(pipe)) {
+            Ok(writer) => writer,
+            Err(_) => {
+                p.files_mut().close(reader);
+                return Err(errno::EMFILE);
+            }
+        };
+        out[..4].copy_from_slice(&(reader as u32).to_le_bytes());
+        out[4..].copy_from_slice(&(writer as u32).to_le_bytes());
+        Ok(0)
+    })
+}
+
+fn mmap(len: u64, prot: u64) -> Result<u64, i64> {
+    let mut flags = PageTableFlags::empty();
+    if prot & PROT_WRITE != 0 {
+        flags |= PageTableFlags::WRITABLE;
+    }
+    if prot & PROT_EXEC == 0 {
+        flags |= PageTableFlags::NO_EXECUTE;
+    }
+    process(|p| p.mmap(len, flags).map_err(|_| errno::ENOMEM))
+}
@@ -0,0 +1,68 @@
+//! Deferred Work Queue, The Bottom Half Of Interrupt Handlers.
+//! IRQ Handlers [schedule] Work & Return Straight Away, The Kernel Runs It Later
+//! From [run_pending] With Interrupts Enabled.
+//! Work Items Are Plain Function Pointers With A Single Argument, So Queueing
+//! Never Touches The Heap & Is Safe From Any Interrupt Handler.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use super::mem::ringbuffer::RingBuffer;
+use crate::{no_interrupt, KResult};
+
+/// The Maximum Number Of Work Items Waiting To Run.
+pub const QUEUE_SIZE: usize = 256;
+
+/// A Deferred Function, Called With The Argument Given To [schedule].
+pub type WorkFn = fn(usize);
+
+#[derive(Debug, Clone, Copy)]
+struct Work {
+    func: WorkFn,
+    arg: usize,
+}
+
+lazy_static! {
+    static ref QUEUE: Mutex<RingBuffer<Option<Work>, QUEUE_SIZE>> = Mutex::new(RingBuffer::new());
+}
+
+static EXECUTED: AtomicU64 = AtomicU64::new(0);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Queue `func(arg)` To Run Outside Interrupt Context.
+/// Returns Err If The Queue Is Full, The Work Is Dropped.
+pub fn schedule(func: WorkFn, arg: usize) -> KResult<()> {
+    no_interrupt!({
+        let result = QUEUE.lock().write(Some(Work { func, arg }));
+        if result.is_err() {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    })
+}
+
+/// Run Every Queued Work Item, Including Any Queued While Running.
+/// Items Are Taken One At A Time So Interrupts Stay Enabled While They Run.
+pub fn run_pending() {
+    while let Some(work) = no_interrupt!({ QUEUE.lock().read().flatten() }) {
+        (work.func)(work.arg);
+        EXECUTED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The Number Of Work Items Waiting To Run.
+pub fn pending() -> usize {
+    no_interrupt!({ QUEUE.lock().len() })
+}
+
+/// The Number Of Work Items Run Since Boot.
+pub fn executed() -> u64 {
+    EXECUTED.load(Ordering::Relaxed)
+}
+
+/// The Number Of Work Items Dropped Because The Queue Was Full.
+pub fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
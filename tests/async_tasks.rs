@@ -0,0 +1,78 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::task::{Context, Poll};
+
+use almond_os::sys::storage::ata;
+use almond_os::sys::task::{self, WakerCell};
+use almond_os::sys::timer::{self, Duration};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+static FLAG: AtomicBool = AtomicBool::new(false);
+static FLAG_WAKER: WakerCell = WakerCell::new();
+static DONE: AtomicUsize = AtomicUsize::new(0);
+
+/// Resolves Once A Timer Callback Sets [FLAG].
+struct Flag;
+
+impl Future for Flag {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        FLAG_WAKER.register(cx.waker());
+        if FLAG.load(Ordering::SeqCst) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+fn raise_flag(_: usize) {
+    FLAG.store(true, Ordering::SeqCst);
+    FLAG_WAKER.wake();
+}
+
+#[test_case]
+fn block_on_returns_ready_value() {
+    assert_eq!(task::block_on(async { 6 * 7 }), 42);
+}
+
+#[test_case]
+fn spawned_task_is_woken() {
+    FLAG.store(false, Ordering::SeqCst);
+    DONE.store(0, Ordering::SeqCst);
+    task::spawn(async {
+        Flag.await;
+        DONE.fetch_add(1, Ordering::SeqCst);
+    });
+    timer::sleep(Duration::from_millis(10));
+    assert_eq!(DONE.load(Ordering::SeqCst), 0);
+    timer::after(Duration::from_millis(5), raise_flag, 0).unwrap();
+    timer::sleep(Duration::from_millis(50));
+    assert_eq!(DONE.load(Ordering::SeqCst), 1);
+    assert_eq!(task::pending(), 0);
+}
+
+#[test_case]
+fn async_read_matches_polled_read() {
+    let polled = ata::read_block(1, 0).unwrap();
+    let irqs = ata::irq_count(0);
+    let awaited = task::block_on(ata::read_block_async(1, 0)).unwrap();
+    assert_eq!(polled, awaited);
+    assert!(ata::irq_count(0) > irqs);
+}
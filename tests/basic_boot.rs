@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::{print, sys::timer};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn vga_print() {
+    print!("basic_boot: vga_print\n");
+}
+
+#[test_case]
+fn timer_ticks() {
+    let start = timer::ticks();
+    timer::sleep_ticks(10);
+    assert!(timer::ticks() >= start + 10);
+}
+
+#[test_case]
+fn breakpoint_returns() {
+    x86_64::instructions::interrupts::int3();
+}
@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::timer::{self, tsc, Duration, Instant};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn tsc_is_calibrated() {
+    assert!(tsc::frequency() > 100_000_000);
+}
+
+#[test_case]
+fn instant_is_monotonic() {
+    let a = Instant::now();
+    let b = Instant::now();
+    assert!(b >= a);
+    assert_eq!(a - b, Duration::ZERO);
+}
+
+#[test_case]
+fn sleep_waits_at_least_duration() {
+    let start = Instant::now();
+    timer::sleep(Duration::from_millis(20));
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(20));
+    assert!(elapsed < Duration::from_millis(200));
+}
+
+#[test_case]
+fn short_sleep_is_sub_tick() {
+    let start = Instant::now();
+    timer::sleep(Duration::from_micros(50));
+    assert!(start.elapsed() < Duration::from_nanos(timer::TICK_NANOS * 5));
+}
@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::mem::{buffer::{Buffer, DmaConstraints}, frame_allocator};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn buffer_is_filled_and_freed() {
+    let before = frame_allocator::stats();
+    let mut buffer = Buffer::new(0xAAu8, 3 * 4096).unwrap();
+    assert!(buffer.iter().all(|&b| b == 0xAA));
+    assert_eq!(frame_allocator::stats().free, before.free - 3);
+    buffer[4096] = 0x55;
+    assert_eq!(buffer[4096], 0x55);
+    drop(buffer);
+    assert_eq!(frame_allocator::stats().free, before.free);
+}
+
+#[test_case]
+fn bus_master_buffer_meets_constraints() {
+    let buffer = Buffer::with_constraints(0u16, 0x4000, DmaConstraints::BUS_MASTER).unwrap();
+    let start = buffer.phys_addr().as_u64();
+    let end = start + buffer.size() as u64 - 1;
+    assert!(end < 1 << 32);
+    assert_eq!(start >> 16, end >> 16);
+}
+
+#[test_case]
+fn oversized_boundary_is_rejected() {
+    assert!(Buffer::with_constraints(0u8, 0x20000, DmaConstraints::BUS_MASTER).is_err());
+    assert!(Buffer::new(0u8, 0).is_err());
+}
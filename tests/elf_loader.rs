@@ -0,0 +1,267 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use almond_os::sys::fd::{self, STDOUT};
+use almond_os::sys::loader::elf::{self, Elf, ET_EXEC, MACHINE_X86_64, PF_R, PF_W, PF_X, PT_LOAD};
+use almond_os::sys::storage::mfs::{self, api::FileIO, file::File};
+use bootloader::{entry_point, BootInfo};
+use x86_64::registers::model_specific::FsBase;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+const BASE: u64 = 0x4000_0000;
+const HEADERS: u64 = 64 + 56;
+/// The File The `lseek` Tests Open.
+const SEEK_PATH: &str = "/home/linux_seek.txt";
+
+/// A Minimal Executable With One Segment Holding The Headers & `code`.
+fn executable(code: &[u8]) -> Vec<u8> {
+    segment(code, PF_R | PF_X, HEADERS + code.len() as u64)
+}
+
+/// Like [executable], But Writable & A Page Long, So `BASE + 0x800` Onwards Is Free
+/// For Data. It Is Below 4GiB, So i386 Calls Can Reach It.
+fn writable(code: &[u8]) -> Vec<u8> {
+    segment(code, PF_R | PF_W | PF_X, 0x1000)
+}
+
+fn segment(code: &[u8], flags: u32, memsz: u64) -> Vec<u8> {
+    let size = HEADERS + code.len() as u64;
+    let mut data = Vec::new();
+    data.extend_from_slice(&elf::MAGIC);
+    data.extend_from_slice(&[2, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    data.extend_from_slice(&ET_EXEC.to_le_bytes());
+    data.extend_from_slice(&MACHINE_X86_64.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&(BASE + HEADERS).to_le_bytes()); // Entry
+    data.extend_from_slice(&64u64.to_le_bytes()); // Program Headers
+    data.extend_from_slice(&0u64.to_le_bytes()); // Section Headers
+    data.extend_from_slice(&0u32.to_le_bytes());
+    for half in [64u16, 56, 1, 64, 0, 0] {
+        data.extend_from_slice(&half.to_le_bytes());
+    }
+    data.extend_from_slice(&PT_LOAD.to_le_bytes());
+    data.extend_from_slice(&flags.to_le_bytes());
+    for word in [0, BASE, BASE, size, memsz, 0x1000] {
+        data.extend_from_slice(&word.to_le_bytes());
+    }
+    data.extend_from_slice(code);
+    data
+}
+
+#[test_case]
+fn rejects_invalid_files() {
+    assert!(Elf::parse(b"not an elf file").is_err());
+    let mut data = executable(&[]);
+    data.truncate(100);
+    assert!(Elf::parse(&data).is_err());
+}
+
+#[test_case]
+fn runs_executable_with_arguments() {
+    // mov rdi, [rsp] (argc); mov eax, 60 (exit); syscall
+    let data = executable(&[0x48, 0x8b, 0x3c, 0x24, 0xb8, 0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05]);
+    let image = elf::load(&data, &["prog", "a", "b"], &["HOME=/"]).unwrap();
+    assert_eq!(image.run().unwrap(), 3);
+}
+
+#[test_case]
+fn int80_uses_i386_numbers() {
+    // mov ebx, 5; mov eax, 1 (exit); int 0x80
+    let data = executable(&[0xbb, 0x05, 0x00, 0x00, 0x00, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xcd, 0x80]);
+    assert_eq!(elf::load(&data, &["prog"], &[]).unwrap().run().unwrap(), 5);
+}
+
+#[test_case]
+fn runs_bundled_hello_object() {
+    let image = elf::load(include_bytes!("../disk/hello.o"), &["hello"], &[]).unwrap();
+    assert_eq!(image.run().unwrap(), 0);
+}
+
+/// Run `data`, Returns Its Exit Code & Everything It Wrote To Standard Output.
+fn run_captured(data: &[u8]) -> (i64, Vec<u8>) {
+    let (reader, writer) = fd::pipe().unwrap();
+    let saved = fd::dup(STDOUT).unwrap();
+    fd::dup2(writer, STDOUT).unwrap();
+    let result = elf::load(data, &["prog"], &[]).unwrap().run();
+    fd::dup2(saved, STDOUT).unwrap();
+    fd::close(saved).unwrap();
+    fd::close(writer).unwrap();
+
+    let mut output = Vec::new();
+    let mut buffer = [0; 16];
+    loop {
+        match fd::read(reader, &mut buffer).unwrap() {
+            0 => break,
+            count => output.extend_from_slice(&buffer[..count]),
+        }
+    }
+    fd::close(reader).unwrap();
+    (result.unwrap(), output)
+}
+
+/// Run `data` With [SEEK_PATH] Holding "0123456789".
+fn run_with_seek_file(data: &[u8]) -> i64 {
+    let _ = File::delete(SEEK_PATH);
+    mfs::create_file(SEEK_PATH).unwrap().write(b"0123456789").unwrap();
+    let result = elf::load(data, &["prog"], &[]).unwrap().run();
+    File::delete(SEEK_PATH).unwrap();
+    result.unwrap()
+}
+
+#[test_case]
+fn open_and_lseek() {
+    // fd = open(SEEK_PATH, 0); lseek(fd, 0, SEEK_END) == 10; lseek(fd, -7, SEEK_CUR) == 3
+    // read(fd, rsp - 8, 1); exit([rsp - 8]), Exits With 1 If A Check Fails.
+    let code = [
+        0x48, 0x8d, 0x3d, 0x68, 0x00, 0x00, 0x00, 0x31, 0xf6, 0xb8, 0x02, 0x00, 0x00, 0x00, 0x0f, 0x05,
+        0x49, 0x89, 0xc4, 0x4c, 0x89, 0xe7, 0x31, 0xf6, 0xba, 0x02, 0x00, 0x00, 0x00, 0xb8, 0x08, 0x00,
+        0x00, 0x00, 0x0f, 0x05, 0x48, 0x83, 0xf8, 0x0a, 0x75, 0x39, 0x4c, 0x89, 0xe7, 0x48, 0xc7, 0xc6,
+        0xf9, 0xff, 0xff, 0xff, 0xba, 0x01, 0x00, 0x00, 0x00, 0xb8, 0x08, 0x00, 0x00, 0x00, 0x0f, 0x05,
+        0x48, 0x83, 0xf8, 0x03, 0x75, 0x1d, 0x4c, 0x89, 0xe7, 0x48, 0x8d, 0x74, 0x24, 0xf8, 0xba, 0x01,
+        0x00, 0x00, 0x00, 0x31, 0xc0, 0x0f, 0x05, 0x0f, 0xb6, 0x7c, 0x24, 0xf8, 0xb8, 0x3c, 0x00, 0x00,
+        0x00, 0x0f, 0x05, 0xbf, 0x01, 0x00, 0x00, 0x00, 0xb8, 0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x2f,
+        0x68, 0x6f, 0x6d, 0x65, 0x2f, 0x6c, 0x69, 0x6e, 0x75, 0x78, 0x5f, 0x73, 0x65, 0x65, 0x6b, 0x2e,
+        0x74, 0x78, 0x74, 0x00,
+    ];
+    assert_eq!(run_with_seek_file(&executable(&code)), b'3' as i64);
+}
+
+#[test_case]
+fn open_and_lseek_i386() {
+    // The Same Through `int 0x80`, -7 Is Passed As A 32 Bit Offset & The Byte Read Into BASE + 0x800.
+    let code = [
+        0x8d, 0x1d, 0x66, 0x00, 0x00, 0x00, 0x31, 0xc9, 0xb8, 0x05, 0x00, 0x00, 0x00, 0xcd, 0x80, 0x89,
+        0xc6, 0x89, 0xf3, 0x31, 0xc9, 0xba, 0x02, 0x00, 0x00, 0x00, 0xb8, 0x13, 0x00, 0x00, 0x00, 0xcd,
+        0x80, 0x83, 0xf8, 0x0a, 0x75, 0x3a, 0x89, 0xf3, 0xb9, 0xf9, 0xff, 0xff, 0xff, 0xba, 0x01, 0x00,
+        0x00, 0x00, 0xb8, 0x13, 0x00, 0x00, 0x00, 0xcd, 0x80, 0x83, 0xf8, 0x03, 0x75, 0x22, 0x89, 0xf3,
+        0xb9, 0x00, 0x08, 0x00, 0x40, 0xba, 0x01, 0x00, 0x00, 0x00, 0xb8, 0x03, 0x00, 0x00, 0x00, 0xcd,
+        0x80, 0x0f, 0xb6, 0x1c, 0x25, 0x00, 0x08, 0x00, 0x40, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xcd, 0x80,
+        0xbb, 0x01, 0x00, 0x00, 0x00, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xcd, 0x80, 0x2f, 0x68, 0x6f, 0x6d,
+        0x65, 0x2f, 0x6c, 0x69, 0x6e, 0x75, 0x78, 0x5f, 0x73, 0x65, 0x65, 0x6b, 0x2e, 0x74, 0x78, 0x74,
+        0x00,
+    ];
+    assert_eq!(run_with_seek_file(&writable(&code)), b'3' as i64);
+}
+
+#[test_case]
+fn brk_and_mmap() {
+    // end = brk(brk(0) + 4096); [end - 1] = 7; p = mmap(0, 4096, RW, PRIVATE | ANONYMOUS, -1, 0)
+    // [p] = 35; exit([p] + [end - 1]), Exits With 1 If A Check Fails.
+    let code = [
+        0x31, 0xff, 0xb8, 0x0c, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x49, 0x89, 0xc4, 0x48, 0x8d, 0xb8, 0x00,
+        0x10, 0x00, 0x00, 0xb8, 0x0c, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x49, 0x8d, 0x8c, 0x24, 0x00, 0x10,
+        0x00, 0x00, 0x48, 0x39, 0xc8, 0x75, 0x41, 0xc6, 0x40, 0xff, 0x07, 0x49, 0x89, 0xc4, 0x31, 0xff,
+        0xbe, 0x00, 0x10, 0x00, 0x00, 0xba, 0x03, 0x00, 0x00, 0x00, 0x41, 0xba, 0x22, 0x00, 0x00, 0x00,
+        0x49, 0xc7, 0xc0, 0xff, 0xff, 0xff, 0xff, 0x45, 0x31, 0xc9, 0xb8, 0x09, 0x00, 0x00, 0x00, 0x0f,
+        0x05, 0x48, 0x85, 0xc0, 0x78, 0x12, 0xc6, 0x00, 0x23, 0x0f, 0xb6, 0x38, 0x41, 0x02, 0x7c, 0x24,
+        0xff, 0xb8, 0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05, 0xbf, 0x01, 0x00, 0x00, 0x00, 0xb8, 0x3c, 0x00,
+        0x00, 0x00, 0x0f, 0x05,
+    ];
+    assert_eq!(elf::load(&executable(&code), &["prog"], &[]).unwrap().run().unwrap(), 42);
+}
+
+#[test_case]
+fn uname_and_clock_gettime() {
+    // uname(rsp - 400) Starts With 'A'; clock_gettime(CLOCK_MONOTONIC) Has nsec < 1e9;
+    // clock_gettime(CLOCK_REALTIME) Is After 2020 With nsec < 1e9; exit(42), Exits With 1 If A Check Fails.
+    let code = [
+        0x48, 0x81, 0xec, 0x90, 0x01, 0x00, 0x00, 0x48, 0x89, 0xe7, 0xb8, 0x3f, 0x00, 0x00, 0x00, 0x0f,
+        0x05, 0x48, 0x85, 0xc0, 0x75, 0x57, 0x80, 0x3c, 0x24, 0x41, 0x75, 0x51, 0xbf, 0x01, 0x00, 0x00,
+        0x00, 0x48, 0x89, 0xe6, 0xb8, 0xe4, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x48, 0x85, 0xc0, 0x75, 0x3d,
+        0x48, 0x81, 0x7c, 0x24, 0x08, 0x00, 0xca, 0x9a, 0x3b, 0x73, 0x32, 0x31, 0xff, 0x48, 0x89, 0xe6,
+        0xb8, 0xe4, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x48, 0x85, 0xc0, 0x75, 0x21, 0x48, 0x81, 0x3c, 0x24,
+        0x00, 0x10, 0x5e, 0x5f, 0x72, 0x17, 0x48, 0x81, 0x7c, 0x24, 0x08, 0x00, 0xca, 0x9a, 0x3b, 0x73,
+        0x0c, 0xbf, 0x2a, 0x00, 0x00, 0x00, 0xb8, 0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05, 0xbf, 0x01, 0x00,
+        0x00, 0x00, 0xb8, 0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05,
+    ];
+    assert_eq!(elf::load(&executable(&code), &["prog"], &[]).unwrap().run().unwrap(), 42);
+}
+
+#[test_case]
+fn uname_and_clock_gettime_i386() {
+    // uname(BASE + 0x800) Starts With 'A'; clock_gettime(CLOCK_REALTIME, BASE + 0xc00) Writes Two 32 Bit
+    // Fields, After 2020 With nsec < 1e9, & Leaves The Word After Them Alone; exit(42).
+    let code = [
+        0xbb, 0x00, 0x08, 0x00, 0x40, 0xb8, 0x7a, 0x00, 0x00, 0x00, 0xcd, 0x80, 0x85, 0xc0, 0x75, 0x5a,
+        0x80, 0x3c, 0x25, 0x00, 0x08, 0x00, 0x40, 0x41, 0x75, 0x50, 0xc7, 0x04, 0x25, 0x08, 0x0c, 0x00,
+        0x40, 0x55, 0x55, 0x55, 0x55, 0x31, 0xdb, 0xb9, 0x00, 0x0c, 0x00, 0x40, 0xb8, 0x09, 0x01, 0x00,
+        0x00, 0xcd, 0x80, 0x85, 0xc0, 0x75, 0x33, 0x81, 0x3c, 0x25, 0x00, 0x0c, 0x00, 0x40, 0x00, 0x10,
+        0x5e, 0x5f, 0x72, 0x26, 0x81, 0x3c, 0x25, 0x04, 0x0c, 0x00, 0x40, 0x00, 0xca, 0x9a, 0x3b, 0x73,
+        0x19, 0x81, 0x3c, 0x25, 0x08, 0x0c, 0x00, 0x40, 0x55, 0x55, 0x55, 0x55, 0x75, 0x0c, 0xbb, 0x2a,
+        0x00, 0x00, 0x00, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xcd, 0x80, 0xbb, 0x01, 0x00, 0x00, 0x00, 0xb8,
+        0x01, 0x00, 0x00, 0x00, 0xcd, 0x80,
+    ];
+    assert_eq!(elf::load(&writable(&code), &["prog"], &[]).unwrap().run().unwrap(), 42);
+}
+
+#[test_case]
+fn arch_prctl_sets_thread_pointer() {
+    // [rsp - 64] = 42; arch_prctl(ARCH_SET_FS, rsp - 64); arch_prctl(ARCH_GET_FS, rsp - 56) Reads It Back;
+    // set_tid_address(0) > 0; exit(fs:[0]), Exits With 1 If A Check Fails.
+    let code = [
+        0x48, 0xc7, 0x44, 0x24, 0xc0, 0x2a, 0x00, 0x00, 0x00, 0x48, 0x8d, 0x74, 0x24, 0xc0, 0xbf, 0x02,
+        0x10, 0x00, 0x00, 0xb8, 0x9e, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x48, 0x85, 0xc0, 0x75, 0x3b, 0x48,
+        0x8d, 0x74, 0x24, 0xc8, 0xbf, 0x03, 0x10, 0x00, 0x00, 0xb8, 0x9e, 0x00, 0x00, 0x00, 0x0f, 0x05,
+        0x48, 0x8d, 0x4c, 0x24, 0xc0, 0x48, 0x39, 0x4c, 0x24, 0xc8, 0x75, 0x1e, 0x31, 0xff, 0xb8, 0xda,
+        0x00, 0x00, 0x00, 0x0f, 0x05, 0x48, 0x85, 0xc0, 0x7e, 0x10, 0x64, 0x48, 0x8b, 0x3c, 0x25, 0x00,
+        0x00, 0x00, 0x00, 0xb8, 0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05, 0xbf, 0x01, 0x00, 0x00, 0x00, 0xb8,
+        0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05,
+    ];
+    assert_eq!(elf::load(&executable(&code), &["prog"], &[]).unwrap().run().unwrap(), 42);
+    assert_eq!(FsBase::read().as_u64(), 0);
+}
+
+#[test_case]
+fn writev_gathers_buffers() {
+    // writev(1, [("he", 2), ("llo", 3)], 2) With 64 Bit iovecs On The Stack; exit(written)
+    let code = [
+        0x48, 0x8d, 0x05, 0x43, 0x00, 0x00, 0x00, 0x48, 0x89, 0x44, 0x24, 0xe0, 0x48, 0xc7, 0x44, 0x24,
+        0xe8, 0x02, 0x00, 0x00, 0x00, 0x48, 0x8d, 0x05, 0x30, 0x00, 0x00, 0x00, 0x48, 0x89, 0x44, 0x24,
+        0xf0, 0x48, 0xc7, 0x44, 0x24, 0xf8, 0x03, 0x00, 0x00, 0x00, 0xbf, 0x01, 0x00, 0x00, 0x00, 0x48,
+        0x8d, 0x74, 0x24, 0xe0, 0xba, 0x02, 0x00, 0x00, 0x00, 0xb8, 0x14, 0x00, 0x00, 0x00, 0x0f, 0x05,
+        0x48, 0x89, 0xc7, 0xb8, 0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+    ];
+    assert_eq!(run_captured(&executable(&code)), (5, Vec::from(*b"hello")));
+}
+
+#[test_case]
+fn writev_gathers_buffers_i386() {
+    // The Same Through `int 0x80` With 32 Bit iovecs At BASE + 0x800.
+    let code = [
+        0x8d, 0x05, 0x49, 0x00, 0x00, 0x00, 0x89, 0x04, 0x25, 0x00, 0x08, 0x00, 0x40, 0xc7, 0x04, 0x25,
+        0x04, 0x08, 0x00, 0x40, 0x02, 0x00, 0x00, 0x00, 0x8d, 0x05, 0x33, 0x00, 0x00, 0x00, 0x89, 0x04,
+        0x25, 0x08, 0x08, 0x00, 0x40, 0xc7, 0x04, 0x25, 0x0c, 0x08, 0x00, 0x40, 0x03, 0x00, 0x00, 0x00,
+        0xbb, 0x01, 0x00, 0x00, 0x00, 0xb9, 0x00, 0x08, 0x00, 0x40, 0xba, 0x02, 0x00, 0x00, 0x00, 0xb8,
+        0x92, 0x00, 0x00, 0x00, 0xcd, 0x80, 0x89, 0xc3, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xcd, 0x80, 0x68,
+        0x65, 0x6c, 0x6c, 0x6f,
+    ];
+    assert_eq!(run_captured(&writable(&code)), (5, Vec::from(*b"hello")));
+}
+
+#[test_case]
+fn ioctl_reports_terminal_size() {
+    // ioctl(1, TIOCGWINSZ, rsp - 16); exit(ws_col), Exits With 1 If It Fails.
+    let code = [
+        0xbf, 0x01, 0x00, 0x00, 0x00, 0xbe, 0x13, 0x54, 0x00, 0x00, 0x48, 0x8d, 0x54, 0x24, 0xf0, 0xb8,
+        0x10, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x48, 0x85, 0xc0, 0x75, 0x0c, 0x0f, 0xb7, 0x7c, 0x24, 0xf2,
+        0xb8, 0x3c, 0x00, 0x00, 0x00, 0x0f, 0x05, 0xbf, 0x01, 0x00, 0x00, 0x00, 0xb8, 0x3c, 0x00, 0x00,
+        0x00, 0x0f, 0x05,
+    ];
+    assert_eq!(elf::load(&executable(&code), &["prog"], &[]).unwrap().run().unwrap(), 80);
+    // A Pipe Isn't A Terminal.
+    assert_eq!(run_captured(&executable(&code)).0, 1);
+}
@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use almond_os::sys::interrupt::exceptions::{self, FaultAction, FaultReport, Registers};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+static LAST_VECTOR: AtomicU8 = AtomicU8::new(u8::MAX);
+
+fn recording_policy(report: &FaultReport) -> FaultAction {
+    LAST_VECTOR.store(report.vector, Ordering::SeqCst);
+    FaultAction::Resume
+}
+
+#[test_case]
+fn policy_sees_breakpoint() {
+    let previous = exceptions::set_fault_policy(recording_policy);
+    x86_64::instructions::interrupts::int3();
+    exceptions::set_fault_policy(previous);
+    assert_eq!(LAST_VECTOR.load(Ordering::SeqCst), 3);
+}
+
+#[test_case]
+fn default_policy_resumes_traps() {
+    let report = FaultReport {
+        vector: 3,
+        error_code: None,
+        frame: unsafe { core::mem::zeroed() },
+        registers: Registers::default(),
+        cr2: None,
+        guard: None,
+    };
+    assert_eq!(report.mnemonic(), "#BP");
+    assert_eq!(exceptions::default_policy(&report), FaultAction::Resume);
+}
+
+static LAST_RAX: AtomicU64 = AtomicU64::new(0);
+
+fn register_policy(report: &FaultReport) -> FaultAction {
+    LAST_RAX.store(report.registers.rax, Ordering::SeqCst);
+    FaultAction::Resume
+}
+
+#[test_case]
+fn report_has_registers() {
+    let previous = exceptions::set_fault_policy(register_policy);
+    unsafe { core::arch::asm!("int3", in("rax") 0x1234_5678u64) };
+    exceptions::set_fault_policy(previous);
+    assert_eq!(LAST_RAX.load(Ordering::SeqCst), 0x1234_5678);
+}
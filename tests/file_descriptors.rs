@@ -0,0 +1,114 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use almond_os::shell::{self, ShellExitCode};
+use almond_os::sys::fd::{self, STDOUT};
+use almond_os::sys::loader;
+use almond_os::sys::thread;
+use almond_os::sys::timer::Duration;
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn pipe_reads_until_writer_closes() {
+    let (reader, writer) = fd::pipe().unwrap();
+    let copy = fd::dup(writer).unwrap();
+    assert_eq!(fd::write(writer, b"abc").unwrap(), 3);
+    fd::close(writer).unwrap();
+    assert_eq!(fd::write(copy, b"de").unwrap(), 2);
+    fd::close(copy).unwrap();
+
+    let mut buffer = [0; 8];
+    assert_eq!(fd::read(reader, &mut buffer).unwrap(), 5);
+    assert_eq!(&buffer[..5], b"abcde");
+    assert_eq!(fd::read(reader, &mut buffer).unwrap(), 0);
+    fd::close(reader).unwrap();
+    assert!(fd::close(reader).is_err());
+}
+
+#[test_case]
+fn child_inherits_redirected_stdout() {
+    // mov eax, 1 (write); mov edi, 1; lea rsi, [rip + 13]; mov edx, 2; syscall
+    // xor edi, edi; xor eax, eax (exit); syscall; "hi"
+    let code = [
+        0xb8, 0x01, 0x00, 0x00, 0x00, 0xbf, 0x01, 0x00, 0x00, 0x00, 0x48, 0x8d, 0x35, 0x0d, 0x00, 0x00,
+        0x00, 0xba, 0x02, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x31, 0xff, 0x31, 0xc0, 0x0f, 0x05, b'h', b'i',
+    ];
+    let (reader, writer) = fd::pipe().unwrap();
+    let saved = fd::dup(STDOUT).unwrap();
+    fd::dup2(writer, STDOUT).unwrap();
+    let result = loader::load_bytes(&code, &["hi"], &[]).unwrap().run();
+    fd::dup2(saved, STDOUT).unwrap();
+    fd::close(saved).unwrap();
+    fd::close(writer).unwrap();
+    assert_eq!(result, Ok(0));
+
+    // The Child's Copy Was Closed When It Exited, So The Pipe Ends After "hi".
+    let mut buffer = [0; 8];
+    assert_eq!(fd::read(reader, &mut buffer).unwrap(), 2);
+    assert_eq!(&buffer[..2], b"hi");
+    assert_eq!(fd::read(reader, &mut buffer).unwrap(), 0);
+    fd::close(reader).unwrap();
+}
+
+#[test_case]
+fn blocked_read_leaves_descriptor_unlocked() {
+    let (reader, writer) = fd::pipe().unwrap();
+    let handle = thread::spawn("pipe reader", move || {
+        let mut buffer = [0; 8];
+        assert_eq!(fd::read(reader, &mut buffer), Ok(2));
+        assert_eq!(&buffer[..2], b"ok");
+    })
+    .unwrap();
+    thread::sleep(Duration::from_millis(20));
+    // The Reader Is Waiting, Yet Its Descriptor Can Still Be Locked.
+    assert!(fd::get(reader).unwrap().lock().is_readable());
+    assert_eq!(fd::write(writer, b"ok").unwrap(), 2);
+    handle.join().unwrap();
+    fd::close(writer).unwrap();
+    fd::close(reader).unwrap();
+}
+
+/// Everything In The MFS File At `path`.
+fn read_file(path: &str) -> Vec<u8> {
+    let file = fd::open(path, 0).unwrap();
+    let mut data = Vec::new();
+    let mut buffer = [0; 64];
+    loop {
+        match fd::read(file, &mut buffer).unwrap() {
+            0 => break,
+            read => data.extend_from_slice(&buffer[..read]),
+        }
+    }
+    fd::close(file).unwrap();
+    data
+}
+
+#[test_case]
+fn shell_pipes_echo_into_cat() {
+    assert!(matches!(shell::run("echo hi | cat > /home/shell_cat.txt"), ShellExitCode::Ok));
+    assert_eq!(read_file("/home/shell_cat.txt"), b"hi\n");
+}
+
+#[test_case]
+fn shell_redirects_printed_output() {
+    // `date` Prints Rather Than Writing To A Descriptor Itself.
+    assert!(matches!(shell::run("date -u > /home/shell_date.txt"), ShellExitCode::Ok));
+    let data = read_file("/home/shell_date.txt");
+    assert!(data.len() > 1 && data.ends_with(b"\n"));
+    assert!(data[..data.len() - 1].iter().all(u8::is_ascii_digit));
+}
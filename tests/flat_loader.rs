@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::loader::{self, flat};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn rejects_empty_binary() {
+    assert!(flat::load(&[], &["prog"], &[]).is_err());
+}
+
+#[test_case]
+fn runs_flat_binary_with_arguments() {
+    // mov rdi, [rsp] (argc); xor eax, eax (exit); syscall
+    let code = [0x48, 0x8b, 0x3c, 0x24, 0x31, 0xc0, 0x0f, 0x05];
+    let image = loader::load_bytes(&code, &["prog", "a"], &[]).unwrap();
+    assert_eq!(image.entry.as_u64(), flat::FLAT_BASE);
+    assert_eq!(image.run().unwrap(), 2);
+}
@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::mem::frame_allocator;
+use x86_64::{structures::paging::PhysFrame, PhysAddr};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn allocate_and_free_single_frame() {
+    let before = frame_allocator::stats();
+    let frame = frame_allocator::allocate().unwrap();
+    assert!(frame.start_address().as_u64() != 0);
+    assert_eq!(frame_allocator::stats().free, before.free - 1);
+    frame_allocator::free(frame).unwrap();
+    assert_eq!(frame_allocator::stats().free, before.free);
+    assert!(frame_allocator::free(frame).is_err());
+}
+
+#[test_case]
+fn freed_frames_are_reused() {
+    let frame = frame_allocator::allocate().unwrap();
+    frame_allocator::free(frame).unwrap();
+    assert_eq!(frame_allocator::allocate(), Some(frame));
+    frame_allocator::free(frame).unwrap();
+}
+
+#[test_case]
+fn contiguous_run_is_aligned() {
+    let before = frame_allocator::stats();
+    let frame = frame_allocator::allocate_contiguous(16, 16).unwrap();
+    assert_eq!(frame.start_address().as_u64() % (16 * 4096), 0);
+    assert_eq!(frame_allocator::stats().free, before.free - 16);
+    frame_allocator::free_contiguous(frame, 16).unwrap();
+    assert_eq!(frame_allocator::stats().free, before.free);
+}
+
+#[test_case]
+fn stats_are_consistent() {
+    let stats = frame_allocator::stats();
+    assert!(stats.total > 0);
+    assert_eq!(stats.used + stats.free, stats.total);
+}
+
+#[test_case]
+fn reserved_frames_cannot_be_freed() {
+    let before = frame_allocator::stats();
+    // Frame 0 & The VGA Buffer Were Never Handed Out.
+    for address in [0, 0xb8000] {
+        let frame = PhysFrame::containing_address(PhysAddr::new(address));
+        assert!(frame_allocator::free(frame).is_err());
+    }
+    assert_eq!(frame_allocator::stats().free, before.free);
+}
@@ -0,0 +1,93 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, vec::Vec};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn simple_allocation() {
+    let a = Box::new(41);
+    let b = Box::new(13);
+    assert_eq!(*a, 41);
+    assert_eq!(*b, 13);
+}
+
+#[test_case]
+fn large_vec() {
+    let n = 1000;
+    let mut vec = Vec::new();
+    for i in 0..n {
+        vec.push(i);
+    }
+    assert_eq!(vec.iter().sum::<u64>(), (n - 1) * n / 2);
+}
+
+#[test_case]
+fn many_boxes() {
+    for i in 0..10_000 {
+        let x = Box::new(i);
+        assert_eq!(*x, i);
+    }
+}
+
+#[test_case]
+fn heap_grows_past_initial_size() {
+    use almond_os::sys::mem;
+    let before = mem::heap_size();
+    let big = alloc::vec![0xA5u8; 4 * 1024 * 1024];
+    assert_eq!(big[big.len() - 1], 0xA5);
+    assert!(mem::heap_size() > before);
+    assert!(mem::heap_size() <= mem::heap_limit());
+}
+
+#[test_case]
+fn heap_limit_cannot_shrink_below_size() {
+    use almond_os::sys::mem;
+    assert!(mem::set_heap_limit(mem::heap_size() - 1).is_err());
+    assert!(mem::set_heap_limit(mem::heap_limit()).is_ok());
+}
+
+#[test_case]
+fn small_allocations_are_aligned() {
+    use core::alloc::Layout;
+    for size in [1, 7, 8, 24, 100, 512, 2000, 4000] {
+        let layout = Layout::from_size_align(size, size.next_power_of_two().min(4096)).unwrap();
+        unsafe {
+            let a = alloc::alloc::alloc(layout);
+            let b = alloc::alloc::alloc(layout);
+            assert!(!a.is_null() && !b.is_null() && a != b);
+            assert_eq!(a as usize % layout.align(), 0);
+            a.write_bytes(0xAA, size);
+            b.write_bytes(0x55, size);
+            assert_eq!(*a.add(size - 1), 0xAA);
+            alloc::alloc::dealloc(a, layout);
+            alloc::alloc::dealloc(b, layout);
+        }
+    }
+}
+
+#[test_case]
+fn stats_track_allocations() {
+    use almond_os::sys::mem;
+    let before = mem::stats();
+    let block = alloc::vec![0u8; 4096];
+    let during = mem::stats();
+    assert!(during.allocated >= before.allocated + block.len());
+    assert!(during.peak >= during.allocated);
+    assert!(during.total_allocations > before.total_allocations);
+    drop(block);
+    assert!(mem::stats().allocated < during.allocated);
+}
@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::interrupt::{self, IrqResult};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+// Each Handler Bumps Its Own Counter So The Two Can Never Be Merged Into One Function.
+static NOOP_CALLS: AtomicUsize = AtomicUsize::new(0);
+static OTHER_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+fn noop_handler(_: u8) -> IrqResult {
+    NOOP_CALLS.fetch_add(1, Ordering::Relaxed);
+    IrqResult::NotHandled
+}
+
+fn other_handler(_: u8) -> IrqResult {
+    OTHER_CALLS.fetch_add(1, Ordering::Relaxed);
+    IrqResult::NotHandled
+}
+
+#[test_case]
+fn unwired_irq_is_rejected() {
+    assert!(interrupt::set_irq_handler(16, noop_handler).is_err());
+}
+
+#[test_case]
+fn registered_irq_is_rejected() {
+    // IRQ 0 Belongs To The Timer.
+    assert!(interrupt::set_irq_handler(0, noop_handler).is_err());
+}
+
+#[test_case]
+fn free_irq_is_accepted() {
+    // IRQ 5 Is Unused On QEMU's Default Machine.
+    assert!(interrupt::set_irq_handler(5, noop_handler).is_ok());
+    assert!(interrupt::unregister_irq_handler(5, noop_handler).is_ok());
+}
+
+#[test_case]
+fn shared_irq_chain() {
+    assert!(interrupt::register_irq_handler(6, noop_handler).is_ok());
+    assert!(interrupt::register_irq_handler(6, other_handler).is_ok());
+    assert!(interrupt::register_irq_handler(6, noop_handler).is_err());
+    assert_eq!(interrupt::irq_stats(6).unwrap().handlers, 2);
+    assert!(interrupt::unregister_irq_handler(6, noop_handler).is_ok());
+    assert!(interrupt::unregister_irq_handler(6, noop_handler).is_err());
+    assert!(interrupt::unregister_irq_handler(6, other_handler).is_ok());
+    assert_eq!(interrupt::irq_stats(6).unwrap().handlers, 0);
+}
+
+#[test_case]
+fn timer_irq_is_counted() {
+    let before = interrupt::irq_stats(0).unwrap().count;
+    almond_os::sys::timer::sleep_ticks(5);
+    assert!(interrupt::irq_stats(0).unwrap().count > before);
+}
+
+#[test_case]
+fn controller_matches_feature() {
+    use almond_os::sys::timer::{self, TickSource};
+    assert_eq!(interrupt::apic::is_enabled(), cfg!(feature = "apic"));
+    let expected = if cfg!(feature = "apic") { TickSource::LapicTimer } else { TickSource::Pit };
+    assert_eq!(timer::tick_source(), expected);
+}
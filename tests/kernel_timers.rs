@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use almond_os::sys::timer::{self, Duration};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+static FIRED: AtomicUsize = AtomicUsize::new(0);
+
+fn count(value: usize) {
+    FIRED.fetch_add(value, Ordering::SeqCst);
+}
+
+#[test_case]
+fn one_shot_fires_once() {
+    FIRED.store(0, Ordering::SeqCst);
+    timer::after(Duration::from_millis(5), count, 1).unwrap();
+    timer::sleep(Duration::from_millis(30));
+    assert_eq!(FIRED.load(Ordering::SeqCst), 1);
+}
+
+#[test_case]
+fn cancelled_timer_never_fires() {
+    FIRED.store(0, Ordering::SeqCst);
+    let handle = timer::after(Duration::from_millis(10), count, 1).unwrap();
+    assert!(handle.cancel());
+    assert!(!handle.cancel());
+    timer::sleep(Duration::from_millis(30));
+    assert_eq!(FIRED.load(Ordering::SeqCst), 0);
+}
+
+#[test_case]
+fn periodic_timer_repeats() {
+    FIRED.store(0, Ordering::SeqCst);
+    let handle = timer::every(Duration::from_millis(5), count, 1).unwrap();
+    timer::sleep(Duration::from_millis(50));
+    assert!(handle.cancel());
+    assert!(FIRED.load(Ordering::SeqCst) >= 3);
+}
+
+#[test_case]
+fn timers_fire_in_deadline_order() {
+    static ORDER: AtomicUsize = AtomicUsize::new(0);
+    fn record(digit: usize) {
+        let order = ORDER.load(Ordering::SeqCst);
+        ORDER.store(order * 10 + digit, Ordering::SeqCst);
+    }
+    timer::after(Duration::from_millis(15), record, 3).unwrap();
+    timer::after(Duration::from_millis(5), record, 1).unwrap();
+    timer::after(Duration::from_millis(10), record, 2).unwrap();
+    timer::sleep(Duration::from_millis(40));
+    assert_eq!(ORDER.load(Ordering::SeqCst), 123);
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use almond_os::sys::storage::mfs::{self, api::FileIO, dir::Dir, file::File};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn root_has_default_dirs() {
+    let root = mfs::root();
+    assert!(root.find("home").is_some());
+    assert!(root.find("bin").is_some());
+}
+
+#[test_case]
+fn file_round_trip() {
+    let path = "/home/mfs_test.txt";
+    let _ = File::delete(path);
+
+    let mut file = mfs::create_file(path).expect("Failed To Create File");
+    assert_eq!(file.write(b"Hello, MFS!"), Ok(11));
+
+    let mut file = mfs::open_file(path).expect("Failed To Open File");
+    assert_eq!(file.size(), 11);
+    assert_eq!(file.read_to_string(), "Hello, MFS!");
+
+    assert!(File::delete(path).is_ok());
+    assert!(mfs::open_file(path).is_none());
+}
+
+#[test_case]
+fn create_and_open_dir() {
+    let _ = Dir::delete("/home/mfs_test_dir");
+    assert!(mfs::root().find("home").is_some());
+    let home = Dir::open("/home").expect("Failed To Open /home");
+    assert!(home.create_dir("mfs_test_dir").is_some());
+    assert!(Dir::open("/home/mfs_test_dir").is_some());
+    assert!(Dir::delete("/home/mfs_test_dir").is_ok());
+}
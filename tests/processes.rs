@@ -0,0 +1,77 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::loader;
+use almond_os::sys::process::{self, Pid, ProcessState, KERNEL_PID, KILLED_EXIT_CODE};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+fn spawn(code: &[u8], name: &str) -> Pid {
+    process::spawn(loader::load_bytes(code, &[name], &[]).unwrap()).unwrap()
+}
+
+#[test_case]
+fn wait_collects_exit_code() {
+    // mov edi, 3; xor eax, eax (exit); syscall
+    let pid = spawn(&[0xbf, 0x03, 0x00, 0x00, 0x00, 0x31, 0xc0, 0x0f, 0x05], "three");
+    let info = process::info(pid).unwrap();
+    assert_eq!(info.parent, KERNEL_PID);
+    assert_eq!(info.name, "three");
+    assert_eq!(process::wait(pid), Ok(3));
+    assert!(process::info(pid).is_none());
+    assert!(process::wait(pid).is_err());
+}
+
+#[test_case]
+fn getpid_returns_pid() {
+    // mov eax, 8 (getpid); syscall; mov rdi, rax; xor eax, eax (exit); syscall
+    let code = [0xb8, 0x08, 0x00, 0x00, 0x00, 0x0f, 0x05, 0x48, 0x89, 0xc7, 0x31, 0xc0, 0x0f, 0x05];
+    let pid = spawn(&code, "getpid");
+    assert_eq!(process::wait(pid), Ok(pid.0 as i64));
+}
+
+#[test_case]
+fn kill_stops_busy_loop() {
+    // jmp $
+    let pid = spawn(&[0xeb, 0xfe], "spin");
+    process::kill(pid).unwrap();
+    assert_eq!(process::wait(pid), Ok(KILLED_EXIT_CODE));
+    assert!(process::kill(KERNEL_PID).is_err());
+}
+
+#[test_case]
+fn exited_process_is_zombie() {
+    // xor edi, edi; xor eax, eax (exit); syscall
+    let pid = spawn(&[0x31, 0xff, 0x31, 0xc0, 0x0f, 0x05], "zombie");
+    while process::info(pid).unwrap().state == ProcessState::Running {
+        almond_os::sys::thread::yield_now();
+    }
+    assert_eq!(process::info(pid).unwrap().state, ProcessState::Zombie(0));
+    assert_eq!(process::wait(pid), Ok(0));
+}
+
+#[test_case]
+fn unwaited_zombies_free_their_threads() {
+    // More Zombies Than There Are Thread Slots, Collected Only At The End.
+    // xor edi, edi; xor eax, eax (exit); syscall
+    let pids: [Pid; 80] = core::array::from_fn(|_| {
+        let pid = spawn(&[0x31, 0xff, 0x31, 0xc0, 0x0f, 0x05], "zombie");
+        while process::info(pid).unwrap().state == ProcessState::Running {
+            almond_os::sys::thread::yield_now();
+        }
+        pid
+    });
+    for pid in pids {
+        assert_eq!(process::wait(pid), Ok(0));
+    }
+}
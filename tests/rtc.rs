@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::{rtc::{self, DateTime}, timer};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+#[test_case]
+fn unix_round_trip() {
+    let time = DateTime { year: 2024, month: 2, day: 29, hour: 23, minute: 59, second: 58 };
+    assert_eq!(time.unix_timestamp(), 1709251198);
+    assert_eq!(DateTime::from_unix(1709251198), time);
+    assert_eq!(DateTime::from_unix(0).year, 1970);
+}
+
+#[test_case]
+fn realtime_is_after_2020() {
+    // 2020-01-01 00:00:00 UTC.
+    assert!(rtc::realtime() > 1577836800);
+    let now = rtc::read();
+    assert!((1..=12).contains(&now.month));
+    assert!(now.hour < 24);
+}
+
+#[test_case]
+fn periodic_interrupt_fires() {
+    let before = rtc::periodic_ticks();
+    assert_eq!(rtc::enable_periodic(6), Ok(1024));
+    timer::sleep_ticks(50);
+    rtc::disable_periodic();
+    assert!(rtc::periodic_ticks() > before);
+}
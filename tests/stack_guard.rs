@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+use almond_os::sprint;
+use almond_os::sys::interrupt::exceptions::{self, FaultAction, FaultReport};
+use almond_os::sys::qemu::{exit_qemu, QemuExitCode};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    sprint!("stack_guard::boot_stack_overflow_is_reported...\t");
+    exceptions::set_fault_policy(check_overflow);
+
+    stack_overflow();
+
+    panic!("Execution continued after stack overflow");
+}
+
+#[allow(unconditional_recursion)]
+fn stack_overflow() {
+    stack_overflow();
+    volatile::Volatile::new(0).read(); // prevent tail recursion optimizations
+}
+
+fn check_overflow(report: &FaultReport) -> FaultAction {
+    if report.is_stack_overflow() && report.guard.map(|g| g.owner) == Some("kernel") {
+        sprint!("\x1b[32m[ok]\x1b[39m\n");
+        exit_qemu(QemuExitCode::Success);
+    } else {
+        sprint!("\x1b[31m[failed]\x1b[39m\n");
+        exit_qemu(QemuExitCode::Failed);
+    }
+    FaultAction::Halt
+}
@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+#![feature(abi_x86_interrupt)]
+
+use almond_os::sprint;
+use almond_os::sys::interrupt::{gdt, tss};
+use almond_os::sys::qemu::{exit_qemu, QemuExitCode};
+use bootloader::{entry_point, BootInfo};
+use lazy_static::lazy_static;
+use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
+
+entry_point!(main);
+
+fn main(_boot_info: &'static BootInfo) -> ! {
+    sprint!("stack_overflow::stack_overflow...\t");
+
+    unsafe {
+        gdt::reload();
+    }
+    TEST_IDT.load();
+
+    stack_overflow();
+
+    panic!("Execution continued after stack overflow");
+}
+
+#[allow(unconditional_recursion)]
+fn stack_overflow() {
+    stack_overflow();
+    volatile::Volatile::new(0).read(); // prevent tail recursion optimizations
+}
+
+lazy_static! {
+    static ref TEST_IDT: InterruptDescriptorTable = {
+        let mut idt = InterruptDescriptorTable::new();
+        unsafe {
+            idt.double_fault
+                .set_handler_fn(test_double_fault_handler)
+                .set_stack_index(tss::DOUBLE_FAULT_IST_INDEX);
+        }
+        idt
+    };
+}
+
+extern "x86-interrupt" fn test_double_fault_handler(_stack_frame: InterruptStackFrame, _error_code: u64) -> ! {
+    sprint!("\x1b[32m[ok]\x1b[39m\n");
+    exit_qemu(QemuExitCode::Success);
+    almond_os::halt();
+}
@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use almond_os::sys::thread::{self, ThreadState};
+use almond_os::sys::timer::{self, Duration};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[test_case]
+fn spawned_thread_runs_and_joins() {
+    COUNTER.store(0, Ordering::SeqCst);
+    let handle = thread::spawn("worker", || {
+        COUNTER.fetch_add(1, Ordering::SeqCst);
+    })
+    .unwrap();
+    handle.join().unwrap();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
+}
+
+#[test_case]
+fn busy_threads_are_preempted() {
+    COUNTER.store(0, Ordering::SeqCst);
+    // Never Yields, So The Main Thread Only Runs Again Through Preemption.
+    let handle = thread::spawn("spinner", || {
+        let start = timer::ticks();
+        while timer::ticks() < start + 50 {
+            core::hint::spin_loop();
+        }
+        COUNTER.fetch_add(1, Ordering::SeqCst);
+    })
+    .unwrap();
+    thread::yield_now();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 0);
+    handle.join().unwrap();
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
+}
+
+#[test_case]
+fn sleeping_thread_is_listed() {
+    let handle = thread::spawn("sleeper", || thread::sleep(Duration::from_millis(50))).unwrap();
+    thread::yield_now();
+    let id = handle.id();
+    let info = thread::list().into_iter().find(|t| t.id == id).unwrap();
+    assert!(matches!(info.state, ThreadState::Sleeping(_)));
+    handle.join().unwrap();
+    assert!(thread::list().iter().all(|t| t.id != id));
+}
+
+#[test_case]
+fn wake_does_not_cut_sleep_short() {
+    let handle = thread::spawn("sleeper", || {
+        let start = timer::ticks();
+        thread::sleep(Duration::from_millis(50));
+        COUNTER.store((timer::ticks() - start) as usize, Ordering::SeqCst);
+    })
+    .unwrap();
+    thread::yield_now();
+    thread::wake(handle.id());
+    handle.join().unwrap();
+    assert!(COUNTER.load(Ordering::SeqCst) >= 50);
+}
+
+#[test_case]
+fn wake_ends_park() {
+    let handle = thread::spawn("parked", || thread::park(Duration::from_millis(10_000))).unwrap();
+    thread::yield_now();
+    let start = timer::ticks();
+    thread::wake(handle.id());
+    handle.join().unwrap();
+    assert!(timer::ticks() - start < 10_000);
+}
@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::mem::PageTableFlags;
+use almond_os::sys::user::{self, Process, DEFAULT_BREAK, FAULT_EXIT_BASE, USER_STACK_PAGES};
+use bootloader::{entry_point, BootInfo};
+use x86_64::structures::paging::Page;
+use x86_64::VirtAddr;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+const CODE: u64 = 0x4000_0000;
+
+/// Run Raw Machine Code In Ring 3, Returns The Exit Code.
+fn run(code: &[u8]) -> i64 {
+    let mut process = Process::new().unwrap();
+    let page = Page::containing_address(VirtAddr::new(CODE));
+    process.space_mut().map(page, PageTableFlags::empty()).unwrap();
+    process.space_mut().write(VirtAddr::new(CODE), code).unwrap();
+    let stack = process.map_stack(USER_STACK_PAGES).unwrap();
+    user::execute(process, VirtAddr::new(CODE), stack).unwrap()
+}
+
+#[test_case]
+fn syscall_exit_returns_code() {
+    // mov edi, 42; xor eax, eax; syscall
+    assert_eq!(run(&[0xbf, 0x2a, 0x00, 0x00, 0x00, 0x31, 0xc0, 0x0f, 0x05]), 42);
+}
+
+#[test_case]
+fn int80_exit_returns_code() {
+    // mov edi, 7; xor eax, eax; int 0x80
+    assert_eq!(run(&[0xbf, 0x07, 0x00, 0x00, 0x00, 0x31, 0xc0, 0xcd, 0x80]), 7);
+}
+
+#[test_case]
+fn brk_returns_default_break() {
+    // mov eax, 5; xor edi, edi; syscall; mov rdi, rax; xor eax, eax; syscall
+    let code = [
+        0xb8, 0x05, 0x00, 0x00, 0x00, 0x31, 0xff, 0x0f, 0x05, 0x48, 0x89, 0xc7, 0x31, 0xc0, 0x0f, 0x05,
+    ];
+    assert_eq!(run(&code), DEFAULT_BREAK as i64);
+}
+
+#[test_case]
+fn privileged_instruction_kills_program() {
+    // hlt
+    assert_eq!(run(&[0xf4]), FAULT_EXIT_BASE + 13);
+}
+
+#[test_case]
+fn rejects_stack_below_user_region() {
+    let process = Process::new().unwrap();
+    assert!(user::execute(process, VirtAddr::new(CODE), VirtAddr::new(0)).is_err());
+}
@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use almond_os::sys::mem::{self, frame_allocator, guard, mapper, stack::KernelStack, vmm, PageTableFlags};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+const RW: PageTableFlags = PageTableFlags::from_bits_truncate(
+    PageTableFlags::PRESENT.bits() | PageTableFlags::WRITABLE.bits(),
+);
+
+#[test_case]
+fn allocate_write_and_free() {
+    // Warm Up First, The First Mapping Allocates Page Table Frames That Are Never Freed.
+    let warm_up = vmm::allocate(4, RW).unwrap();
+    vmm::free(warm_up, 4).unwrap();
+    let free_pages = vmm::free_pages();
+    let free_frames = frame_allocator::stats().free;
+    let addr = vmm::allocate(4, RW).unwrap();
+    let ptr = addr.as_mut_ptr::<u64>();
+    unsafe {
+        ptr.write_volatile(0xA1_30_4D);
+        ptr.add(1023).write_volatile(42);
+        assert_eq!(ptr.read_volatile(), 0xA1_30_4D);
+    }
+    assert_eq!(vmm::free_pages(), free_pages - 4);
+    vmm::free(addr, 4).unwrap();
+    assert_eq!(vmm::free_pages(), free_pages);
+    assert_eq!(frame_allocator::stats().free, free_frames);
+    assert!(mapper::translate(addr).is_none());
+}
+
+#[test_case]
+fn double_release_is_rejected() {
+    let addr = vmm::reserve(2).unwrap();
+    vmm::release(addr, 2).unwrap();
+    assert!(vmm::release(addr, 2).is_err());
+}
+
+#[test_case]
+fn map_virt_and_unmap_virt() {
+    let frame = frame_allocator::allocate().unwrap();
+    let addr = vmm::reserve(1).unwrap();
+    mem::map_virt(addr, frame.start_address(), RW).unwrap();
+    assert_eq!(mapper::translate(addr), Some(frame.start_address()));
+    assert!(mem::map_virt(addr, frame.start_address(), RW).is_err());
+    mem::protect_virt(addr, PageTableFlags::PRESENT).unwrap();
+    assert_eq!(mem::unmap_virt(addr), Ok(frame.start_address()));
+    assert!(mapper::translate(addr).is_none());
+    vmm::release(addr, 1).unwrap();
+    frame_allocator::free(frame).unwrap();
+}
+
+#[test_case]
+fn kernel_stack_has_guard_page() {
+    let stack = KernelStack::new(4, "test").unwrap();
+    let guard_addr = stack.guard();
+    assert!(mapper::translate(guard_addr).is_none());
+    assert!(mapper::translate(stack.bottom()).is_some());
+    assert!(mapper::translate(stack.top() - 1u64).is_some());
+    let region = guard::lookup(guard_addr).unwrap();
+    assert_eq!(region.owner, "test");
+    drop(stack);
+    assert!(guard::lookup(guard_addr).is_none());
+}
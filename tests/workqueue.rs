@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(almond_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use almond_os::sys::{mem::ringbuffer::RingBuffer, workqueue};
+use bootloader::{entry_point, BootInfo};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    almond_os::boot(boot_info);
+    test_main();
+    almond_os::halt();
+}
+
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+fn add(value: usize) {
+    TOTAL.fetch_add(value, Ordering::SeqCst);
+}
+
+#[test_case]
+fn ring_buffer_fills_and_drains() {
+    let mut buffer: RingBuffer<u8, 4> = RingBuffer::new();
+    assert!(buffer.is_empty());
+    for i in 0..3 {
+        assert!(buffer.write(i).is_ok());
+    }
+    assert!(buffer.is_full());
+    assert!(buffer.write(3).is_err());
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.read(), Some(0));
+    assert_eq!(buffer.read(), Some(1));
+    assert_eq!(buffer.read(), Some(2));
+    assert_eq!(buffer.read(), None);
+}
+
+#[test_case]
+fn scheduled_work_runs() {
+    TOTAL.store(0, Ordering::SeqCst);
+    workqueue::schedule(add, 2).unwrap();
+    workqueue::schedule(add, 40).unwrap();
+    workqueue::run_pending();
+    assert_eq!(TOTAL.load(Ordering::SeqCst), 42);
+    assert_eq!(workqueue::pending(), 0);
+}
+
+#[test_case]
+fn idle_loop_runs_work() {
+    TOTAL.store(0, Ordering::SeqCst);
+    workqueue::schedule(add, 1).unwrap();
+    almond_os::sys::timer::sleep_ticks(2);
+    assert_eq!(TOTAL.load(Ordering::SeqCst), 1);
+}